@@ -0,0 +1,187 @@
+// Operations applied at once to many entries, selected by id or by a search query.
+
+use crate::database;
+use crate::entry::form_from_entry;
+use crate::url_to_sql_query::SqlQuery;
+use crate::validation::{self, ValidationErrors};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkOperation {
+    AddTags(Vec<String>),
+    RemoveTags(Vec<String>),
+    AddThemes(Vec<String>),
+    RemoveThemes(Vec<String>),
+    SetCategory(String),
+    SetExceptional(bool),
+    // Moves the entries to the trash.
+    Delete,
+    // Downloads again the image named in the meta headers of the page at the link of each entry.
+    RefetchImage,
+    // Downloads again the resource at the link of each entry.
+    RefetchBackup,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BulkRequest {
+    // Entries to apply the operation to. Either this or query must be given.
+    pub ids: Option<Vec<i64>>,
+    // Search query with the same syntax as the one of /api/texts. Applies the operation to every entry found, not
+    // only the first page.
+    pub query: Option<String>,
+    pub operation: BulkOperation,
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkStatus {
+    Updated,
+    // The entry already was as the operation would leave it.
+    Unchanged,
+    NotFound,
+    Failed,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct BulkEntryResult {
+    pub id: i64,
+    pub status: BulkStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct BulkResponse {
+    pub results: Vec<BulkEntryResult>,
+}
+
+// Ids of every entry the query finds, in ascending order. Offset and seed are ignored.
+pub fn select_ids(database: &rusqlite::Connection, query: &SqlQuery) -> rusqlite::Result<Vec<i64>> {
+    let sql_query = if query.where_query.is_empty() {
+        String::from("SELECT entry_id FROM entries WHERE date_deleted IS NULL ORDER BY entry_id")
+    } else {
+        format!(
+            "SELECT entry_id FROM entries WHERE date_deleted IS NULL AND ({}) ORDER BY entry_id",
+            query.where_query
+        )
+    };
+    let params = query
+        .params
+        .iter()
+        .map(|x| x as &dyn rusqlite::ToSql)
+        .collect::<Vec<&dyn rusqlite::ToSql>>();
+
+    let mut statement = database.prepare(&sql_query)?;
+    let ids = statement
+        .query_map(params.as_slice(), |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<i64>>>()?;
+    Ok(ids)
+}
+
+// Normalizes the values the operation adds or sets the way entry forms are, and checks them.
+pub fn normalize_operation(operation: &mut BulkOperation) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::default();
+    match operation {
+        BulkOperation::AddTags(tags) | BulkOperation::RemoveTags(tags) => {
+            validation::normalize_list(tags);
+            validation::check_list(&mut errors, "tags", tags);
+        }
+        BulkOperation::AddThemes(themes) | BulkOperation::RemoveThemes(themes) => {
+            validation::normalize_list(themes);
+            validation::check_list(&mut errors, "themes", themes);
+        }
+        BulkOperation::SetCategory(category) => {
+            *category = category.trim().to_owned();
+            validation::check_category(&mut errors, category);
+        }
+        BulkOperation::SetExceptional(_)
+        | BulkOperation::Delete
+        | BulkOperation::RefetchImage
+        | BulkOperation::RefetchBackup => {}
+    }
+    errors.into_result()
+}
+
+fn add_values(list: &mut Vec<String>, values: &[String]) {
+    for value in values {
+        if !list.contains(value) {
+            list.push(value.clone());
+        }
+    }
+}
+
+fn remove_values(list: &mut Vec<String>, values: &[String]) {
+    list.retain(|value| !values.contains(value));
+}
+
+// Applies an operation that only needs the database to the entry. Refetch operations need the resources to be
+// downloaded first, so the caller stores them with database::update_image and database::update_backup instead, and
+// they leave the entry unchanged here. The entry is checked the way an edit of it is, and left as it is if the
+// operation would leave it invalid.
+pub fn apply_operation(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+    operation: &BulkOperation,
+) -> rusqlite::Result<Result<BulkStatus, ValidationErrors>> {
+    let entry = match database::select_entry(database, entry_id)? {
+        Some(entry) => entry,
+        None => return Ok(Ok(BulkStatus::NotFound)),
+    };
+
+    if let BulkOperation::Delete = operation {
+        database::delete_entry(database, entry_id)?;
+        return Ok(Ok(BulkStatus::Updated));
+    }
+
+    let previous = form_from_entry(&entry);
+    let mut form = previous.clone();
+    match operation {
+        BulkOperation::AddTags(tags) => add_values(&mut form.tags, tags),
+        BulkOperation::RemoveTags(tags) => remove_values(&mut form.tags, tags),
+        BulkOperation::AddThemes(themes) => add_values(&mut form.themes, themes),
+        BulkOperation::RemoveThemes(themes) => remove_values(&mut form.themes, themes),
+        BulkOperation::SetCategory(category) => form.category = category.clone(),
+        BulkOperation::SetExceptional(exceptional) => form.exceptional = *exceptional,
+        BulkOperation::Delete | BulkOperation::RefetchImage | BulkOperation::RefetchBackup => {}
+    }
+
+    if form == previous {
+        return Ok(Ok(BulkStatus::Unchanged));
+    }
+    if let Err(errors) = validation::validate_edit(&form, Some(&entry.link)) {
+        return Ok(Err(errors));
+    }
+    database::update_entry(database, entry_id, &form)?;
+    Ok(Ok(BulkStatus::Updated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // add_values, remove_values
+
+    #[test]
+    fn add_values_skips_values_already_in_the_list() {
+        let mut list = vec![String::from("Classic")];
+        add_values(&mut list, &[String::from("Classic"), String::from("Noir")]);
+        assert_eq!(list, ["Classic", "Noir"]);
+    }
+
+    #[test]
+    fn remove_values_removes_every_occurrence() {
+        let mut list = vec![
+            String::from("Classic"),
+            String::from("Noir"),
+            String::from("Classic"),
+        ];
+        remove_values(
+            &mut list,
+            &[String::from("Classic"), String::from("Western")],
+        );
+        assert_eq!(list, ["Noir"]);
+    }
+}
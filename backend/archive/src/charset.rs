@@ -0,0 +1,177 @@
+// Character sets of fetched pages and stored backups, for reading them as text whatever encoding they are in.
+
+use crate::html::{attribute, Token, Tokenizer};
+
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+
+// How far into the content to look for a <meta charset>, as browsers do.
+const PRESCAN_LENGTH: usize = 1024;
+
+// Charset of a Content-Type header or the content of a <meta http-equiv="Content-Type">, like
+// "text/html; charset=ISO-8859-1".
+fn charset_parameter(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(['"', '\'']))
+    })
+}
+
+// Charset declared by a meta tag at the start of an html page.
+fn meta_charset(content: &[u8]) -> Option<&'static Encoding> {
+    let start = String::from_utf8_lossy(&content[..content.len().min(PRESCAN_LENGTH)]);
+    Tokenizer::new(&start).find_map(|token| match token {
+        Token::StartTag { name, attributes } if name == "meta" => {
+            let label = attribute(&attributes, "charset").or_else(|| {
+                attribute(&attributes, "http-equiv")
+                    .filter(|equiv| equiv.eq_ignore_ascii_case("content-type"))
+                    .and(attribute(&attributes, "content"))
+                    .and_then(charset_parameter)
+            })?;
+            // A page that could be read to find the meta tag is not in UTF-16, whatever it says, which the output
+            // encoding takes into account.
+            Encoding::for_label(label.trim().as_bytes()).map(Encoding::output_encoding)
+        }
+        _ => None,
+    })
+}
+
+// Encoding of the content from, in order: its byte order mark, the charset of its content type, the charset that an
+// html page declares for itself, and whether it is valid UTF-8. Anything else is taken to be Windows-1252, which is
+// what old pages that say nothing are written in.
+pub fn detect_encoding(content_type: &str, content: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(content) {
+        return encoding;
+    }
+    if let Some(encoding) =
+        charset_parameter(content_type).and_then(|label| Encoding::for_label(label.as_bytes()))
+    {
+        return encoding;
+    }
+    if content_type.starts_with("text/html") {
+        if let Some(encoding) = meta_charset(content) {
+            return encoding;
+        }
+    }
+    if std::str::from_utf8(content).is_ok() {
+        UTF_8
+    } else {
+        WINDOWS_1252
+    }
+}
+
+// Text of the content, transcoded to UTF-8 from the encoding it was detected to be in.
+pub fn decode_text(content_type: &str, content: &[u8]) -> String {
+    let encoding = detect_encoding(content_type, content);
+    let (text, _, _) = encoding.decode(content);
+    text.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{ISO_2022_JP, SHIFT_JIS, UTF_16LE};
+
+    fn encode(text: &str, encoding: &'static Encoding) -> Vec<u8> {
+        encoding.encode(text).0.into_owned()
+    }
+
+    // detect_encoding
+
+    #[test]
+    fn encoding_of_the_content_type_header() {
+        let content = encode("<title>Crítica</title>", WINDOWS_1252);
+        assert_eq!(
+            detect_encoding("text/html; charset=ISO-8859-1", &content),
+            WINDOWS_1252
+        );
+        assert_eq!(
+            detect_encoding("text/plain;Charset=\"Shift_JIS\"", &content),
+            SHIFT_JIS
+        );
+    }
+
+    #[test]
+    fn encoding_of_meta_tags() {
+        let html = |head: &str| format!("<html><head>{}<title>Crítica</title>", head);
+        assert_eq!(
+            detect_encoding(
+                "text/html",
+                &encode(&html("<meta charset='windows-1252'>"), WINDOWS_1252)
+            ),
+            WINDOWS_1252
+        );
+        assert_eq!(
+            detect_encoding(
+                "text/html",
+                &encode(
+                    &html(
+                        r#"<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=Shift_JIS">"#
+                    ),
+                    SHIFT_JIS
+                )
+            ),
+            SHIFT_JIS
+        );
+        assert_eq!(
+            detect_encoding("text/html", html("<meta charset=utf-16>").as_bytes()),
+            UTF_8
+        );
+        // Only html is searched for meta tags.
+        assert_eq!(
+            detect_encoding(
+                "text/plain",
+                &encode(&html("<meta charset='iso-2022-jp'>"), ISO_2022_JP)
+            ),
+            UTF_8
+        );
+    }
+
+    #[test]
+    fn byte_order_mark_wins_over_everything() {
+        let mut content = vec![0xFF, 0xFE];
+        content.extend(
+            "<meta charset=latin1>"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        assert_eq!(
+            detect_encoding("text/html; charset=latin1", &content),
+            UTF_16LE
+        );
+    }
+
+    #[test]
+    fn undeclared_encoding_is_utf_8_if_valid() {
+        assert_eq!(detect_encoding("text/html", "Crítica".as_bytes()), UTF_8);
+        assert_eq!(
+            detect_encoding("text/html", &encode("Crítica", WINDOWS_1252)),
+            WINDOWS_1252
+        );
+    }
+
+    // decode_text
+
+    #[test]
+    fn text_is_transcoded_to_utf_8() {
+        assert_eq!(
+            decode_text(
+                "text/html; charset=iso-8859-1",
+                b"Cr\xEDtica \x93Bonnie\x94"
+            ),
+            "Crítica “Bonnie”"
+        );
+        assert_eq!(
+            decode_text(
+                "text/html",
+                &encode(
+                    "<meta charset=\"shift_jis\"><title>俺たちに明日はない</title>",
+                    SHIFT_JIS
+                )
+            ),
+            "<meta charset=\"shift_jis\"><title>俺たちに明日はない</title>"
+        );
+        assert_eq!(decode_text("text/plain", b"\xEF\xBB\xBFHello"), "Hello");
+    }
+}
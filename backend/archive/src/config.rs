@@ -0,0 +1,35 @@
+use crate::resolvers::ResolverUrls;
+
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::fs;
+
+#[derive(Deserialize)]
+pub struct ConfigFile {
+    pub database_path: String,
+    // Days deleted entries are kept in the trash before they are purged.
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    // Where DOIs, arXiv ids and ISBNs are looked up. Any of them can be pointed to a local stand-in.
+    #[serde(default)]
+    pub resolvers: ResolverUrls,
+}
+
+fn default_trash_retention_days() -> u32 {
+    30
+}
+
+// The config file is .localhost.json in the home directory of the user.
+pub fn config_file_path() -> Option<OsString> {
+    dirs::home_dir().map(|dir| {
+        let mut osstr = dir.into_os_string();
+        osstr.push("/.localhost.json");
+        osstr
+    })
+}
+
+pub fn load_config_file<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<ConfigFile, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
@@ -0,0 +1,1534 @@
+use crate::date;
+use crate::entry::{form_from_entry, Entry, NewEntryForm};
+use crate::entry_type;
+use crate::history::{self, RevisionAction};
+use crate::links;
+use crate::merge_patch;
+use crate::sites;
+use crate::sql_array::*;
+use crate::taxonomy;
+use crate::url_to_sql_query::SqlQuery;
+use crate::validation::{self, ValidationErrors};
+
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// Opens the database at the given path, creating it and any missing table or column if needed.
+pub fn open<P: AsRef<std::path::Path>>(path: P) -> rusqlite::Result<rusqlite::Connection> {
+    let connection = rusqlite::Connection::open(path)?;
+    create_tables(&connection)?;
+    Ok(connection)
+}
+
+// Creates the tables, or adds what is missing to the ones of older versions. Either all of it is done or nothing, so
+// that a migration interrupted halfway, like a column added but not filled yet, is run again whole.
+pub fn create_tables(connection: &rusqlite::Connection) -> rusqlite::Result<()> {
+    atomically(connection, || create_or_migrate_tables(connection))
+}
+
+fn create_or_migrate_tables(connection: &rusqlite::Connection) -> rusqlite::Result<()> {
+    connection.execute(
+        "CREATE TABLE IF NOT EXISTS entries (
+            entry_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            link TEXT NOT NULL COLLATE NOCASE,
+            title TEXT NOT NULL COLLATE NOCASE,
+            description TEXT NOT NULL COLLATE NOCASE,
+            author TEXT NOT NULL,
+            category TEXT NOT NULL,
+            themes TEXT NOT NULL,
+            works_mentioned TEXT NOT NULL,
+            tags TEXT NOT NULL,
+            date_published DATE NOT NULL,
+            date_saved DATE NOT NULL,
+            exceptional BOOL NOT NULL,
+            entry_type INT NOT NULL,
+            entry_type_metadata INT NOT NULL,
+            image BLOB,
+            backup BLOB,
+            backup_etag TEXT,
+            date_modified TEXT,
+            date_deleted DATE,
+            version INTEGER NOT NULL DEFAULT 1,
+            canonical_link TEXT NOT NULL DEFAULT '',
+            identifier TEXT,
+            site TEXT
+        );",
+        [],
+    )?;
+
+    // Columns added after the table was first created. Databases created by older versions need them added.
+    add_column_if_missing(connection, "entries", "backup_etag", "TEXT")?;
+    if add_column_if_missing(connection, "entries", "date_modified", "TEXT")? {
+        // Older versions overwrote date_saved on every edit, so it is the best guess of when the entry was last
+        // modified.
+        connection.execute("UPDATE entries SET date_modified = date_saved", [])?;
+    }
+    add_column_if_missing(connection, "entries", "date_deleted", "DATE")?;
+    repair_invalid_dates(connection)?;
+    // Older versions only kept the day the entry was modified, which becomes the start of that day in local time.
+    connection.execute(
+        "UPDATE entries
+        SET date_modified = strftime('%Y-%m-%dT%H:%M:%S.000000Z', date_modified, 'utc')
+        WHERE length(date_modified) = 10",
+        [],
+    )?;
+    add_column_if_missing(
+        connection,
+        "entries",
+        "version",
+        "INTEGER NOT NULL DEFAULT 1",
+    )?;
+    if add_column_if_missing(
+        connection,
+        "entries",
+        "canonical_link",
+        "TEXT NOT NULL DEFAULT ''",
+    )? {
+        fill_canonical_links(connection)?;
+    }
+    connection.execute(
+        "CREATE INDEX IF NOT EXISTS entries_by_canonical_link ON entries (canonical_link);",
+        [],
+    )?;
+    add_column_if_missing(connection, "entries", "identifier", "TEXT")?;
+    connection.execute(
+        "CREATE INDEX IF NOT EXISTS entries_by_identifier ON entries (identifier);",
+        [],
+    )?;
+    add_column_if_missing(connection, "entries", "site", "TEXT")?;
+    fill_sites(connection)?;
+    connection.execute(
+        "CREATE INDEX IF NOT EXISTS entries_by_site ON entries (site);",
+        [],
+    )?;
+
+    connection.execute(
+        "CREATE TABLE IF NOT EXISTS entry_revisions (
+            revision_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            entry_id INTEGER NOT NULL,
+            action TEXT NOT NULL,
+            timestamp TEXT NOT NULL,
+            previous TEXT,
+            current TEXT,
+            version INTEGER
+        );",
+        [],
+    )?;
+    connection.execute(
+        "CREATE INDEX IF NOT EXISTS entry_revisions_by_entry ON entry_revisions (entry_id);",
+        [],
+    )?;
+    add_column_if_missing(connection, "entry_revisions", "version", "INTEGER")?;
+    // Older versions recorded revisions in local time to the second, like 2024-05-03T19:02:11+02:00.
+    connection.execute(
+        "UPDATE entry_revisions
+        SET timestamp = strftime('%Y-%m-%dT%H:%M:%S.000000Z', timestamp)
+        WHERE timestamp NOT LIKE '%Z' AND strftime('%s', timestamp) IS NOT NULL",
+        [],
+    )?;
+
+    connection.execute("CREATE TABLE IF NOT EXISTS categories (id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL, value TEXT UNIQUE NOT NULL);", [])?;
+
+    connection.execute(
+        "CREATE TABLE IF NOT EXISTS authors (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            value TEXT UNIQUE NOT NULL,
+            category TEXT NOT NULL
+        );",
+        [],
+    )?;
+    connection.execute(
+        "CREATE TABLE IF NOT EXISTS themes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            value TEXT UNIQUE NOT NULL,
+            category TEXT NOT NULL
+        );",
+        [],
+    )?;
+    connection.execute(
+        "CREATE TABLE IF NOT EXISTS works (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            value TEXT UNIQUE NOT NULL,
+            category TEXT NOT NULL
+        );",
+        [],
+    )?;
+    connection.execute(
+        "CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            value TEXT UNIQUE NOT NULL,
+            category TEXT NOT NULL
+        );",
+        [],
+    )?;
+
+    connection.execute(
+        "CREATE TABLE IF NOT EXISTS sites (
+            domain TEXT PRIMARY KEY NOT NULL,
+            site_name TEXT,
+            favicon BLOB
+        );",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn fill_canonical_links(connection: &rusqlite::Connection) -> rusqlite::Result<()> {
+    let mut statement = connection.prepare("SELECT entry_id, link FROM entries")?;
+    let links = statement
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (entry_id, link) in links {
+        connection.execute(
+            "UPDATE entries SET canonical_link = ? WHERE entry_id = ?",
+            rusqlite::params![links::canonical_link(&link), entry_id],
+        )?;
+    }
+    Ok(())
+}
+
+// Older versions accepted dates that don't exist, like February 31, which can't be read back. Their day is moved to the
+// last day of the month.
+fn repair_invalid_dates(connection: &rusqlite::Connection) -> rusqlite::Result<()> {
+    for column in [
+        "date_published",
+        "date_saved",
+        "date_deleted",
+        "date_modified",
+    ] {
+        let mut statement = connection.prepare(&format!(
+            "SELECT entry_id, {0} FROM entries WHERE length({0}) = 10",
+            column
+        ))?;
+        let dates = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (entry_id, text) in dates {
+            if date::read_sql_date(&text).is_some() {
+                continue;
+            }
+            if let Some(date) = date::read_sql_date_clamped(&text) {
+                connection.execute(
+                    &format!("UPDATE entries SET {} = ? WHERE entry_id = ?", column),
+                    rusqlite::params![date::format_as_sql_date(date), entry_id],
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Entries saved before sites were kept have none, so it is filled from their links. Only entries without a web link
+// are left without one.
+fn fill_sites(connection: &rusqlite::Connection) -> rusqlite::Result<()> {
+    let mut statement =
+        connection.prepare("SELECT entry_id, link FROM entries WHERE site IS NULL")?;
+    let links = statement
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (entry_id, link) in links {
+        connection.execute(
+            "UPDATE entries SET site = ? WHERE entry_id = ?",
+            rusqlite::params![links::canonical_domain(&link), entry_id],
+        )?;
+    }
+    Ok(())
+}
+
+// Returns whether the column was added.
+fn add_column_if_missing(
+    connection: &rusqlite::Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<bool> {
+    let mut statement = connection.prepare(&format!("PRAGMA table_info({})", table))?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        if row.get::<_, String>(1)? == column {
+            return Ok(false);
+        }
+    }
+
+    connection.execute(
+        &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+        [],
+    )?;
+    Ok(true)
+}
+
+// Entries
+
+#[derive(Serialize, ToSchema)]
+pub struct GetTextsResponse {
+    pub entries: Vec<Entry>,
+    pub current_offset: usize,
+    pub next_offset: usize,
+    pub total_size: usize,
+    pub seed: u64,
+}
+
+// Columns and tables of the queries of entries read with read_entry_from_database_row: those of the entry, followed by
+// the name of its site and whether the site has a favicon.
+const ENTRY_COLUMNS: &str = "entries.*, sites.site_name, sites.favicon IS NOT NULL";
+const ENTRIES_WITH_SITES: &str = "entries LEFT JOIN sites ON sites.domain = entries.site";
+
+// Dates are validated before they are written, so one that can't be read is an error of the row rather than a panic,
+// which would take the whole server down with it.
+fn read_date_column(row: &rusqlite::Row<'_>, index: usize) -> rusqlite::Result<date::Date> {
+    let text: String = row.get(index)?;
+    date::read_sql_date(&text).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            index,
+            rusqlite::types::Type::Text,
+            format!("'{}' is not a valid date", text).into(),
+        )
+    })
+}
+
+fn read_entry_from_database_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Entry> {
+    let id: i64 = row.get(0)?;
+    let entry_type_index: i32 = row.get(12)?;
+    let entry_type_metadata: i32 = row.get(13)?;
+
+    Ok(Entry {
+        id,
+        link: row.get(1)?,
+        title: row.get(2)?,
+        description: row.get(3)?,
+        authors: read_from_sql_array(&row.get::<_, String>(4)?),
+        category: row.get(5)?,
+        themes: read_from_sql_array(&row.get::<_, String>(6)?),
+        works_mentioned: read_from_sql_array(&row.get::<_, String>(7)?),
+        tags: read_from_sql_array(&row.get::<_, String>(8)?),
+        date_published: read_date_column(row, 9)?,
+        date_saved: read_date_column(row, 10)?,
+        exceptional: row.get(11)?,
+        entry_type: entry_type::from_index_and_metadata(entry_type_index, entry_type_metadata),
+        image: if row.get_ref(14)?.as_blob_or_null()?.is_some() {
+            Some(format!("/api/texts/{}/image", id))
+        } else {
+            None
+        },
+        backup: if row.get_ref(15)?.as_blob_or_null()?.is_some() {
+            Some(format!("/api/texts/{}/backup", id))
+        } else {
+            None
+        },
+        date_modified: row.get(17)?,
+        date_deleted: match row.get_ref(18)?.as_str_or_null()? {
+            Some(_) => Some(read_date_column(row, 18)?),
+            None => None,
+        },
+        version: row.get(19)?,
+        identifier: row.get(21)?,
+        site: sites::read_site(row.get(22)?, row.get(23)?, row.get(24)?),
+    })
+}
+
+fn get_number_of_texts_in_database(database: &rusqlite::Connection) -> rusqlite::Result<usize> {
+    let sql_query = "SELECT count(*) FROM entries WHERE date_deleted IS NULL";
+    let mut statement = database.prepare(sql_query)?;
+    let mut rows = statement.query([])?;
+
+    if let Some(row) = rows.next()? {
+        row.get(0)
+    } else {
+        Ok(0)
+    }
+}
+
+fn select_random_texts(
+    database: &rusqlite::Connection,
+    offset: usize,
+    seed: u64,
+) -> rusqlite::Result<GetTextsResponse> {
+    let total_size: usize = get_number_of_texts_in_database(database)?;
+
+    let mut rng = SmallRng::seed_from_u64(seed);
+
+    let mut indices: Vec<usize> = (0..total_size).collect();
+    indices.shuffle(&mut rng);
+
+    let indices_to_include = &indices[offset..std::cmp::min(offset + 10, indices.len())];
+
+    let sql_query = format!(
+        "SELECT {} FROM {} WHERE date_deleted IS NULL",
+        ENTRY_COLUMNS, ENTRIES_WITH_SITES
+    );
+    let mut statement = database.prepare(&sql_query)?;
+    let mut rows = statement.query([])?;
+
+    let mut found_entries: Vec<Entry> = Vec::new();
+
+    let mut i: usize = 0;
+    while let Some(row) = rows.next()? {
+        if indices_to_include.contains(&i) {
+            found_entries.push(read_entry_from_database_row(row)?);
+        }
+
+        i += 1;
+    }
+
+    Ok(GetTextsResponse {
+        next_offset: offset + found_entries.len(),
+        entries: found_entries,
+        current_offset: offset,
+        total_size,
+        seed,
+    })
+}
+
+pub fn select_texts(
+    database: &rusqlite::Connection,
+    query: &SqlQuery,
+) -> rusqlite::Result<GetTextsResponse> {
+    let seed = query
+        .seed
+        .unwrap_or_else(|| rand::random::<i32>().unsigned_abs() as u64);
+
+    if query.where_query.is_empty() {
+        return select_random_texts(database, query.offset, seed);
+    }
+
+    let mut found_entries: Vec<Entry> = Vec::new();
+
+    let sql_query = format!(
+        "SELECT {}, count(*) OVER() AS full_count FROM {} WHERE date_deleted IS NULL AND ({}) LIMIT 10 OFFSET {}",
+        ENTRY_COLUMNS, ENTRIES_WITH_SITES, query.where_query, query.offset
+    );
+    let params = query
+        .params
+        .iter()
+        .map(|x| x as &dyn rusqlite::ToSql)
+        .collect::<Vec<&dyn rusqlite::ToSql>>();
+
+    let mut statement = database.prepare(&sql_query)?;
+    let mut rows = statement.query(params.as_slice())?;
+    let mut total_size = 0;
+
+    // Read the size from the first row. We don't need to read it from subsequent rows because it is always the same.
+    if let Some(row) = rows.next()? {
+        found_entries.push(read_entry_from_database_row(row)?);
+        total_size = row.get("full_count")?;
+    }
+
+    while let Some(row) = rows.next()? {
+        found_entries.push(read_entry_from_database_row(row)?);
+    }
+
+    Ok(GetTextsResponse {
+        next_offset: query.offset + found_entries.len(),
+        entries: found_entries,
+        current_offset: query.offset,
+        total_size,
+        seed,
+    })
+}
+
+pub fn select_entry(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+) -> rusqlite::Result<Option<Entry>> {
+    let mut statement = database.prepare(&format!(
+        "SELECT {} FROM {} WHERE entry_id = ? AND date_deleted IS NULL",
+        ENTRY_COLUMNS, ENTRIES_WITH_SITES
+    ))?;
+    let mut rows = statement.query([entry_id])?;
+    match rows.next()? {
+        Some(row) => Ok(Some(read_entry_from_database_row(row)?)),
+        None => Ok(None),
+    }
+}
+
+// Id of an entry outside the trash with the same canonical link, if there is any.
+pub fn select_entry_with_same_link(
+    database: &rusqlite::Connection,
+    link: &str,
+) -> rusqlite::Result<Option<i64>> {
+    let mut statement = database.prepare(
+        "SELECT entry_id FROM entries WHERE canonical_link = ? AND date_deleted IS NULL ORDER BY entry_id LIMIT 1",
+    )?;
+    let mut rows = statement.query([links::canonical_link(link)])?;
+    match rows.next()? {
+        Some(row) => Ok(Some(row.get(0)?)),
+        None => Ok(None),
+    }
+}
+
+// Runs the writes so that either all of them or none are kept. It uses a savepoint instead of a transaction so that it
+// can also be used inside one, like the transaction of a bulk request.
+pub fn atomically<T>(
+    database: &rusqlite::Connection,
+    writes: impl FnOnce() -> rusqlite::Result<T>,
+) -> rusqlite::Result<T> {
+    database.execute_batch("SAVEPOINT atomically")?;
+    match writes() {
+        Ok(result) => {
+            database.execute_batch("RELEASE atomically")?;
+            Ok(result)
+        }
+        Err(err) => {
+            _ = database.execute_batch("ROLLBACK TO atomically; RELEASE atomically");
+            Err(err)
+        }
+    }
+}
+
+// Records the revision of a write to the entry and brings the tables of unique values up to date with it.
+fn record_change(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+    action: RevisionAction,
+    previous: Option<&NewEntryForm>,
+) -> rusqlite::Result<()> {
+    let current = history::snapshot(database, entry_id)?;
+    taxonomy::update(database, previous, current.as_ref())?;
+    history::record_revision(
+        database,
+        entry_id,
+        action,
+        previous,
+        select_version(database, entry_id)?,
+    )
+}
+
+// Returns None if there is no such entry.
+fn select_version(database: &rusqlite::Connection, entry_id: i64) -> rusqlite::Result<Option<i64>> {
+    let mut statement = database.prepare("SELECT version FROM entries WHERE entry_id = ?")?;
+    let mut rows = statement.query([entry_id])?;
+    match rows.next()? {
+        Some(row) => Ok(Some(row.get(0)?)),
+        None => Ok(None),
+    }
+}
+
+// Inserts a new entry and returns its id. Unless the form says otherwise, the entry is saved today.
+pub fn insert_entry(database: &rusqlite::Connection, form: &NewEntryForm) -> rusqlite::Result<i64> {
+    atomically(database, || {
+        let entry_id = insert_entry_row(database, None, form)?;
+        record_change(database, entry_id, RevisionAction::Create, None)?;
+        Ok(entry_id)
+    })
+}
+
+// Inserts the entry with the given id if there is none, or with a new one otherwise.
+fn insert_entry_row(
+    database: &rusqlite::Connection,
+    entry_id: Option<i64>,
+    form: &NewEntryForm,
+) -> rusqlite::Result<i64> {
+    database.execute(
+        "
+        INSERT INTO entries (entry_id, link, title, description, author, category, themes, works_mentioned, tags, date_published, date_saved, exceptional, entry_type, entry_type_metadata, date_modified, canonical_link, identifier, site)
+        VALUES (?15, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?16, NULLIF(?17, ''), ?18);
+        ",
+        rusqlite::params![
+            &form.link,
+            &form.title,
+            &form.description,
+            &format_as_sql_array(&form.authors),
+            &form.category,
+            &format_as_sql_array(&form.themes),
+            &format_as_sql_array(&form.works_mentioned),
+            &format_as_sql_array(&form.tags),
+            &date::format_as_sql_date(form.date_published),
+            &date::format_as_sql_date(form.date_saved.unwrap_or_else(date::today)),
+            form.exceptional,
+            entry_type::index(form.entry_type),
+            entry_type::metadata(form.entry_type),
+            &date::now_timestamp(),
+            entry_id,
+            links::canonical_link(&form.link),
+            &form.identifier,
+            links::canonical_domain(&form.link),
+        ],
+    )?;
+
+    Ok(database.last_insert_rowid())
+}
+
+// Replaces the values of an entry. The date the entry was saved is kept unless the form sets it. Returns the number
+// of entries modified, which is 0 if there is no such entry.
+pub fn update_entry(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+    form: &NewEntryForm,
+) -> rusqlite::Result<usize> {
+    atomically(database, || {
+        let previous = history::snapshot(database, entry_id)?;
+        let modified = update_entry_row(database, entry_id, form)?;
+        if modified > 0 {
+            record_change(
+                database,
+                entry_id,
+                RevisionAction::Update,
+                previous.as_ref(),
+            )?;
+        }
+        Ok(modified)
+    })
+}
+
+fn update_entry_row(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+    form: &NewEntryForm,
+) -> rusqlite::Result<usize> {
+    let modified = database.execute(
+        "
+        UPDATE entries
+        SET link = ?1,
+            title = ?2,
+            description = ?3,
+            author = ?4,
+            category = ?5,
+            themes = ?6,
+            works_mentioned = ?7,
+            tags = ?8,
+            date_published = ?9,
+            date_saved = COALESCE(?10, date_saved),
+            exceptional = ?11,
+            entry_type = ?12,
+            entry_type_metadata = ?13,
+            date_modified = ?14,
+            version = version + 1,
+            canonical_link = ?16,
+            identifier = NULLIF(COALESCE(?17, identifier), ''),
+            site = ?18
+        WHERE entry_id = ?15 AND date_deleted IS NULL;
+        ",
+        rusqlite::params![
+            &form.link,
+            &form.title,
+            &form.description,
+            &format_as_sql_array(&form.authors),
+            &form.category,
+            &format_as_sql_array(&form.themes),
+            &format_as_sql_array(&form.works_mentioned),
+            &format_as_sql_array(&form.tags),
+            &date::format_as_sql_date(form.date_published),
+            form.date_saved.map(date::format_as_sql_date),
+            form.exceptional,
+            entry_type::index(form.entry_type),
+            entry_type::metadata(form.entry_type),
+            &date::now_timestamp(),
+            entry_id,
+            links::canonical_link(&form.link),
+            &form.identifier,
+            links::canonical_domain(&form.link),
+        ],
+    )?;
+
+    Ok(modified)
+}
+
+pub enum PatchResult {
+    Patched(Box<Entry>),
+    NoSuchEntry,
+    // The version of the entry is none of the expected ones.
+    VersionMismatch,
+    Invalid(String),
+    // The patched form doesn't pass validation.
+    Rejected(ValidationErrors),
+}
+
+// Applies a JSON Merge Patch to the values of the entry that can be set through the form. If expected versions are
+// given, the entry is only patched if its version is one of them.
+pub fn patch_entry(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+    patch: &serde_json::Value,
+    expected_versions: Option<&[i64]>,
+) -> rusqlite::Result<PatchResult> {
+    let entry = match select_entry(database, entry_id)? {
+        Some(entry) => entry,
+        None => return Ok(PatchResult::NoSuchEntry),
+    };
+    if let Some(versions) = expected_versions {
+        if !versions.contains(&entry.version) {
+            return Ok(PatchResult::VersionMismatch);
+        }
+    }
+
+    let patch_members = match patch.as_object() {
+        Some(members) => members,
+        None => {
+            return Ok(PatchResult::Invalid(String::from(
+                "The patch must be a JSON object",
+            )))
+        }
+    };
+
+    let mut form = serde_json::to_value(form_from_entry(&entry)).unwrap();
+    for (name, value) in patch_members {
+        if form.get(name).is_none() {
+            return Ok(PatchResult::Invalid(format!("Unknown field '{}'", name)));
+        }
+        // The entry type is an enum with a single member named after the variant, so merging would mix the
+        // metadata of two variants. It is replaced instead.
+        if name == "entry_type" {
+            form[name] = value.clone();
+        }
+    }
+    merge_patch::apply(&mut form, patch);
+    // Merging null removes the identifier from the form, which would keep the one the entry has instead.
+    if patch_members
+        .get("identifier")
+        .is_some_and(|value| value.is_null())
+    {
+        form["identifier"] = serde_json::json!("");
+    }
+
+    let form: NewEntryForm = match serde_json::from_value(form) {
+        Ok(form) => form,
+        Err(err) => return Ok(PatchResult::Invalid(err.to_string())),
+    };
+    let form = match validation::normalize_and_validate_edit(form, Some(&entry.link)) {
+        Ok(form) => form,
+        Err(errors) => return Ok(PatchResult::Rejected(errors)),
+    };
+
+    update_entry(database, entry_id, &form)?;
+    match select_entry(database, entry_id)? {
+        Some(entry) => Ok(PatchResult::Patched(Box::new(entry))),
+        None => Ok(PatchResult::NoSuchEntry),
+    }
+}
+
+// Moves the entry to the trash, where it is hidden from everything but the trash itself until it is restored or
+// purged. Returns the number of entries deleted, which is 0 if there is no such entry.
+pub fn delete_entry(database: &rusqlite::Connection, entry_id: i64) -> rusqlite::Result<usize> {
+    atomically(database, || {
+        let previous = history::snapshot(database, entry_id)?;
+
+        let deleted = database.execute(
+            "
+            UPDATE entries
+            SET date_deleted = ?, version = version + 1
+            WHERE entry_id = ? AND date_deleted IS NULL
+            ",
+            rusqlite::params![date::format_as_sql_date(date::today()), entry_id],
+        )?;
+
+        if deleted > 0 {
+            record_change(
+                database,
+                entry_id,
+                RevisionAction::Delete,
+                previous.as_ref(),
+            )?;
+        }
+        Ok(deleted)
+    })
+}
+
+pub enum RestoreResult {
+    Restored,
+    NoSuchRevision,
+    // The revision deleted the entry, so there are no values to go back to.
+    NothingToRestore,
+}
+
+// Sets the values of the entry back to the ones it had right after the revision. An entry in the trash is taken out
+// of it. An entry that was purged is created again with the same id, but without its image and backup.
+pub fn restore_revision(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+    revision_id: i64,
+) -> rusqlite::Result<RestoreResult> {
+    let mut form = match history::select_revision(database, entry_id, revision_id)? {
+        None => return Ok(RestoreResult::NoSuchRevision),
+        Some(revision) => match revision.current {
+            None => return Ok(RestoreResult::NothingToRestore),
+            Some(form) => form,
+        },
+    };
+    // A form without identifier keeps the one the entry has, but the entry had none after the revision.
+    form.identifier.get_or_insert_with(String::new);
+
+    atomically(database, || {
+        let previous = history::snapshot(database, entry_id)?;
+        untrash_entry_row(database, entry_id)?;
+        if update_entry_row(database, entry_id, &form)? == 0 {
+            // The entry was purged.
+            insert_entry_row(database, Some(entry_id), &form)?;
+            database.execute(
+                "UPDATE entries SET version = ? WHERE entry_id = ?",
+                [history::last_version(database, entry_id)? + 1, entry_id],
+            )?;
+        }
+        record_change(
+            database,
+            entry_id,
+            RevisionAction::Restore,
+            previous.as_ref(),
+        )?;
+        Ok(RestoreResult::Restored)
+    })
+}
+
+// Trash
+
+fn untrash_entry_row(database: &rusqlite::Connection, entry_id: i64) -> rusqlite::Result<usize> {
+    database.execute(
+        "UPDATE entries SET date_deleted = NULL, version = version + 1 WHERE entry_id = ? AND date_deleted IS NOT NULL",
+        [entry_id],
+    )
+}
+
+// Entries in the trash, most recently deleted first.
+pub fn select_trash(database: &rusqlite::Connection) -> rusqlite::Result<Vec<Entry>> {
+    let mut statement = database.prepare(&format!(
+        "SELECT {} FROM {} WHERE date_deleted IS NOT NULL ORDER BY date_deleted DESC, entry_id DESC",
+        ENTRY_COLUMNS, ENTRIES_WITH_SITES
+    ))?;
+    let mut rows = statement.query([])?;
+    let mut entries = Vec::new();
+    while let Some(row) = rows.next()? {
+        entries.push(read_entry_from_database_row(row)?);
+    }
+    Ok(entries)
+}
+
+// Takes the entry out of the trash. Returns the number of entries restored, which is 0 if the entry is not in the
+// trash.
+pub fn restore_from_trash(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+) -> rusqlite::Result<usize> {
+    atomically(database, || {
+        let restored = untrash_entry_row(database, entry_id)?;
+        if restored > 0 {
+            record_change(database, entry_id, RevisionAction::Restore, None)?;
+        }
+        Ok(restored)
+    })
+}
+
+// Removes the entry from the trash and the database, including its image and backup. Its history is kept. Returns
+// the number of entries purged, which is 0 if the entry is not in the trash.
+pub fn purge_entry(database: &rusqlite::Connection, entry_id: i64) -> rusqlite::Result<usize> {
+    atomically(database, || {
+        let version = select_version(database, entry_id)?;
+        let purged = database.execute(
+            "DELETE FROM entries WHERE entry_id = ? AND date_deleted IS NOT NULL",
+            [entry_id],
+        )?;
+        if purged > 0 {
+            history::record_revision(database, entry_id, RevisionAction::Purge, None, version)?;
+        }
+        Ok(purged)
+    })
+}
+
+// Purges the entries that have been in the trash for at least the given number of days. Returns the number of
+// entries purged.
+pub fn purge_expired_trash(
+    database: &rusqlite::Connection,
+    retention_days: u32,
+) -> rusqlite::Result<usize> {
+    let mut statement = database.prepare(
+        "SELECT entry_id FROM entries WHERE date_deleted IS NOT NULL AND date_deleted <= DATE(?, ?)",
+    )?;
+    let expired = statement
+        .query_map(
+            rusqlite::params![
+                date::format_as_sql_date(date::today()),
+                format!("-{} days", retention_days)
+            ],
+            |row| row.get::<_, i64>(0),
+        )?
+        .collect::<rusqlite::Result<Vec<i64>>>()?;
+
+    atomically(database, || {
+        let mut purged = 0;
+        for &entry_id in &expired {
+            purged += purge_entry(database, entry_id)?;
+        }
+        Ok(purged)
+    })
+}
+
+// Images
+
+// Returns None if there is no such entry or it has no image.
+pub fn select_image(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+) -> rusqlite::Result<Option<Vec<u8>>> {
+    let mut statement = database
+        .prepare("SELECT image FROM entries WHERE entry_id = ? AND date_deleted IS NULL")?;
+    let mut rows = statement.query([entry_id])?;
+    match rows.next()? {
+        Some(row) => Ok(row.get_ref(0)?.as_blob_or_null()?.map(Vec::from)),
+        None => Ok(None),
+    }
+}
+
+// Returns the number of entries modified, which is 0 if there is no such entry.
+pub fn update_image(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+    image: &[u8],
+) -> rusqlite::Result<usize> {
+    // The blob is allocated filled with zeros and then written, which must not be left halfway.
+    atomically(database, || {
+        let modified = database.execute(
+            "
+            UPDATE entries
+            SET image = ?, date_modified = ?, version = version + 1
+            WHERE entry_id = ? AND date_deleted IS NULL
+            ",
+            rusqlite::params![
+                rusqlite::blob::ZeroBlob(image.len() as i32),
+                date::now_timestamp(),
+                entry_id
+            ],
+        )?;
+
+        if modified == 0 {
+            return Ok(0);
+        }
+
+        let mut blob =
+            database.blob_open(rusqlite::MAIN_DB, "entries", "image", entry_id, false)?;
+        blob.write_at(image, 0)?;
+
+        Ok(modified)
+    })
+}
+
+// Returns the number of entries modified, which is 0 if there is no such entry.
+pub fn delete_image(database: &rusqlite::Connection, entry_id: i64) -> rusqlite::Result<usize> {
+    database.execute(
+        "
+        UPDATE entries
+        SET image = NULL, date_modified = ?, version = version + 1
+        WHERE entry_id = ? AND date_deleted IS NULL
+        ",
+        rusqlite::params![date::now_timestamp(), entry_id],
+    )
+}
+
+// Backups
+
+// A backup is stored in a blob that starts with the length of the content type string in 1 byte, followed by the
+// content type string and then the actual content.
+pub struct BackupInfo {
+    pub content_type: String,
+    // Position of the content in the blob.
+    pub content_offset: usize,
+    pub content_length: usize,
+    pub etag: Option<String>,
+}
+
+// Returns None if there is no such entry or it has no backup.
+pub fn select_backup_info(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+) -> rusqlite::Result<Option<BackupInfo>> {
+    let found = database.query_row(
+        "SELECT length(backup), backup_etag FROM entries WHERE entry_id = ? AND date_deleted IS NULL",
+        [entry_id],
+        |row| {
+            Ok((
+                row.get::<_, Option<usize>>(0)?,
+                row.get::<_, Option<String>>(1)?,
+            ))
+        },
+    );
+
+    let (blob_length, etag) = match found {
+        Ok((Some(blob_length), etag)) => (blob_length, etag),
+        Ok((None, _)) | Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    let content_type_length = match read_backup_bytes(database, entry_id, 0, 1)?.first() {
+        Some(length) => *length as usize,
+        None => return Err(rusqlite::Error::BlobSizeError),
+    };
+    let content_type = read_backup_bytes(database, entry_id, 1, content_type_length)?;
+    if content_type.len() != content_type_length {
+        return Err(rusqlite::Error::BlobSizeError);
+    }
+
+    let content_offset = content_type_length + 1;
+    Ok(Some(BackupInfo {
+        content_type: String::from_utf8_lossy(&content_type).into_owned(),
+        content_offset,
+        content_length: blob_length - content_offset,
+        etag,
+    }))
+}
+
+// Reads up to `length` bytes of the backup blob starting at `offset`, without loading the rest of the blob.
+pub fn read_backup_bytes(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+    offset: usize,
+    length: usize,
+) -> rusqlite::Result<Vec<u8>> {
+    let blob = database.blob_open(rusqlite::MAIN_DB, "entries", "backup", entry_id, true)?;
+    let mut buffer = vec![0; length];
+    let read = blob.read_at(&mut buffer, offset)?;
+    buffer.truncate(read);
+    Ok(buffer)
+}
+
+// Returns the number of entries modified, which is 0 if there is no such entry.
+pub fn update_backup(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+    content_type: &str,
+    content: &[u8],
+) -> rusqlite::Result<usize> {
+    let content_offset = content_type.len() + 1; // + 1 for storing the size.
+    let blob_length = content_offset + content.len();
+
+    // Like images, the blob is allocated filled with zeros and then written, which must not be left halfway.
+    atomically(database, || {
+        let modified = database.execute(
+            "
+            UPDATE entries
+            SET backup = ?, backup_etag = ?, date_modified = ?, version = version + 1
+            WHERE entry_id = ? AND date_deleted IS NULL
+            ",
+            // Every write gets a new random entity tag, so that clients resuming a download with If-Range
+            // can tell whether the backup changed in between.
+            rusqlite::params![
+                rusqlite::blob::ZeroBlob(blob_length as i32),
+                format!("\"{:016x}\"", rand::random::<u64>()),
+                date::now_timestamp(),
+                entry_id
+            ],
+        )?;
+
+        if modified == 0 {
+            return Ok(0);
+        }
+
+        let mut blob =
+            database.blob_open(rusqlite::MAIN_DB, "entries", "backup", entry_id, false)?;
+
+        // Write the length of the content type string in 1 byte. Content type strings are very short
+        // so 1 byte should always be enough.
+        blob.write_at(&[content_type.len() as u8], 0)?;
+
+        // Write the content type string. This way, when a client requests the backup, we can return it with
+        // the correct content type.
+        blob.write_at(content_type.as_bytes(), 1)?;
+
+        // Write the actual backup data.
+        blob.write_at(content, content_offset)?;
+
+        Ok(modified)
+    })
+}
+
+// Returns the number of entries modified, which is 0 if there is no such entry.
+pub fn delete_backup(database: &rusqlite::Connection, entry_id: i64) -> rusqlite::Result<usize> {
+    database.execute(
+        "
+        UPDATE entries
+        SET backup = NULL, backup_etag = NULL, date_modified = ?, version = version + 1
+        WHERE entry_id = ? AND date_deleted IS NULL
+        ",
+        rusqlite::params![date::now_timestamp(), entry_id],
+    )
+}
+
+// Tables of unique values
+
+pub fn get_all_strings_of(
+    database: &rusqlite::Connection,
+    table: &str,
+) -> rusqlite::Result<Vec<String>> {
+    let mut entries: Vec<String> = Vec::new();
+
+    let mut statement = database.prepare(&format!("SELECT * from {}", table))?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        entries.push(row.get(1)?)
+    }
+
+    Ok(entries)
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct StringWithCategory {
+    pub value: String,
+    pub category: String,
+}
+
+pub fn get_all_strings_by_category_of(
+    database: &rusqlite::Connection,
+    table: &str,
+) -> rusqlite::Result<Vec<StringWithCategory>> {
+    let mut entries: Vec<StringWithCategory> = Vec::new();
+
+    let mut statement = database.prepare(&format!("SELECT * from {}", table))?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        entries.push(StringWithCategory {
+            value: row.get(1)?,
+            category: row.get(2)?,
+        })
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::{Date, Month};
+    use crate::entry_type::EntryType;
+
+    fn open_in_memory() -> rusqlite::Connection {
+        let database = rusqlite::Connection::open_in_memory().unwrap();
+        create_tables(&database).unwrap();
+        database
+    }
+
+    fn new_entry_form(title: &str) -> NewEntryForm {
+        NewEntryForm {
+            link: String::from("https://example.com/bonnie-and-clyde"),
+            title: String::from(title),
+            description: String::new(),
+            authors: vec![String::from("Pauline Kael")],
+            category: String::from("Cinema"),
+            themes: vec![String::from("Criticism")],
+            works_mentioned: Vec::new(),
+            tags: Vec::new(),
+            date_published: Date {
+                day: 3,
+                month: Month::May,
+                year: 1967,
+            },
+            date_saved: None,
+            exceptional: false,
+            entry_type: EntryType::Article { words: 1000 },
+            identifier: None,
+        }
+    }
+
+    // create_tables
+
+    #[test]
+    fn create_tables_is_idempotent() {
+        let database = open_in_memory();
+        assert!(create_tables(&database).is_ok());
+    }
+
+    // insert_entry, select_entry, update_entry, delete_entry
+
+    #[test]
+    fn inserted_entry_can_be_selected() {
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+
+        let entry = select_entry(&database, id).unwrap().unwrap();
+        assert_eq!(entry.id, id);
+        assert_eq!(entry.title, "Bonnie and Clyde");
+        assert_eq!(entry.authors, vec![String::from("Pauline Kael")]);
+        assert_eq!(entry.date_saved, date::today());
+        assert!(entry.image.is_none());
+        assert!(entry.backup.is_none());
+    }
+
+    #[test]
+    fn inserting_an_entry_fills_the_tables_of_unique_values() {
+        let database = open_in_memory();
+        insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+        insert_entry(&database, &new_entry_form("Trash, Art, and the Movies")).unwrap();
+
+        assert_eq!(
+            get_all_strings_of(&database, "categories").unwrap(),
+            vec![String::from("Cinema")]
+        );
+        let authors = get_all_strings_by_category_of(&database, "authors").unwrap();
+        assert_eq!(authors.len(), 1);
+        assert_eq!(authors[0].value, "Pauline Kael");
+        assert_eq!(authors[0].category, "Cinema");
+    }
+
+    #[test]
+    fn select_missing_entry_is_none() {
+        let database = open_in_memory();
+        assert!(select_entry(&database, 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn update_entry_replaces_its_values() {
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+
+        assert_eq!(
+            update_entry(&database, id, &new_entry_form("The Frontier of Movies")).unwrap(),
+            1
+        );
+        let entry = select_entry(&database, id).unwrap().unwrap();
+        assert_eq!(entry.title, "The Frontier of Movies");
+    }
+
+    #[test]
+    fn update_entry_keeps_date_saved_unless_the_form_sets_it() {
+        let database = open_in_memory();
+        let saved = Date {
+            day: 12,
+            month: Month::August,
+            year: 2003,
+        };
+        let mut form = new_entry_form("Bonnie and Clyde");
+        form.date_saved = Some(saved);
+        let id = insert_entry(&database, &form).unwrap();
+
+        update_entry(&database, id, &new_entry_form("Bonnie & Clyde")).unwrap();
+        let entry = select_entry(&database, id).unwrap().unwrap();
+        assert_eq!(entry.date_saved, saved);
+        assert_eq!(
+            date::day_of_timestamp(&entry.date_modified),
+            Some(date::today())
+        );
+
+        let mut form = new_entry_form("Bonnie & Clyde");
+        form.date_saved = Some(date::today());
+        update_entry(&database, id, &form).unwrap();
+        let entry = select_entry(&database, id).unwrap().unwrap();
+        assert_eq!(entry.date_saved, date::today());
+    }
+
+    #[test]
+    fn every_write_moves_date_modified_forward() {
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+        let modified = || select_entry(&database, id).unwrap().unwrap().date_modified;
+        let wait = || std::thread::sleep(std::time::Duration::from_millis(2));
+
+        let created = modified();
+        wait();
+        update_entry(&database, id, &new_entry_form("Bonnie & Clyde")).unwrap();
+        let edited = modified();
+        assert!(edited > created);
+        wait();
+        update_entry(&database, id, &new_entry_form("Bonnie and Clyde")).unwrap();
+        let edited_again = modified();
+        assert!(edited_again > edited);
+        wait();
+        update_image(&database, id, &[1, 2, 3]).unwrap();
+        assert!(modified() > edited_again);
+    }
+
+    #[test]
+    fn days_modified_of_old_databases_become_timestamps() {
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+        database
+            .execute("UPDATE entries SET date_modified = '2003-08-12'", [])
+            .unwrap();
+
+        create_tables(&database).unwrap();
+
+        let modified = select_entry(&database, id).unwrap().unwrap().date_modified;
+        assert_eq!(modified.len(), "2003-08-12T00:00:00.000000Z".len());
+        assert_eq!(
+            date::day_of_timestamp(&modified),
+            Some(Date {
+                day: 12,
+                month: Month::August,
+                year: 2003
+            })
+        );
+    }
+
+    #[test]
+    fn revision_times_of_old_databases_become_utc_timestamps() {
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+        database
+            .execute(
+                "UPDATE entry_revisions SET timestamp = '2024-05-03T19:02:11+02:00'",
+                [],
+            )
+            .unwrap();
+
+        create_tables(&database).unwrap();
+
+        let revisions = history::select_revisions(&database, id).unwrap();
+        assert_eq!(revisions[0].timestamp, "2024-05-03T17:02:11.000000Z");
+    }
+
+    #[test]
+    fn date_modified_is_added_to_old_databases_from_date_saved() {
+        let database = rusqlite::Connection::open_in_memory().unwrap();
+        database
+            .execute(
+                "CREATE TABLE entries (
+                    entry_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+                    link TEXT NOT NULL COLLATE NOCASE,
+                    title TEXT NOT NULL COLLATE NOCASE,
+                    description TEXT NOT NULL COLLATE NOCASE,
+                    author TEXT NOT NULL,
+                    category TEXT NOT NULL,
+                    themes TEXT NOT NULL,
+                    works_mentioned TEXT NOT NULL,
+                    tags TEXT NOT NULL,
+                    date_published DATE NOT NULL,
+                    date_saved DATE NOT NULL,
+                    exceptional BOOL NOT NULL,
+                    entry_type INT NOT NULL,
+                    entry_type_metadata INT NOT NULL,
+                    image BLOB,
+                    backup BLOB
+                );",
+                [],
+            )
+            .unwrap();
+        database
+            .execute(
+                "INSERT INTO entries VALUES (1, 'link', 'Bonnie and Clyde', '', '|Pauline Kael|', 'Cinema', '', '', '',
+                    '1967-05-03', '2003-08-12', FALSE, 0, 1000, NULL, NULL)",
+                [],
+            )
+            .unwrap();
+
+        create_tables(&database).unwrap();
+
+        let entry = select_entry(&database, 1).unwrap().unwrap();
+        assert_eq!(
+            date::day_of_timestamp(&entry.date_modified),
+            Some(entry.date_saved)
+        );
+        assert_eq!(
+            select_entry_with_same_link(&database, "https://www.link/").unwrap(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn sites_are_added_to_old_databases_from_the_links() {
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+        database
+            .execute_batch("DROP INDEX entries_by_site; ALTER TABLE entries DROP COLUMN site;")
+            .unwrap();
+
+        create_tables(&database).unwrap();
+
+        let site = select_entry(&database, id).unwrap().unwrap().site.unwrap();
+        assert_eq!(site.domain, "example.com");
+
+        // Entries left without a site once the column exists are filled all the same.
+        database
+            .execute("UPDATE entries SET site = NULL", [])
+            .unwrap();
+        create_tables(&database).unwrap();
+        let site = select_entry(&database, id).unwrap().unwrap().site.unwrap();
+        assert_eq!(site.domain, "example.com");
+    }
+
+    #[test]
+    fn invalid_dates_of_old_databases_are_moved_to_the_end_of_the_month() {
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+        database
+            .execute(
+                "UPDATE entries SET date_published = '2019-02-31', date_saved = '2019-04-31'",
+                [],
+            )
+            .unwrap();
+
+        // The entry can't be read, but that is an error instead of a panic.
+        assert!(matches!(
+            select_entry(&database, id),
+            Err(rusqlite::Error::FromSqlConversionFailure(9, _, _))
+        ));
+
+        create_tables(&database).unwrap();
+
+        let entry = select_entry(&database, id).unwrap().unwrap();
+        assert_eq!(
+            entry.date_published,
+            Date {
+                day: 28,
+                month: Month::February,
+                year: 2019
+            }
+        );
+        assert_eq!(
+            entry.date_saved,
+            Date {
+                day: 30,
+                month: Month::April,
+                year: 2019
+            }
+        );
+    }
+
+    #[test]
+    fn update_and_delete_of_missing_entry_modify_nothing() {
+        let database = open_in_memory();
+        assert_eq!(
+            update_entry(&database, 1, &new_entry_form("Bonnie and Clyde")).unwrap(),
+            0
+        );
+        assert_eq!(delete_entry(&database, 1).unwrap(), 0);
+    }
+
+    #[test]
+    fn deleted_entry_is_hidden() {
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+
+        assert_eq!(delete_entry(&database, id).unwrap(), 1);
+        assert!(select_entry(&database, id).unwrap().is_none());
+    }
+
+    // delete_entry, purge_expired_trash
+
+    #[test]
+    fn deleted_entry_is_in_the_trash_until_it_expires() {
+        let database = open_in_memory();
+        let old = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+        let recent = insert_entry(&database, &new_entry_form("The Godfather")).unwrap();
+        delete_entry(&database, old).unwrap();
+        delete_entry(&database, recent).unwrap();
+        database
+            .execute(
+                "UPDATE entries SET date_deleted = DATE(date_deleted, '-31 days') WHERE entry_id = ?",
+                [old],
+            )
+            .unwrap();
+
+        assert_eq!(purge_expired_trash(&database, 30).unwrap(), 1);
+        let trash = select_trash(&database).unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, recent);
+
+        assert_eq!(purge_expired_trash(&database, 0).unwrap(), 1);
+        assert!(select_trash(&database).unwrap().is_empty());
+    }
+
+    // select_texts
+
+    #[test]
+    fn select_texts_finds_entries_matching_the_query() {
+        let database = open_in_memory();
+        insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+        insert_entry(&database, &new_entry_form("Trash, Art, and the Movies")).unwrap();
+
+        let query = SqlQuery::with_query_str("title LIKE ?", vec![String::from("%Trash%")]);
+        let response = select_texts(&database, &query).unwrap();
+        assert_eq!(response.total_size, 1);
+        assert_eq!(response.entries.len(), 1);
+        assert_eq!(response.entries[0].title, "Trash, Art, and the Movies");
+    }
+
+    // update_backup, select_backup_info, read_backup_bytes, delete_backup
+
+    #[test]
+    fn backup_content_type_and_content_are_stored_separately() {
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+
+        assert_eq!(
+            update_backup(&database, id, "text/plain", b"Hello").unwrap(),
+            1
+        );
+        let backup = select_backup_info(&database, id).unwrap().unwrap();
+        assert_eq!(backup.content_type, "text/plain");
+        assert_eq!(backup.content_length, 5);
+        assert!(backup.etag.is_some());
+        assert_eq!(
+            read_backup_bytes(&database, id, backup.content_offset, backup.content_length).unwrap(),
+            b"Hello"
+        );
+
+        assert_eq!(delete_backup(&database, id).unwrap(), 1);
+        assert!(select_backup_info(&database, id).unwrap().is_none());
+    }
+
+    #[test]
+    fn every_backup_write_changes_the_etag() {
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+
+        update_backup(&database, id, "text/plain", b"Hello").unwrap();
+        let first = select_backup_info(&database, id).unwrap().unwrap().etag;
+        update_backup(&database, id, "text/plain", b"Hello").unwrap();
+        let second = select_backup_info(&database, id).unwrap().unwrap().etag;
+        assert_ne!(first, second);
+    }
+
+    // update_image, select_image, delete_image
+
+    #[test]
+    fn image_can_be_stored_and_deleted() {
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+
+        assert_eq!(update_image(&database, id, &[1, 2, 3]).unwrap(), 1);
+        assert_eq!(select_image(&database, id).unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(
+            select_entry(&database, id).unwrap().unwrap().image,
+            Some(format!("/api/texts/{}/image", id))
+        );
+
+        assert_eq!(delete_image(&database, id).unwrap(), 1);
+        assert_eq!(select_image(&database, id).unwrap(), None);
+    }
+
+    // atomically
+
+    #[test]
+    fn failed_taxonomy_insert_leaves_no_entry() {
+        let database = open_in_memory();
+        database
+            .execute_batch(
+                "CREATE TRIGGER no_tags BEFORE INSERT ON tags BEGIN SELECT RAISE(ABORT, 'no tags'); END;",
+            )
+            .unwrap();
+        let mut form = new_entry_form("Bonnie and Clyde");
+        form.tags = vec![String::from("Classic")];
+
+        assert!(insert_entry(&database, &form).is_err());
+        let count: i64 = database
+            .query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(get_all_strings_of(&database, "categories")
+            .unwrap()
+            .is_empty());
+        assert!(history::select_revisions(&database, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn atomic_writes_can_be_part_of_a_transaction() {
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Bonnie and Clyde")).unwrap();
+
+        let transaction = database.unchecked_transaction().unwrap();
+        assert_eq!(update_image(&transaction, id, &[1, 2, 3]).unwrap(), 1);
+        assert_eq!(
+            update_backup(&transaction, id, "text/plain", b"Hello").unwrap(),
+            1
+        );
+        drop(transaction);
+
+        assert_eq!(select_image(&database, id).unwrap(), None);
+        assert!(select_backup_info(&database, id).unwrap().is_none());
+        assert_eq!(select_entry(&database, id).unwrap().unwrap().version, 1);
+    }
+}
@@ -0,0 +1,267 @@
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ord, Ordering};
+use utoipa::ToSchema;
+
+#[derive(
+    Serialize, Deserialize, ToSchema, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Default,
+)]
+pub enum Month {
+    #[default]
+    January,
+    February,
+    March,
+    April,
+    May,
+    June,
+    July,
+    August,
+    September,
+    October,
+    November,
+    December,
+}
+
+pub fn month_from_index(index: u32) -> Month {
+    match index {
+        1 => Month::January,
+        2 => Month::February,
+        3 => Month::March,
+        4 => Month::April,
+        5 => Month::May,
+        6 => Month::June,
+        7 => Month::July,
+        8 => Month::August,
+        9 => Month::September,
+        10 => Month::October,
+        11 => Month::November,
+        _ => Month::December,
+    }
+}
+
+pub fn month_to_index(month: Month) -> i32 {
+    match month {
+        Month::January => 1,
+        Month::February => 2,
+        Month::March => 3,
+        Month::April => 4,
+        Month::May => 5,
+        Month::June => 6,
+        Month::July => 7,
+        Month::August => 8,
+        Month::September => 9,
+        Month::October => 10,
+        Month::November => 11,
+        Month::December => 12,
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct Date {
+    pub day: i32,
+    pub month: Month,
+    pub year: i32,
+}
+
+impl Ord for Date {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.year, self.month, self.day).cmp(&(other.year, other.month, other.day))
+    }
+}
+
+impl PartialOrd for Date {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+pub fn today() -> Date {
+    let date = chrono::Local::now().date_naive();
+
+    Date {
+        day: date.day() as i32,
+        month: month_from_index(date.month()),
+        year: date.year(),
+    }
+}
+
+// The current time as stored in date_modified: an RFC 3339 timestamp in UTC with microseconds, like
+// 2024-05-03T17:02:11.123456Z, so that timestamps sort as text in the order they were taken.
+pub fn now_timestamp() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+}
+
+// The day of an RFC 3339 timestamp in local time.
+pub fn day_of_timestamp(timestamp: &str) -> Option<Date> {
+    let date = chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()?
+        .with_timezone(&chrono::Local)
+        .date_naive();
+
+    Some(Date {
+        day: date.day() as i32,
+        month: month_from_index(date.month()),
+        year: date.year(),
+    })
+}
+
+fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(month: Month, year: i32) -> i32 {
+    match month {
+        Month::February if is_leap_year(year) => 29,
+        Month::February => 28,
+        Month::April | Month::June | Month::September | Month::November => 30,
+        _ => 31,
+    }
+}
+
+// Whether the date exists in the calendar and can be stored as an SQL date.
+pub fn is_valid(date: Date) -> bool {
+    (1..=9999).contains(&date.year)
+        && (1..=days_in_month(date.month, date.year)).contains(&date.day)
+}
+
+pub fn format_as_sql_date(date: Date) -> String {
+    format!(
+        "{:04}-{:02}-{:02}",
+        date.year,
+        month_to_index(date.month),
+        date.day
+    )
+}
+
+pub fn read_sql_date(text: &str) -> Option<Date> {
+    let elements: Vec<_> = text.split('-').collect();
+    if elements.len() != 3 {
+        return None;
+    }
+
+    let year = match elements[0].parse::<i32>() {
+        Ok(year) => year,
+        Err(_) => return None,
+    };
+
+    let month = match elements[1].parse::<u32>() {
+        Ok(month) => {
+            if (1..=12).contains(&month) {
+                month_from_index(month)
+            } else {
+                return None;
+            }
+        }
+        Err(_) => return None,
+    };
+
+    let day = match elements[2].parse::<i32>() {
+        Ok(day) => {
+            if (1..=days_in_month(month, year)).contains(&day) {
+                day
+            } else {
+                return None;
+            }
+        }
+        Err(_) => return None,
+    };
+
+    Some(Date { day, month, year })
+}
+
+// Like read_sql_date, but a day past the end of the month becomes its last day, like 2019-02-28 for 2019-02-31. Older
+// versions stored such dates.
+pub fn read_sql_date_clamped(text: &str) -> Option<Date> {
+    let elements: Vec<_> = text.split('-').collect();
+    if elements.len() != 3 {
+        return None;
+    }
+
+    let year = elements[0].parse::<i32>().ok()?;
+    let month = match elements[1].parse::<u32>().ok()? {
+        month @ 1..=12 => month_from_index(month),
+        _ => return None,
+    };
+    let day = elements[2].parse::<i32>().ok().filter(|day| *day >= 1)?;
+
+    Some(Date {
+        day: day.min(days_in_month(month, year)),
+        month,
+        year,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // days_in_month
+
+    #[test]
+    fn february_has_29_days_in_leap_years() {
+        assert_eq!(days_in_month(Month::February, 2024), 29);
+        assert_eq!(days_in_month(Month::February, 2000), 29);
+        assert_eq!(days_in_month(Month::February, 1900), 28);
+        assert_eq!(days_in_month(Month::February, 2023), 28);
+    }
+
+    // now_timestamp, day_of_timestamp
+
+    #[test]
+    fn timestamps_are_of_the_current_day_and_sort_in_order() {
+        let first = now_timestamp();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = now_timestamp();
+        assert!(first < second);
+        assert_eq!(first.len(), "2024-05-03T17:02:11.123456Z".len());
+        assert_eq!(day_of_timestamp(&second), Some(today()));
+        assert_eq!(day_of_timestamp("2024-05-03"), None);
+    }
+
+    // read_sql_date
+
+    #[test]
+    fn read_sql_date_rejects_days_past_the_end_of_the_month() {
+        assert_eq!(
+            read_sql_date("2024-02-29"),
+            Some(Date {
+                day: 29,
+                month: Month::February,
+                year: 2024
+            })
+        );
+        assert_eq!(read_sql_date("2023-02-29"), None);
+        assert_eq!(read_sql_date("2023-02-31"), None);
+        assert_eq!(read_sql_date("2023-04-31"), None);
+        assert_eq!(read_sql_date("2023-13-01"), None);
+    }
+
+    // read_sql_date_clamped
+
+    #[test]
+    fn read_sql_date_clamped_moves_days_past_the_end_of_the_month_to_its_last_day() {
+        assert_eq!(
+            read_sql_date_clamped("2019-02-31"),
+            Some(Date {
+                day: 28,
+                month: Month::February,
+                year: 2019
+            })
+        );
+        assert_eq!(
+            read_sql_date_clamped("2024-04-31"),
+            Some(Date {
+                day: 30,
+                month: Month::April,
+                year: 2024
+            })
+        );
+        assert_eq!(
+            read_sql_date_clamped("2024-05-03"),
+            read_sql_date("2024-05-03")
+        );
+        assert_eq!(read_sql_date_clamped("2019-13-01"), None);
+        assert_eq!(read_sql_date_clamped("2019-02-00"), None);
+        assert_eq!(read_sql_date_clamped("yesterday"), None);
+    }
+}
@@ -0,0 +1,208 @@
+// Finding entries that are probably the same text archived more than once.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateReason {
+    // The entries have the same canonical link.
+    Link,
+    // The titles of the entries are similar.
+    Title,
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone)]
+pub struct DuplicateEntry {
+    pub id: i64,
+    pub title: String,
+    pub link: String,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct DuplicateGroup {
+    pub reason: DuplicateReason,
+    // From 0 to 1. Always 1 for entries with the same link.
+    pub similarity: f64,
+    pub entries: Vec<DuplicateEntry>,
+}
+
+// Pairs of letters and digits of the words of the title, in lowercase.
+fn bigrams(title: &str) -> HashSet<(char, char)> {
+    let mut result = HashSet::new();
+    for word in title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        let chars = word.to_lowercase().chars().collect::<Vec<_>>();
+        for pair in chars.windows(2) {
+            result.insert((pair[0], pair[1]));
+        }
+    }
+    result
+}
+
+// Sørensen–Dice coefficient of two sets of bigrams.
+fn dice_coefficient(a: &HashSet<(char, char)>, b: &HashSet<(char, char)>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 0.0;
+    }
+    2.0 * a.intersection(b).count() as f64 / (a.len() + b.len()) as f64
+}
+
+// Sørensen–Dice coefficient of the bigrams of both titles. 1 for titles with the same words regardless of case and
+// punctuation, 0 for titles with nothing in common.
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    dice_coefficient(&bigrams(a), &bigrams(b))
+}
+
+// An entry that may be a duplicate of another.
+pub struct Candidate {
+    pub entry: DuplicateEntry,
+    pub canonical_link: String,
+}
+
+// Entries outside the trash, which are the ones that are compared. Comparing them takes long with many entries, so
+// it is done apart, once the database is no longer needed.
+pub fn select_candidates(database: &rusqlite::Connection) -> rusqlite::Result<Vec<Candidate>> {
+    let mut statement = database.prepare(
+        "SELECT entry_id, title, link, canonical_link FROM entries WHERE date_deleted IS NULL ORDER BY entry_id",
+    )?;
+    let candidates = statement
+        .query_map([], |row| {
+            Ok(Candidate {
+                entry: DuplicateEntry {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    link: row.get(2)?,
+                },
+                canonical_link: row.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(candidates)
+}
+
+// Groups of candidates with the same canonical link, followed by pairs of candidates in different groups whose titles
+// are at least as similar as min_similarity, most similar first.
+pub fn find_duplicates(candidates: &[Candidate], min_similarity: f64) -> Vec<DuplicateGroup> {
+    let mut by_link: BTreeMap<&str, Vec<DuplicateEntry>> = BTreeMap::new();
+    for candidate in candidates {
+        by_link
+            .entry(candidate.canonical_link.as_str())
+            .or_default()
+            .push(candidate.entry.clone());
+    }
+
+    let mut groups = by_link
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| DuplicateGroup {
+            reason: DuplicateReason::Link,
+            similarity: 1.0,
+            entries: group,
+        })
+        .collect::<Vec<_>>();
+
+    // Every title is compared with every other, so its bigrams are only made once.
+    let title_bigrams = candidates
+        .iter()
+        .map(|candidate| bigrams(&candidate.entry.title))
+        .collect::<Vec<_>>();
+    let mut similar_titles = Vec::new();
+    for (i, a) in candidates.iter().enumerate() {
+        for (j, b) in candidates.iter().enumerate().skip(i + 1) {
+            if a.canonical_link == b.canonical_link {
+                continue;
+            }
+            let similarity = dice_coefficient(&title_bigrams[i], &title_bigrams[j]);
+            if similarity >= min_similarity {
+                similar_titles.push(DuplicateGroup {
+                    reason: DuplicateReason::Title,
+                    similarity,
+                    entries: vec![a.entry.clone(), b.entry.clone()],
+                });
+            }
+        }
+    }
+    similar_titles.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+
+    groups.append(&mut similar_titles);
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // title_similarity
+
+    #[test]
+    fn title_similarity_ignores_case_and_punctuation() {
+        assert_eq!(
+            title_similarity("Bonnie and Clyde", "bonnie, and clyde!"),
+            1.0
+        );
+    }
+
+    #[test]
+    fn title_similarity_of_unrelated_titles_is_low() {
+        assert!(title_similarity("Bonnie and Clyde", "Writing a compiler") < 0.3);
+        assert_eq!(title_similarity("", ""), 0.0);
+    }
+
+    #[test]
+    fn title_similarity_of_close_titles_is_high() {
+        let similarity = title_similarity(
+            "Bonnie and Clyde: the frontier of movies",
+            "Bonnie & Clyde - The frontier of movies",
+        );
+        assert!(similarity > 0.85, "{}", similarity);
+    }
+
+    // find_duplicates
+
+    fn candidate(id: i64, title: &str, canonical_link: &str) -> Candidate {
+        Candidate {
+            entry: DuplicateEntry {
+                id,
+                title: String::from(title),
+                link: format!("https://{}", canonical_link),
+            },
+            canonical_link: String::from(canonical_link),
+        }
+    }
+
+    #[test]
+    fn find_duplicates_groups_links_and_pairs_similar_titles() {
+        let candidates = [
+            candidate(1, "Bonnie and Clyde", "newyorker.com/bonnie-and-clyde"),
+            candidate(2, "Bonnie and Clyde", "newyorker.com/bonnie-and-clyde"),
+            candidate(3, "Bonnie & Clyde", "elpais.com/bonnie-y-clyde"),
+            candidate(4, "Writing a compiler", "example.com/compiler"),
+        ];
+
+        let groups = find_duplicates(&candidates, 0.85)
+            .into_iter()
+            .map(|group| {
+                (
+                    group.reason,
+                    group
+                        .entries
+                        .iter()
+                        .map(|entry| entry.id)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            [
+                (DuplicateReason::Link, vec![1, 2]),
+                (DuplicateReason::Title, vec![1, 3]),
+                (DuplicateReason::Title, vec![2, 3]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use crate::date::Date;
+use crate::entry_type::EntryType;
+use crate::sites::Site;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct NewEntryForm {
+    pub link: String,
+    pub title: String,
+    pub description: String,
+    pub authors: Vec<String>,
+    pub category: String,
+    pub themes: Vec<String>,
+    pub works_mentioned: Vec<String>,
+    pub tags: Vec<String>,
+    pub date_published: Date,
+    // Defaults to the day the entry is created. Editing an entry without it keeps the date it was saved.
+    #[serde(default)]
+    pub date_saved: Option<Date>,
+    pub exceptional: bool,
+    pub entry_type: EntryType,
+    // The DOI, arXiv id or ISBN of the text, like "doi:10.1145/3297858.3304013". Editing an entry without it keeps the
+    // one it has, and an empty one removes it.
+    #[serde(default)]
+    pub identifier: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default)]
+pub struct Entry {
+    pub id: i64,
+    pub link: String,
+    pub title: String,
+    pub description: String,
+    pub authors: Vec<String>,
+    pub category: String,
+    pub themes: Vec<String>,
+    pub works_mentioned: Vec<String>,
+    pub tags: Vec<String>,
+    pub date_published: Date,
+    pub date_saved: Date,
+    pub exceptional: bool,
+    pub entry_type: EntryType,
+    pub image: Option<String>,
+    pub backup: Option<String>,
+    // When the entry, its image or its backup last changed, as an RFC 3339 timestamp in UTC with microseconds, like
+    // 2024-05-03T17:02:11.123456Z.
+    pub date_modified: String,
+    // Set while the entry is in the trash.
+    pub date_deleted: Option<Date>,
+    // Increases with every change to the entry. Served as its ETag.
+    pub version: i64,
+    // The DOI, arXiv id or ISBN of the text, like "doi:10.1145/3297858.3304013".
+    pub identifier: Option<String>,
+    // The site of its link. None if the link is not a web page.
+    pub site: Option<Site>,
+}
+
+// The values of an entry that can be set through the form.
+pub fn form_from_entry(entry: &Entry) -> NewEntryForm {
+    NewEntryForm {
+        link: entry.link.clone(),
+        title: entry.title.clone(),
+        description: entry.description.clone(),
+        authors: entry.authors.clone(),
+        category: entry.category.clone(),
+        themes: entry.themes.clone(),
+        works_mentioned: entry.works_mentioned.clone(),
+        tags: entry.tags.clone(),
+        date_published: entry.date_published,
+        date_saved: Some(entry.date_saved),
+        exceptional: entry.exceptional,
+        entry_type: entry.entry_type,
+        identifier: entry.identifier.clone(),
+    }
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EntryType {
     Article { words: i32 },
     Paper { pages: i32 },
@@ -53,3 +54,18 @@ pub fn from_index_and_metadata(index: i32, metadata: i32) -> EntryType {
         _ => unreachable!(),
     }
 }
+
+// Whether the lengths of both types are counted in the same unit: words, pages or seconds.
+pub fn same_unit(a: EntryType, b: EntryType) -> bool {
+    let unit = |t| match t {
+        EntryType::Article { words: _ } => 0,
+        EntryType::Paper { pages: _ } | EntryType::Book { pages: _ } => 1,
+        EntryType::Video {
+            length_in_seconds: _,
+        }
+        | EntryType::Audio {
+            length_in_seconds: _,
+        } => 2,
+    };
+    unit(a) == unit(b)
+}
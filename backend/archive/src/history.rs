@@ -0,0 +1,205 @@
+// Every create, update, delete and restore of an entry is recorded as a revision with the values the entry had
+// before and after it, so that edits can be reviewed and undone.
+
+use crate::database;
+use crate::date;
+use crate::entry::{form_from_entry, NewEntryForm};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RevisionAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+    Purge,
+}
+
+fn action_to_sql(action: RevisionAction) -> &'static str {
+    match action {
+        RevisionAction::Create => "create",
+        RevisionAction::Update => "update",
+        RevisionAction::Delete => "delete",
+        RevisionAction::Restore => "restore",
+        RevisionAction::Purge => "purge",
+    }
+}
+
+fn action_from_sql(text: &str) -> RevisionAction {
+    match text {
+        "create" => RevisionAction::Create,
+        "delete" => RevisionAction::Delete,
+        "restore" => RevisionAction::Restore,
+        "purge" => RevisionAction::Purge,
+        _ => RevisionAction::Update,
+    }
+}
+
+pub struct Revision {
+    pub id: i64,
+    pub action: RevisionAction,
+    pub timestamp: String,
+    // Values of the entry before the revision. None if it did not exist.
+    pub previous: Option<NewEntryForm>,
+    // Values of the entry after the revision. None if it was deleted.
+    pub current: Option<NewEntryForm>,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct FieldChange {
+    pub field: String,
+    // Null if the entry did not exist before the revision.
+    pub old: serde_json::Value,
+    // Null if the entry was deleted by the revision.
+    pub new: serde_json::Value,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct RevisionChanges {
+    pub revision: i64,
+    pub action: RevisionAction,
+    // RFC 3339 timestamp in UTC with microseconds, like the date_modified of entries.
+    pub timestamp: String,
+    pub changes: Vec<FieldChange>,
+}
+
+// Current values of the entry, or None if there is no such entry.
+pub fn snapshot(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+) -> rusqlite::Result<Option<NewEntryForm>> {
+    Ok(database::select_entry(database, entry_id)?.map(|entry| form_from_entry(&entry)))
+}
+
+// Records a revision of the entry that changed it from the previous values to the current ones. The version is the
+// one the entry has after the revision, or the last one it had if the revision purged it.
+pub fn record_revision(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+    action: RevisionAction,
+    previous: Option<&NewEntryForm>,
+    version: Option<i64>,
+) -> rusqlite::Result<()> {
+    let current = snapshot(database, entry_id)?;
+    database.execute(
+        "INSERT INTO entry_revisions (entry_id, action, timestamp, previous, current, version) VALUES (?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            entry_id,
+            action_to_sql(action),
+            date::now_timestamp(),
+            previous.map(to_json).transpose()?,
+            current.as_ref().map(to_json).transpose()?,
+            version,
+        ],
+    )?;
+    Ok(())
+}
+
+// The highest version the entry has had, so that an entry purged and then restored under the same id continues from
+// it and no entity tag of the entry before it was purged matches again. Revisions recorded by older versions have
+// none, and the number of revisions is the best guess then.
+pub fn last_version(database: &rusqlite::Connection, entry_id: i64) -> rusqlite::Result<i64> {
+    database.query_row(
+        "SELECT max(coalesce(max(version), 0), count(*)) FROM entry_revisions WHERE entry_id = ?",
+        [entry_id],
+        |row| row.get(0),
+    )
+}
+
+fn to_json(form: &NewEntryForm) -> rusqlite::Result<String> {
+    serde_json::to_string(form)
+        .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))
+}
+
+fn from_json(text: Option<String>) -> rusqlite::Result<Option<NewEntryForm>> {
+    text.map(|text| serde_json::from_str(&text))
+        .transpose()
+        .map_err(|err| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(err))
+        })
+}
+
+fn read_revision_from_database_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Revision> {
+    Ok(Revision {
+        id: row.get(0)?,
+        action: action_from_sql(&row.get::<_, String>(1)?),
+        timestamp: row.get(2)?,
+        previous: from_json(row.get(3)?)?,
+        current: from_json(row.get(4)?)?,
+    })
+}
+
+// Revisions of the entry, oldest first.
+pub fn select_revisions(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+) -> rusqlite::Result<Vec<Revision>> {
+    let mut statement = database.prepare(
+        "SELECT revision_id, action, timestamp, previous, current FROM entry_revisions WHERE entry_id = ? ORDER BY revision_id",
+    )?;
+    let mut rows = statement.query([entry_id])?;
+    let mut revisions = Vec::new();
+    while let Some(row) = rows.next()? {
+        revisions.push(read_revision_from_database_row(row)?);
+    }
+    Ok(revisions)
+}
+
+pub fn select_revision(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+    revision_id: i64,
+) -> rusqlite::Result<Option<Revision>> {
+    let mut statement = database.prepare(
+        "SELECT revision_id, action, timestamp, previous, current FROM entry_revisions WHERE entry_id = ? AND revision_id = ?",
+    )?;
+    let mut rows = statement.query([entry_id, revision_id])?;
+    match rows.next()? {
+        Some(row) => Ok(Some(read_revision_from_database_row(row)?)),
+        None => Ok(None),
+    }
+}
+
+// The fields that changed in the revision, sorted by name.
+pub fn changes_of(revision: &Revision) -> Vec<FieldChange> {
+    let previous = serde_json::to_value(&revision.previous).unwrap();
+    let current = serde_json::to_value(&revision.current).unwrap();
+
+    let field_names = match (&previous, &current) {
+        (_, serde_json::Value::Object(fields)) | (serde_json::Value::Object(fields), _) => {
+            fields.keys().cloned().collect::<Vec<_>>()
+        }
+        _ => Vec::new(),
+    };
+
+    field_names
+        .into_iter()
+        .filter_map(|field| {
+            let old = previous.get(&field).cloned().unwrap_or_default();
+            let new = current.get(&field).cloned().unwrap_or_default();
+            if old == new {
+                None
+            } else {
+                Some(FieldChange { field, old, new })
+            }
+        })
+        .collect()
+}
+
+pub fn select_history(
+    database: &rusqlite::Connection,
+    entry_id: i64,
+) -> rusqlite::Result<Vec<RevisionChanges>> {
+    Ok(select_revisions(database, entry_id)?
+        .iter()
+        .map(|revision| RevisionChanges {
+            revision: revision.id,
+            action: revision.action,
+            timestamp: revision.timestamp.clone(),
+            changes: changes_of(revision),
+        })
+        .collect())
+}
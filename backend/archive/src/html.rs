@@ -0,0 +1,450 @@
+// A tolerant tokenizer of HTML, good enough for reading the metadata of pages. It never fails: what isn't a tag,
+// comment or doctype is read as text, and unterminated constructs end with the source.
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Token {
+    // Names of tags and attributes are in lowercase, and the values of attributes are decoded. When an attribute is
+    // repeated, the first one is kept.
+    StartTag {
+        name: String,
+        attributes: Vec<(String, String)>,
+    },
+    EndTag {
+        name: String,
+    },
+    Text(String),
+}
+
+// Value of the attribute with the name, which must be in lowercase.
+pub fn attribute<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(attribute, _)| attribute == name)
+        .map(|(_, value)| value.as_str())
+}
+
+// Elements whose content is text up to their end tag, even if it looks like markup. The content of the ones that are
+// not scripts or styles can still have character references.
+const RAW_TEXT_ELEMENTS: [&str; 4] = ["script", "style", "title", "textarea"];
+
+pub struct Tokenizer<'a> {
+    source: &'a str,
+    position: usize,
+    // The element whose content is read next as raw text.
+    raw_text_element: Option<String>,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(source: &'a str) -> Tokenizer<'a> {
+        Tokenizer {
+            source,
+            position: 0,
+            raw_text_element: None,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.source[self.position..]
+    }
+
+    // Moves past the next occurrence of the end, or to the end of the source if there is none.
+    fn skip_past(&mut self, end: &str) {
+        self.position = match self.rest().find(end) {
+            Some(index) => self.position + index + end.len(),
+            None => self.source.len(),
+        };
+    }
+
+    fn skip_spaces(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    // Reads up to the first character that matches, or the end of the source.
+    fn read_until(&mut self, end: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let length = rest.find(end).unwrap_or(rest.len());
+        self.position += length;
+        &rest[..length]
+    }
+
+    fn read_raw_text(&mut self, element: &str) -> Token {
+        let end_tag = format!("</{}", element);
+        let rest = self.rest();
+        let length = rest
+            .as_bytes()
+            .windows(end_tag.len())
+            .position(|window| window.eq_ignore_ascii_case(end_tag.as_bytes()))
+            .unwrap_or(rest.len());
+        self.position += length;
+
+        let text = &rest[..length];
+        if element == "script" || element == "style" {
+            Token::Text(text.to_owned())
+        } else {
+            Token::Text(decode_entities(text))
+        }
+    }
+
+    fn read_end_tag(&mut self) -> Token {
+        self.position += 2;
+        let name = self
+            .read_until(|c| c.is_whitespace() || c == '/' || c == '>')
+            .to_ascii_lowercase();
+        self.skip_past(">");
+        Token::EndTag { name }
+    }
+
+    fn read_attribute_value(&mut self) -> &'a str {
+        let rest = self.rest();
+        match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                self.position += 1;
+                let value = self.read_until(|c| c == quote);
+                if self.position < self.source.len() {
+                    self.position += 1;
+                    value
+                } else {
+                    // Without a closing quote, the value is taken to end with the tag instead of the source.
+                    self.position -= value.len();
+                    self.read_until(|c| c == '>')
+                }
+            }
+            _ => self.read_until(|c| c.is_whitespace() || c == '>'),
+        }
+    }
+
+    fn read_start_tag(&mut self) -> Token {
+        self.position += 1;
+        let name = self
+            .read_until(|c| c.is_whitespace() || c == '/' || c == '>')
+            .to_ascii_lowercase();
+
+        let mut attributes: Vec<(String, String)> = Vec::new();
+        loop {
+            self.skip_spaces();
+            match self.rest().chars().next() {
+                None => break,
+                Some('>') => {
+                    self.position += 1;
+                    break;
+                }
+                Some('/') => {
+                    self.position += 1;
+                    continue;
+                }
+                Some(_) => {}
+            }
+
+            // A name can start with '=', which is kept as part of it.
+            let first = self.rest().chars().next().unwrap_or_default().len_utf8();
+            self.position += first;
+            let attribute_name = (String::from(&self.source[self.position - first..self.position])
+                + self.read_until(|c| c.is_whitespace() || c == '=' || c == '>' || c == '/'))
+            .to_ascii_lowercase();
+
+            self.skip_spaces();
+            let value = if self.rest().starts_with('=') {
+                self.position += 1;
+                self.skip_spaces();
+                decode_entities(self.read_attribute_value())
+            } else {
+                String::new()
+            };
+
+            if attribute(&attributes, &attribute_name).is_none() {
+                attributes.push((attribute_name, value));
+            }
+        }
+
+        if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+            self.raw_text_element = Some(name.clone());
+        }
+        Token::StartTag { name, attributes }
+    }
+
+    fn read_text(&mut self) -> Token {
+        // The first character may be a '<' that doesn't start a tag.
+        let first = self.rest().chars().next().unwrap_or_default().len_utf8();
+        self.position += first;
+        let text = &self.source[self.position - first..];
+        let length = first + self.read_until(|c| c == '<').len();
+        Token::Text(decode_entities(&text[..length]))
+    }
+}
+
+impl Iterator for Tokenizer<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        if let Some(element) = self.raw_text_element.take() {
+            let token = self.read_raw_text(&element);
+            if token != Token::Text(String::new()) {
+                return Some(token);
+            }
+        }
+
+        loop {
+            let rest = self.rest();
+            let mut chars = rest.chars();
+            let (first, second) = (chars.next()?, chars.next());
+
+            if rest.starts_with("<!--") {
+                self.position += 4;
+                self.skip_past("-->");
+            } else if first == '<' && matches!(second, Some('!' | '?')) {
+                self.skip_past(">");
+            } else if rest.starts_with("</")
+                && rest[2..].starts_with(|c: char| c.is_ascii_alphabetic())
+            {
+                return Some(self.read_end_tag());
+            } else if first == '<' && second.is_some_and(|c| c.is_ascii_alphabetic()) {
+                return Some(self.read_start_tag());
+            } else {
+                return Some(self.read_text());
+            }
+        }
+    }
+}
+
+fn named_character(name: &str) -> Option<char> {
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "hellip" => '…',
+        "mdash" => '—',
+        "ndash" => '–',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "sbquo" => '‚',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "bdquo" => '„',
+        "laquo" => '«',
+        "raquo" => '»',
+        "middot" => '·',
+        "bull" => '•',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "deg" => '°',
+        "euro" => '€',
+        "pound" => '£',
+        "yen" => '¥',
+        "cent" => '¢',
+        "sect" => '§',
+        "para" => '¶',
+        "times" => '×',
+        "divide" => '÷',
+        "iexcl" => '¡',
+        "iquest" => '¿',
+        "ordf" => 'ª',
+        "ordm" => 'º',
+        "aacute" => 'á',
+        "eacute" => 'é',
+        "iacute" => 'í',
+        "oacute" => 'ó',
+        "uacute" => 'ú',
+        "Aacute" => 'Á',
+        "Eacute" => 'É',
+        "Iacute" => 'Í',
+        "Oacute" => 'Ó',
+        "Uacute" => 'Ú',
+        "agrave" => 'à',
+        "egrave" => 'è',
+        "igrave" => 'ì',
+        "ograve" => 'ò',
+        "ugrave" => 'ù',
+        "Agrave" => 'À',
+        "Egrave" => 'È',
+        "acirc" => 'â',
+        "ecirc" => 'ê',
+        "icirc" => 'î',
+        "ocirc" => 'ô',
+        "ucirc" => 'û',
+        "auml" => 'ä',
+        "euml" => 'ë',
+        "iuml" => 'ï',
+        "ouml" => 'ö',
+        "uuml" => 'ü',
+        "Auml" => 'Ä',
+        "Ouml" => 'Ö',
+        "Uuml" => 'Ü',
+        "ntilde" => 'ñ',
+        "Ntilde" => 'Ñ',
+        "ccedil" => 'ç',
+        "Ccedil" => 'Ç',
+        "aring" => 'å',
+        "Aring" => 'Å',
+        "oslash" => 'ø',
+        "Oslash" => 'Ø',
+        "aelig" => 'æ',
+        "AElig" => 'Æ',
+        "szlig" => 'ß',
+        _ => return None,
+    };
+    Some(c)
+}
+
+// Decodes the character reference at the start of the text, returning the character and the length of the
+// reference.
+fn decode_reference(text: &str) -> Option<(char, usize)> {
+    let semicolon = text.char_indices().take(12).find(|(_, c)| *c == ';');
+    let Some((end, _)) = semicolon else {
+        // Old pages leave out the semicolon of the most common references.
+        return ["amp", "lt", "gt", "quot"].iter().find_map(|name| {
+            let rest = text[1..].strip_prefix(name)?;
+            (!rest.starts_with(|c: char| c.is_ascii_alphanumeric()))
+                .then(|| (named_character(name).unwrap(), name.len() + 1))
+        });
+    };
+
+    let reference = &text[1..end];
+    let c = match reference.strip_prefix('#') {
+        Some(number) => {
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            match char::from_u32(code) {
+                Some('\0') | None => char::REPLACEMENT_CHARACTER,
+                Some(c) => c,
+            }
+        }
+        None => named_character(reference)?,
+    };
+    Some((c, end + 1))
+}
+
+// Replaces character references like &amp;, &#39; or &#x27; with the characters they stand for. Unknown references
+// are kept as they are.
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(ampersand) = rest.find('&') {
+        decoded.push_str(&rest[..ampersand]);
+        rest = &rest[ampersand..];
+        match decode_reference(rest) {
+            Some((c, length)) => {
+                decoded.push(c);
+                rest = &rest[length..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start_tag(name: &str, attributes: &[(&str, &str)]) -> Token {
+        Token::StartTag {
+            name: String::from(name),
+            attributes: attributes
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    fn text(text: &str) -> Token {
+        Token::Text(String::from(text))
+    }
+
+    fn end_tag(name: &str) -> Token {
+        Token::EndTag {
+            name: String::from(name),
+        }
+    }
+
+    // Tokenizer
+
+    #[test]
+    fn attributes_can_be_quoted_in_any_way() {
+        let tokens = Tokenizer::new(
+            r#"<META NAME=author content='Pauline Kael' property = "og:x" data-empty checked/>"#,
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [start_tag(
+                "meta",
+                &[
+                    ("name", "author"),
+                    ("content", "Pauline Kael"),
+                    ("property", "og:x"),
+                    ("data-empty", ""),
+                    ("checked", ""),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn markup_inside_quotes_comments_and_scripts_is_not_read_as_tags() {
+        let tokens = Tokenizer::new(
+            r#"<!DOCTYPE html><!-- <meta name="a" content="b"> --><meta content="1 > 0"><script>let s = "<meta name='c'>";</script>"#,
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                start_tag("meta", &[("content", "1 > 0")]),
+                start_tag("script", &[]),
+                text("let s = \"<meta name='c'>\";"),
+                end_tag("script"),
+            ]
+        );
+    }
+
+    #[test]
+    fn title_is_raw_text_with_references() {
+        let tokens = Tokenizer::new("<title>Bonnie &amp; Clyde <b></title>").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            [
+                start_tag("title", &[]),
+                text("Bonnie & Clyde <b>"),
+                end_tag("title")
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_markup_ends_with_the_source() {
+        assert_eq!(
+            Tokenizer::new("<meta name=\"a content=b>x").collect::<Vec<_>>(),
+            [start_tag("meta", &[("name", "a content=b")]), text("x")]
+        );
+        assert_eq!(
+            Tokenizer::new("a < b <!-- c").collect::<Vec<_>>(),
+            [text("a "), text("< b ")]
+        );
+        assert_eq!(
+            Tokenizer::new("<title>Untitled").collect::<Vec<_>>(),
+            [start_tag("title", &[]), text("Untitled")]
+        );
+    }
+
+    // decode_entities
+
+    #[test]
+    fn decode_entities_of_every_kind() {
+        assert_eq!(
+            decode_entities("Kael&#39;s &quot;Bonnie &amp; Clyde&quot; &#x2014; Espa&ntilde;a"),
+            "Kael's \"Bonnie & Clyde\" — España"
+        );
+        assert_eq!(decode_entities("AT&T &amp co"), "AT&T & co");
+        assert_eq!(decode_entities("&unknown; &#xZZ; &"), "&unknown; &#xZZ; &");
+    }
+}
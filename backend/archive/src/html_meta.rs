@@ -0,0 +1,715 @@
+use crate::date::{self, Date};
+use crate::entry_type::EntryType;
+use crate::html::{attribute, Token, Tokenizer};
+use crate::identifiers::identifier_of;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct MetaTag {
+    pub name: Option<String>,
+    pub property: Option<String>,
+    pub content: String,
+}
+
+// What the head of a page says about it.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct HtmlHead {
+    // Meta tags with content and a name or property, in the order of the page.
+    pub meta_tags: Vec<MetaTag>,
+    // Text of the first <title>, with its whitespace collapsed.
+    pub title: Option<String>,
+    // Link of the first <link rel="canonical">, as written in the page.
+    pub canonical_link: Option<String>,
+    // Link of the first <link rel="alternate" type="application/json+oembed">, the endpoint that describes the page
+    // in JSON, as written in the page.
+    pub oembed_link: Option<String>,
+    // Link of the first <link rel="icon">, or of the first <link rel="apple-touch-icon"> if there is none, as
+    // written in the page.
+    pub icon_link: Option<String>,
+    // Language of the <html> element.
+    pub language: Option<String>,
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+}
+
+pub fn parse_html_head(html_source: &str) -> HtmlHead {
+    let mut head = HtmlHead::default();
+    let mut title: Option<String> = None;
+    let mut in_title = false;
+    let mut apple_touch_icon_link: Option<String> = None;
+
+    for token in Tokenizer::new(html_source) {
+        match token {
+            Token::StartTag { name, attributes } => match name.as_str() {
+                "html" if head.language.is_none() => {
+                    head.language = non_empty(attribute(&attributes, "lang"));
+                }
+                "meta" => {
+                    let name = non_empty(attribute(&attributes, "name"));
+                    let property = non_empty(attribute(&attributes, "property"));
+                    if let Some(content) = attribute(&attributes, "content") {
+                        if name.is_some() || property.is_some() {
+                            head.meta_tags.push(MetaTag {
+                                name,
+                                property,
+                                content: String::from(content),
+                            });
+                        }
+                    }
+                }
+                "link" => {
+                    let rel = attribute(&attributes, "rel").unwrap_or_default();
+                    let has_rel = |value: &str| {
+                        rel.split_ascii_whitespace()
+                            .any(|rel| rel.eq_ignore_ascii_case(value))
+                    };
+                    let link_type = attribute(&attributes, "type").unwrap_or_default();
+                    if has_rel("canonical") && head.canonical_link.is_none() {
+                        head.canonical_link = non_empty(attribute(&attributes, "href"));
+                    } else if has_rel("alternate")
+                        && link_type
+                            .trim()
+                            .eq_ignore_ascii_case("application/json+oembed")
+                        && head.oembed_link.is_none()
+                    {
+                        head.oembed_link = non_empty(attribute(&attributes, "href"));
+                    } else if has_rel("icon") && head.icon_link.is_none() {
+                        head.icon_link = non_empty(attribute(&attributes, "href"));
+                    } else if has_rel("apple-touch-icon") && apple_touch_icon_link.is_none() {
+                        apple_touch_icon_link = non_empty(attribute(&attributes, "href"));
+                    }
+                }
+                "title" if title.is_none() => {
+                    title = Some(String::new());
+                    in_title = true;
+                }
+                _ => {}
+            },
+            Token::Text(text) if in_title => {
+                if let Some(title) = &mut title {
+                    title.push_str(&text);
+                }
+            }
+            Token::EndTag { name } if name == "title" => in_title = false,
+            _ => {}
+        }
+    }
+
+    head.title = title
+        .map(|title| title.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|title| !title.is_empty());
+    head.icon_link = head.icon_link.or(apple_touch_icon_link);
+    head
+}
+
+// Name, or property if it has no name, and content of every meta tag of the page.
+pub fn html_meta_headers(html_source: &str) -> Vec<(String, String)> {
+    parse_html_head(html_source)
+        .meta_tags
+        .into_iter()
+        .filter_map(|tag| Some((tag.name.or(tag.property)?, tag.content)))
+        .collect()
+}
+
+// What one of the vocabularies of a page says about it, in the terms of an entry.
+#[derive(Serialize, ToSchema, Debug, Default, PartialEq, Eq, Clone)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub authors: Vec<String>,
+    pub date_published: Option<Date>,
+    pub entry_type: Option<EntryType>,
+    // As written in the page, so it may be relative to the page's url.
+    pub image: Option<String>,
+    // As written in the page, so it may be relative to the page's url.
+    pub canonical_link: Option<String>,
+    // The DOI, arXiv id or ISBN of the text, as stored in entries, like "doi:10.1145/3297858.3304013".
+    pub identifier: Option<String>,
+}
+
+fn is_named(tag: &MetaTag, name: &str) -> bool {
+    [&tag.name, &tag.property]
+        .into_iter()
+        .flatten()
+        .any(|tag_name| tag_name.eq_ignore_ascii_case(name))
+}
+
+// Content of the first tag with the first of the names, as a name or property, that has any.
+fn find_one_of(tags: &[MetaTag], names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| {
+        tags.iter()
+            .find(|tag| is_named(tag, name) && !tag.content.trim().is_empty())
+            .map(|tag| tag.content.trim().to_owned())
+    })
+}
+
+// Contents of every tag with any of the names, for values that can be given many times, like authors.
+fn find_all_of(tags: &[MetaTag], names: &[&str]) -> Vec<String> {
+    tags.iter()
+        .filter(|tag| names.iter().any(|name| is_named(tag, name)))
+        .map(|tag| tag.content.trim().to_owned())
+        .filter(|content| !content.is_empty())
+        .collect()
+}
+
+// Dates like 2016-06-01, 2016/06/01 or 2016-06-01T10:00:00+02:00, ignoring the time.
+pub(crate) fn parse_date(text: &str) -> Option<Date> {
+    let date = text.split('T').next().unwrap_or_default().replace('/', "-");
+    date::read_sql_date(date.trim()).filter(|date| date::is_valid(*date))
+}
+
+fn find_date(tags: &[MetaTag], names: &[&str]) -> Option<Date> {
+    names
+        .iter()
+        .find_map(|name| parse_date(&find_one_of(tags, &[name])?))
+}
+
+// Citation formats write authors as "Kael, Pauline".
+pub(crate) fn author_name(author: &str) -> String {
+    match author.split_once(',') {
+        Some((last, first)) if !first.contains(',') && !first.trim().is_empty() => {
+            format!("{} {}", first.trim(), last.trim())
+        }
+        _ => String::from(author),
+    }
+}
+
+fn open_graph_entry_type(tags: &[MetaTag]) -> Option<EntryType> {
+    let duration = |name| {
+        find_one_of(tags, &[name])
+            .and_then(|duration| duration.parse().ok())
+            .unwrap_or(0)
+    };
+
+    let og_type = find_one_of(tags, &["og:type"])?;
+    if og_type.starts_with("video") {
+        Some(EntryType::Video {
+            length_in_seconds: duration("video:duration"),
+        })
+    } else if og_type.starts_with("music") {
+        Some(EntryType::Audio {
+            length_in_seconds: duration("music:duration"),
+        })
+    } else if og_type == "book" {
+        Some(EntryType::Book { pages: 0 })
+    } else if og_type == "article" {
+        Some(EntryType::Article { words: 0 })
+    } else {
+        None
+    }
+}
+
+// The og: tags, and the article:, book:, video: and music: tags of their types.
+pub fn open_graph_metadata(head: &HtmlHead) -> PageMetadata {
+    let tags = &head.meta_tags;
+    PageMetadata {
+        title: find_one_of(tags, &["og:title"]),
+        description: find_one_of(tags, &["og:description"]),
+        // article:author is often a link to the author's profile instead of their name.
+        authors: find_all_of(tags, &["article:author", "book:author", "og:author"])
+            .into_iter()
+            .filter(|author| !author.contains("://"))
+            .collect(),
+        date_published: find_date(
+            tags,
+            &[
+                "article:published_time",
+                "book:release_date",
+                "video:release_date",
+                "music:release_date",
+            ],
+        ),
+        entry_type: open_graph_entry_type(tags),
+        image: find_one_of(tags, &["og:image", "og:image:url", "og:image:secure_url"]),
+        canonical_link: find_one_of(tags, &["og:url"]),
+        identifier: None,
+    }
+}
+
+// The twitter: tags of Twitter cards, which have no authors or dates, only handles.
+pub fn twitter_card_metadata(head: &HtmlHead) -> PageMetadata {
+    let tags = &head.meta_tags;
+    PageMetadata {
+        title: find_one_of(tags, &["twitter:title"]),
+        description: find_one_of(tags, &["twitter:description"]),
+        image: find_one_of(tags, &["twitter:image", "twitter:image:src"]),
+        ..PageMetadata::default()
+    }
+}
+
+// The DC. and dcterms. tags of Dublin Core.
+pub fn dublin_core_metadata(head: &HtmlHead) -> PageMetadata {
+    let tags = &head.meta_tags;
+    let entry_type = match find_one_of(tags, &["DC.type", "dcterms.type"])
+        .map(|kind| kind.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("text") => Some(EntryType::Article { words: 0 }),
+        Some("movingimage") => Some(EntryType::Video {
+            length_in_seconds: 0,
+        }),
+        Some("sound") => Some(EntryType::Audio {
+            length_in_seconds: 0,
+        }),
+        _ => None,
+    };
+
+    PageMetadata {
+        title: find_one_of(tags, &["DC.title", "dcterms.title"]),
+        description: find_one_of(
+            tags,
+            &["DC.description", "dcterms.description", "dcterms.abstract"],
+        ),
+        authors: find_all_of(tags, &["DC.creator", "dcterms.creator"])
+            .iter()
+            .map(|author| author_name(author))
+            .collect(),
+        date_published: find_date(
+            tags,
+            &[
+                "DC.date",
+                "dcterms.issued",
+                "dcterms.date",
+                "dcterms.created",
+            ],
+        ),
+        entry_type,
+        image: None,
+        canonical_link: find_one_of(tags, &["DC.identifier", "dcterms.identifier"])
+            .filter(|identifier| identifier.contains("://")),
+        // Repositories write the DOI or ISBN of the text as its identifier when it has one, instead of its link.
+        identifier: find_all_of(tags, &["DC.identifier", "dcterms.identifier"])
+            .iter()
+            .find_map(|identifier| identifier_of(identifier))
+            .map(|identifier| identifier.to_string()),
+    }
+}
+
+// The citation_ tags of Highwire Press, that Google Scholar reads and that journals and repositories of papers use.
+pub fn highwire_metadata(head: &HtmlHead) -> PageMetadata {
+    let tags = &head.meta_tags;
+    let title = find_one_of(tags, &["citation_title"]);
+    let page = |name| find_one_of(tags, &[name]).and_then(|page| page.parse::<i32>().ok());
+    let pages = match (page("citation_firstpage"), page("citation_lastpage")) {
+        (Some(first), Some(last)) if last >= first => last - first + 1,
+        _ => 0,
+    };
+    let entry_type = title.as_ref().map(|_| {
+        if find_one_of(tags, &["citation_isbn"]).is_some()
+            && find_one_of(tags, &["citation_inbook_title"]).is_none()
+        {
+            EntryType::Book { pages }
+        } else {
+            EntryType::Paper { pages }
+        }
+    });
+
+    PageMetadata {
+        title,
+        description: find_one_of(tags, &["citation_abstract"]),
+        authors: find_all_of(tags, &["citation_author"])
+            .iter()
+            .map(|author| author_name(author))
+            .collect(),
+        date_published: find_date(
+            tags,
+            &[
+                "citation_publication_date",
+                "citation_date",
+                "citation_online_date",
+            ],
+        ),
+        entry_type,
+        image: None,
+        canonical_link: find_one_of(tags, &["citation_abstract_html_url"]),
+        identifier: ["citation_doi", "citation_arxiv_id", "citation_isbn"]
+            .iter()
+            .find_map(|name| identifier_of(&find_one_of(tags, &[name])?))
+            .map(|identifier| identifier.to_string()),
+    }
+}
+
+// The meta tags of plain html, the <title> and the <link rel="canonical">.
+pub fn html_metadata(head: &HtmlHead) -> PageMetadata {
+    let tags = &head.meta_tags;
+    PageMetadata {
+        title: find_one_of(tags, &["title"]).or_else(|| head.title.clone()),
+        description: find_one_of(tags, &["description"]),
+        authors: find_all_of(tags, &["author"])
+            .into_iter()
+            .filter(|author| !author.contains("://"))
+            .collect(),
+        canonical_link: head.canonical_link.clone(),
+        ..PageMetadata::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::Month;
+
+    fn fixture(name: &str) -> String {
+        let path = format!(
+            "{}/fixtures/pages/{}.html",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    fn meta(name: Option<&str>, property: Option<&str>, content: &str) -> MetaTag {
+        MetaTag {
+            name: name.map(String::from),
+            property: property.map(String::from),
+            content: String::from(content),
+        }
+    }
+
+    fn content_of<'a>(head: &'a HtmlHead, name: &str) -> Option<&'a str> {
+        head.meta_tags
+            .iter()
+            .find(|tag| tag.name.as_deref() == Some(name) || tag.property.as_deref() == Some(name))
+            .map(|tag| tag.content.as_str())
+    }
+
+    // parse_html_head
+
+    #[test]
+    fn head_of_a_magazine_article() {
+        let head = parse_html_head(&fixture("magazine_article"));
+
+        assert_eq!(
+            head.title.as_deref(),
+            Some("Bonnie and Clyde | The New Yorker")
+        );
+        assert_eq!(
+            head.canonical_link.as_deref(),
+            Some("https://www.newyorker.com/magazine/1967/10/21/bonnie-and-clyde")
+        );
+        assert_eq!(head.language.as_deref(), Some("en-US"));
+        assert_eq!(
+            content_of(&head, "description"),
+            Some("Pauline Kael on Arthur Penn's \"Bonnie and Clyde,\" a movie that puts the sting back into death.")
+        );
+        assert_eq!(
+            content_of(&head, "og:description"),
+            Some("Pauline Kael on Arthur Penn's “Bonnie and Clyde.”")
+        );
+        assert_eq!(
+            content_of(&head, "og:image"),
+            Some("https://media.newyorker.com/photos/bonnie-and-clyde.jpg?w=1200&h=630")
+        );
+        // The meta tag in the script is not one.
+        assert_eq!(
+            head.meta_tags
+                .iter()
+                .filter(|tag| tag.name.as_deref() == Some("author"))
+                .collect::<Vec<_>>(),
+            [&meta(Some("author"), None, "Pauline Kael")]
+        );
+        // Tags without a name or property, like charset and http-equiv, are left out.
+        assert_eq!(head.meta_tags.len(), 14);
+    }
+
+    #[test]
+    fn head_of_a_video_page() {
+        let head = parse_html_head(&fixture("video_page"));
+
+        assert_eq!(
+            head.title.as_deref(),
+            Some("Arthur Penn sobre \"Bonnie & Clyde\" (1967) - YouTube")
+        );
+        assert_eq!(
+            head.canonical_link.as_deref(),
+            Some("https://www.youtube.com/watch?v=abc123")
+        );
+        // The JSON one, not the XML one before it.
+        assert_eq!(
+            head.oembed_link.as_deref(),
+            Some("https://www.youtube.com/oembed?format=json&url=https%3A%2F%2Fwww.youtube.com%2Fwatch%3Fv%3Dabc123")
+        );
+        assert_eq!(
+            head.icon_link.as_deref(),
+            Some("https://www.youtube.com/s/desktop/favicon.ico")
+        );
+        assert_eq!(head.language.as_deref(), Some("es-419"));
+        assert_eq!(
+            head.meta_tags[0],
+            meta(
+                Some("title"),
+                None,
+                "Arthur Penn sobre \"Bonnie & Clyde\" (1967)"
+            )
+        );
+        assert_eq!(content_of(&head, "og:site_name"), Some("YouTube"));
+        assert_eq!(content_of(&head, "video:duration"), Some("754"));
+        assert_eq!(content_of(&head, "datePublished"), None);
+    }
+
+    #[test]
+    fn head_with_uppercase_tags_and_single_quotes() {
+        let head = parse_html_head(&fixture("spanish_news"));
+
+        assert_eq!(
+            head.title.as_deref(),
+            Some("Crítica: «Bonnie y Clyde», la frontera del cine | Cultura | EL PAÍS")
+        );
+        assert_eq!(
+            head.canonical_link.as_deref(),
+            Some("https://elpais.com/cultura/2017/08/13/bonnie-y-clyde.html")
+        );
+        assert_eq!(head.language.as_deref(), Some("es"));
+        assert_eq!(
+            content_of(&head, "description"),
+            Some("Medio siglo después, la película de Arthur Penn sigue siendo un disparo en la sala.")
+        );
+        assert_eq!(
+            content_of(&head, "og:title"),
+            Some("Crítica: «Bonnie y Clyde»")
+        );
+    }
+
+    #[test]
+    fn head_with_comments_styles_and_attributes_in_any_order() {
+        let head = parse_html_head(&fixture("blog_post"));
+
+        assert_eq!(
+            head.title.as_deref(),
+            Some("Ten years of Bonnie & Clyde – Movie Diary")
+        );
+        assert_eq!(
+            head.canonical_link.as_deref(),
+            Some("/2019/05/ten-years-of-bonnie-and-clyde/")
+        );
+        // The icon is preferred to the larger apple-touch-icon before it.
+        assert_eq!(
+            head.icon_link.as_deref(),
+            Some("/wp-content/uploads/2019/01/cropped-icon-32x32.png")
+        );
+        assert_eq!(
+            head.meta_tags,
+            [
+                meta(None, Some("og:title"), "Ten years of Bonnie & Clyde"),
+                meta(
+                    None,
+                    Some("og:description"),
+                    "A look back at the movie that changed Hollywood."
+                ),
+                meta(None, Some("og:type"), "article"),
+                meta(None, Some("article:published_time"), "2019-05-04"),
+                meta(
+                    Some("twitter:image"),
+                    None,
+                    "https://example.blog/wp-content/uploads/2019/05/bonnie.jpg"
+                ),
+                meta(Some("generator"), None, "WordPress 5.2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn head_of_a_broken_page() {
+        let head = parse_html_head(&fixture("broken_page"));
+
+        assert_eq!(head.title, None);
+        assert_eq!(head.canonical_link, None);
+        assert_eq!(head.icon_link, None);
+        assert_eq!(head.language, None);
+        assert_eq!(
+            head.meta_tags,
+            [
+                meta(None, Some("og:title"), ""),
+                meta(Some("author"), None, "Someone"),
+                meta(Some("keywords"), None, "a, b, c"),
+                meta(None, Some("og:image"), "https://example.com/image.png"),
+            ]
+        );
+    }
+
+    // html_meta_headers
+
+    #[test]
+    fn html_meta_headers_prefer_the_name() {
+        assert_eq!(
+            html_meta_headers(
+                r#"<meta name="twitter:title" property="og:title" content="A"><meta property="og:type" content="article">"#
+            ),
+            [
+                (String::from("twitter:title"), String::from("A")),
+                (String::from("og:type"), String::from("article")),
+            ]
+        );
+    }
+
+    // open_graph_metadata
+
+    #[test]
+    fn open_graph_of_an_article() {
+        let head = parse_html_head(&fixture("magazine_article"));
+        assert_eq!(
+            open_graph_metadata(&head),
+            PageMetadata {
+                title: Some(String::from("Bonnie and Clyde")),
+                description: Some(String::from(
+                    "Pauline Kael on Arthur Penn's “Bonnie and Clyde.”"
+                )),
+                authors: vec![],
+                date_published: Some(Date {
+                    day: 13,
+                    month: Month::October,
+                    year: 1967
+                }),
+                entry_type: Some(EntryType::Article { words: 0 }),
+                image: Some(String::from(
+                    "https://media.newyorker.com/photos/bonnie-and-clyde.jpg?w=1200&h=630"
+                )),
+                canonical_link: Some(String::from(
+                    "https://www.newyorker.com/magazine/1967/10/21/bonnie-and-clyde"
+                )),
+                identifier: None,
+            }
+        );
+    }
+
+    #[test]
+    fn open_graph_of_a_video() {
+        let head = parse_html_head(
+            r#"
+            <meta property="og:type" content="video.other">
+            <meta property="video:duration" content="754">
+            <meta property="article:author" content="https://example.com/someone">
+            <meta property="article:published_time" content="2023-02-29">
+        "#,
+        );
+
+        let metadata = open_graph_metadata(&head);
+        assert_eq!(
+            metadata.entry_type,
+            Some(EntryType::Video {
+                length_in_seconds: 754
+            })
+        );
+        assert!(metadata.authors.is_empty());
+        assert_eq!(metadata.date_published, None);
+    }
+
+    // dublin_core_metadata
+
+    #[test]
+    fn dublin_core_of_a_repository_page() {
+        let head = parse_html_head(
+            r#"
+            <meta name="DC.title" content="Bonnie and Clyde">
+            <meta name="DC.creator" content="Kael, Pauline">
+            <meta name="dc.creator" content="Roger Ebert">
+            <meta name="DCTERMS.issued" content="1967/10/21">
+            <meta name="DC.type" content="MovingImage">
+            <meta name="DC.identifier" content="urn:isbn:0-316-48163-7">
+        "#,
+        );
+
+        let metadata = dublin_core_metadata(&head);
+        assert_eq!(metadata.title.as_deref(), Some("Bonnie and Clyde"));
+        assert_eq!(metadata.authors, ["Pauline Kael", "Roger Ebert"]);
+        assert_eq!(
+            metadata.date_published,
+            Some(Date {
+                day: 21,
+                month: Month::October,
+                year: 1967
+            })
+        );
+        assert_eq!(
+            metadata.entry_type,
+            Some(EntryType::Video {
+                length_in_seconds: 0
+            })
+        );
+        assert_eq!(metadata.canonical_link, None);
+        assert_eq!(metadata.identifier.as_deref(), Some("isbn:9780316481632"));
+    }
+
+    // highwire_metadata
+
+    #[test]
+    fn highwire_of_a_paper() {
+        let head = parse_html_head(
+            r#"
+            <meta name="citation_title" content="The Frontier of Movies">
+            <meta name="citation_author" content="Kael, Pauline">
+            <meta name="citation_author" content="Penn, Arthur">
+            <meta name="citation_publication_date" content="1968/01/15">
+            <meta name="citation_journal_title" content="Film Quarterly">
+            <meta name="citation_firstpage" content="112">
+            <meta name="citation_lastpage" content="131">
+            <meta name="citation_doi" content="https://doi.org/10.2307/1210357">
+        "#,
+        );
+
+        assert_eq!(
+            highwire_metadata(&head),
+            PageMetadata {
+                title: Some(String::from("The Frontier of Movies")),
+                authors: vec![String::from("Pauline Kael"), String::from("Arthur Penn")],
+                date_published: Some(Date {
+                    day: 15,
+                    month: Month::January,
+                    year: 1968
+                }),
+                entry_type: Some(EntryType::Paper { pages: 20 }),
+                identifier: Some(String::from("doi:10.2307/1210357")),
+                ..PageMetadata::default()
+            }
+        );
+    }
+
+    #[test]
+    fn highwire_of_a_book() {
+        let head = parse_html_head(
+            r#"<meta name="citation_title" content="Kiss Kiss Bang Bang"><meta name="citation_isbn" content="0-316-48163-7">"#,
+        );
+        assert_eq!(
+            highwire_metadata(&head).entry_type,
+            Some(EntryType::Book { pages: 0 })
+        );
+        assert_eq!(
+            highwire_metadata(&head).identifier.as_deref(),
+            Some("isbn:9780316481632")
+        );
+        assert_eq!(
+            highwire_metadata(&HtmlHead::default()),
+            PageMetadata::default()
+        );
+    }
+
+    // html_metadata
+
+    #[test]
+    fn html_falls_back_to_the_title_tag() {
+        let head = parse_html_head(&fixture("spanish_news"));
+        let metadata = html_metadata(&head);
+        assert_eq!(
+            metadata.title.as_deref(),
+            Some("Crítica: «Bonnie y Clyde», la frontera del cine | Cultura | EL PAÍS")
+        );
+        assert_eq!(metadata.authors, ["Carlos Boyero"]);
+        assert_eq!(
+            metadata.canonical_link.as_deref(),
+            Some("https://elpais.com/cultura/2017/08/13/bonnie-y-clyde.html")
+        );
+
+        let head = parse_html_head(&fixture("video_page"));
+        assert_eq!(
+            html_metadata(&head).title.as_deref(),
+            Some("Arthur Penn sobre \"Bonnie & Clyde\" (1967)")
+        );
+    }
+}
@@ -0,0 +1,424 @@
+// DOIs, arXiv ids and ISBNs, which identify papers and books better than the links to them do, as they are written
+// by hand, found in links and stored in entries.
+
+use percent_encoding::percent_decode_str;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Identifier {
+    // Lowercase, as DOIs are case insensitive.
+    Doi(String),
+    // Without the version, which all name the same paper.
+    Arxiv(String),
+    // Always of 13 digits, those of 10 being converted.
+    Isbn(String),
+}
+
+// Written as stored in entries, like "doi:10.1145/3297858.3304013", "arxiv:1706.03762" or "isbn:9780140328721".
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifier::Doi(doi) => write!(f, "doi:{}", doi),
+            Identifier::Arxiv(id) => write!(f, "arxiv:{}", id),
+            Identifier::Isbn(isbn) => write!(f, "isbn:{}", isbn),
+        }
+    }
+}
+
+impl Identifier {
+    // Link to the page of the text, for entries created from just the identifier.
+    pub fn link(&self) -> String {
+        match self {
+            Identifier::Doi(doi) => format!("https://doi.org/{}", doi),
+            Identifier::Arxiv(id) => format!("https://arxiv.org/abs/{}", id),
+            Identifier::Isbn(isbn) => format!("https://openlibrary.org/isbn/{}", isbn),
+        }
+    }
+}
+
+// DOIs are "10.", the number of the registrant, a slash and anything the registrant wants.
+fn doi(text: &str) -> Option<Identifier> {
+    let text = text.trim().trim_end_matches(['.', ',', ';']);
+    let (registrant, suffix) = text.strip_prefix("10.")?.split_once('/')?;
+    let registrant_is_valid = registrant
+        .split('.')
+        .all(|part| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit()));
+    (registrant_is_valid && !suffix.is_empty() && !text.chars().any(char::is_whitespace))
+        .then(|| Identifier::Doi(text.to_lowercase()))
+}
+
+fn is_digits(text: &str, lengths: &[usize]) -> bool {
+    lengths.contains(&text.len()) && text.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+// Ids since 2007 are like 1706.03762, the year and month and a number. Older ones are like hep-th/9901001, the archive
+// and then the same with a number of 3 digits.
+fn arxiv(text: &str) -> Option<Identifier> {
+    let text = text.trim();
+    let id = match text.rfind('v') {
+        Some(version) if version > 0 && is_digits(&text[version + 1..], &[1, 2, 3]) => {
+            &text[..version]
+        }
+        _ => text,
+    };
+    let is_valid = match id.split_once('/') {
+        Some((archive, number)) => {
+            !archive.is_empty()
+                && archive
+                    .bytes()
+                    .all(|byte| byte.is_ascii_alphabetic() || byte == b'-' || byte == b'.')
+                && is_digits(number, &[7])
+                && (1..=12).contains(&number[2..4].parse::<u32>().unwrap_or(0))
+        }
+        None => match id.split_once('.') {
+            Some((date, number)) => {
+                is_digits(date, &[4])
+                    && is_digits(number, &[4, 5])
+                    && (1..=12).contains(&date[2..].parse::<u32>().unwrap_or(0))
+            }
+            None => false,
+        },
+    };
+    is_valid.then(|| Identifier::Arxiv(id.to_string()))
+}
+
+// The check digit of the first 9 digits of an ISBN-10, of which 10 is written as X.
+fn isbn_10_check_digit(digits: &[u32]) -> char {
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(i, digit)| (10 - i as u32) * digit)
+        .sum();
+    match (11 - sum % 11) % 11 {
+        10 => 'X',
+        check => char::from_digit(check, 10).unwrap(),
+    }
+}
+
+fn isbn_13_check_digit(digits: &[u32]) -> char {
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(i, digit)| if i % 2 == 0 { *digit } else { 3 * digit })
+        .sum();
+    char::from_digit((10 - sum % 10) % 10, 10).unwrap()
+}
+
+// ISBNs are written with or without hyphens or spaces between their groups. Only those whose check digit is right are
+// taken to be ISBNs, so that other numbers aren't.
+fn isbn(text: &str) -> Option<Identifier> {
+    let text: String = text
+        .trim()
+        .chars()
+        .filter(|c| *c != '-' && *c != ' ')
+        .collect::<String>()
+        .to_uppercase();
+    let check = text.chars().last()?;
+    let body = &text[..text.len() - check.len_utf8()];
+    if !body.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let digits: Vec<u32> = body.chars().filter_map(|c| c.to_digit(10)).collect();
+    match digits.len() {
+        9 if isbn_10_check_digit(&digits) == check => {
+            let mut digits_13 = vec![9, 7, 8];
+            digits_13.extend(&digits);
+            Some(Identifier::Isbn(format!(
+                "978{}{}",
+                body,
+                isbn_13_check_digit(&digits_13)
+            )))
+        }
+        12 if (body.starts_with("978") || body.starts_with("979"))
+            && isbn_13_check_digit(&digits) == check =>
+        {
+            Some(Identifier::Isbn(text))
+        }
+        _ => None,
+    }
+}
+
+// Strips the prefix from the text if it starts with it in any case, and the separator that may follow it.
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let start = text.get(..prefix.len())?;
+    start.eq_ignore_ascii_case(prefix).then(|| {
+        text[prefix.len()..]
+            .trim_start_matches([':', ' '])
+            .trim_start_matches("-10")
+            .trim_start_matches("-13")
+            .trim_start_matches([':', ' '])
+    })
+}
+
+// The identifier written in the text, either as stored, with a prefix like "DOI 10.1145/3297858.3304013",
+// "arXiv:1706.03762" or "ISBN 0-14-118260-1", or alone.
+pub fn parse_identifier(text: &str) -> Option<Identifier> {
+    let text = text.trim();
+    // Also as URNs, like "urn:isbn:0-316-48163-7".
+    let text = match text.get(..4) {
+        Some(urn) if urn.eq_ignore_ascii_case("urn:") => &text[4..],
+        _ => text,
+    };
+    if let Some(rest) = strip_prefix_ignore_case(text, "doi") {
+        return doi(rest);
+    }
+    if let Some(rest) = strip_prefix_ignore_case(text, "arxiv") {
+        return arxiv(rest);
+    }
+    if let Some(rest) = strip_prefix_ignore_case(text, "isbn") {
+        return isbn(rest);
+    }
+    doi(text).or_else(|| arxiv(text)).or_else(|| isbn(text))
+}
+
+// Whether the host is the domain or one of its subdomains.
+fn host_is(host: &str, domain: &str) -> bool {
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+// The identifier of the text the link is to: DOI links, the pages and PDFs of arXiv, pages of publishers whose paths
+// have the DOI after /doi/, and the pages of books that have the ISBN in their path or query, like those of Open
+// Library and Amazon.
+pub fn identifier_in_link(link: &str) -> Option<Identifier> {
+    let (_, rest) = link.split_once("://")?;
+    let (host, path) = match rest.find(['/', '?', '#']) {
+        Some(end) => rest.split_at(end),
+        None => (rest, ""),
+    };
+    let host = host.to_lowercase();
+    let (path, query) = match path.split_once('?') {
+        Some((path, query)) => (path, query.split('#').next().unwrap_or_default()),
+        None => (path.split('#').next().unwrap_or_default(), ""),
+    };
+    let path = percent_decode_str(path).decode_utf8().ok()?;
+    let path = path.trim_start_matches('/');
+
+    if host_is(&host, "doi.org") {
+        return doi(path);
+    }
+    if host_is(&host, "arxiv.org") {
+        let id = path
+            .strip_prefix("abs/")
+            .or_else(|| path.strip_prefix("pdf/"))?;
+        return arxiv(id.trim_end_matches(".pdf"));
+    }
+
+    let segments: Vec<&str> = path.split('/').collect();
+    if let Some(start) = segments.iter().position(|segment| *segment == "doi") {
+        let rest = &segments[start + 1..];
+        let rest = match rest.first() {
+            Some(&("abs" | "full" | "pdf" | "epdf" | "book")) => &rest[1..],
+            _ => rest,
+        };
+        if let Some(found) = doi(&rest.join("/")) {
+            return Some(found);
+        }
+    }
+    if let Some(start) = segments
+        .iter()
+        .position(|segment| segment.eq_ignore_ascii_case("isbn"))
+    {
+        if let Some(found) = segments.get(start + 1).and_then(|segment| isbn(segment)) {
+            return Some(found);
+        }
+    }
+    if host.starts_with("amazon.") || host.contains(".amazon.") {
+        if let Some(start) = segments
+            .iter()
+            .position(|segment| *segment == "dp" || *segment == "product")
+        {
+            if let Some(found) = segments.get(start + 1).and_then(|segment| isbn(segment)) {
+                return Some(found);
+            }
+        }
+    }
+    query.split('&').find_map(|argument| {
+        let (name, value) = argument.split_once('=')?;
+        name.eq_ignore_ascii_case("isbn")
+            .then(|| isbn(&percent_decode_str(value).decode_utf8_lossy()))
+            .flatten()
+    })
+}
+
+// The identifier in text that may be written either way, like the citation_doi of pages, which some write as links.
+pub fn identifier_of(text: &str) -> Option<Identifier> {
+    parse_identifier(text).or_else(|| identifier_in_link(text.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doi(text: &str) -> Option<Identifier> {
+        Some(Identifier::Doi(String::from(text)))
+    }
+
+    fn arxiv(text: &str) -> Option<Identifier> {
+        Some(Identifier::Arxiv(String::from(text)))
+    }
+
+    fn isbn(text: &str) -> Option<Identifier> {
+        Some(Identifier::Isbn(String::from(text)))
+    }
+
+    // parse_identifier
+
+    #[test]
+    fn dois_with_and_without_prefix() {
+        for text in [
+            "10.1145/3297858.3304013",
+            "doi:10.1145/3297858.3304013",
+            "DOI: 10.1145/3297858.3304013",
+            " doi 10.1145/3297858.3304013. ",
+        ] {
+            assert_eq!(
+                parse_identifier(text),
+                doi("10.1145/3297858.3304013"),
+                "{}",
+                text
+            );
+        }
+        assert_eq!(
+            parse_identifier("10.1002/(SICI)1097-4571(199806)49:8<693::AID-ASI4>3.0.CO;2-0"),
+            doi("10.1002/(sici)1097-4571(199806)49:8<693::aid-asi4>3.0.co;2-0")
+        );
+        assert_eq!(parse_identifier("10.abc/123"), None);
+        assert_eq!(parse_identifier("doi:10.1145/"), None);
+        assert_eq!(parse_identifier("doi:10.1145/3297858 3304013"), None);
+    }
+
+    #[test]
+    fn arxiv_ids_of_both_schemes_without_version() {
+        assert_eq!(parse_identifier("arXiv:1706.03762v7"), arxiv("1706.03762"));
+        assert_eq!(parse_identifier("arxiv 2301.00001"), arxiv("2301.00001"));
+        assert_eq!(parse_identifier("1706.03762"), arxiv("1706.03762"));
+        assert_eq!(
+            parse_identifier("arXiv:hep-th/9901001v2"),
+            arxiv("hep-th/9901001")
+        );
+        assert_eq!(
+            parse_identifier("math.GT/0309136"),
+            arxiv("math.GT/0309136")
+        );
+        assert_eq!(parse_identifier("arXiv:1713.03762"), None);
+        assert_eq!(parse_identifier("3.14159"), None);
+    }
+
+    #[test]
+    fn isbns_are_converted_to_13_digits() {
+        assert_eq!(parse_identifier("ISBN 0-14-032872-X"), None);
+        assert_eq!(
+            parse_identifier("ISBN 0-14-118260-1"),
+            isbn("9780141182605")
+        );
+        assert_eq!(parse_identifier("isbn:0141182601"), isbn("9780141182605"));
+        assert_eq!(parse_identifier("978-0-14-118260-5"), isbn("9780141182605"));
+        assert_eq!(
+            parse_identifier("ISBN-13: 978 0 14 118260 5"),
+            isbn("9780141182605")
+        );
+        assert_eq!(parse_identifier("0-8044-2957-X"), isbn("9780804429573"));
+        assert_eq!(
+            parse_identifier("urn:isbn:0-316-48163-7"),
+            isbn("9780316481632")
+        );
+        assert_eq!(parse_identifier("978-0-14-118260-6"), None);
+        assert_eq!(parse_identifier("1234567890"), None);
+    }
+
+    #[test]
+    fn identifiers_as_stored_are_parsed_back() {
+        for identifier in [
+            Identifier::Doi(String::from("10.1145/3297858.3304013")),
+            Identifier::Arxiv(String::from("hep-th/9901001")),
+            Identifier::Isbn(String::from("9780141182605")),
+        ] {
+            assert_eq!(
+                parse_identifier(&identifier.to_string()),
+                Some(identifier.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn text_that_is_no_identifier() {
+        assert_eq!(parse_identifier("Bonnie and Clyde"), None);
+        assert_eq!(
+            parse_identifier("https://example.com/10.1145/3297858"),
+            None
+        );
+        assert_eq!(parse_identifier(""), None);
+    }
+
+    // identifier_in_link
+
+    #[test]
+    fn dois_in_links() {
+        assert_eq!(
+            identifier_in_link("https://doi.org/10.1145/3297858.3304013"),
+            doi("10.1145/3297858.3304013")
+        );
+        assert_eq!(
+            identifier_in_link("http://dx.doi.org/10.1007%2Fs00453-019-00634-0"),
+            doi("10.1007/s00453-019-00634-0")
+        );
+        assert_eq!(
+            identifier_in_link("https://dl.acm.org/doi/10.1145/3297858.3304013"),
+            doi("10.1145/3297858.3304013")
+        );
+        assert_eq!(
+            identifier_in_link("https://onlinelibrary.wiley.com/doi/full/10.1002/asi.24750?af=R"),
+            doi("10.1002/asi.24750")
+        );
+    }
+
+    #[test]
+    fn arxiv_ids_in_links() {
+        assert_eq!(
+            identifier_in_link("https://arxiv.org/abs/1706.03762v7"),
+            arxiv("1706.03762")
+        );
+        assert_eq!(
+            identifier_in_link("https://arxiv.org/pdf/1706.03762.pdf"),
+            arxiv("1706.03762")
+        );
+        assert_eq!(
+            identifier_in_link("http://export.arxiv.org/abs/hep-th/9901001"),
+            arxiv("hep-th/9901001")
+        );
+        assert_eq!(
+            identifier_in_link("https://arxiv.org/list/cs.CL/recent"),
+            None
+        );
+    }
+
+    #[test]
+    fn isbns_in_links() {
+        assert_eq!(
+            identifier_in_link("https://openlibrary.org/isbn/0141182601"),
+            isbn("9780141182605")
+        );
+        assert_eq!(
+            identifier_in_link("https://www.amazon.com/Bonnie-Clyde/dp/0141182601/ref=sr_1_1"),
+            isbn("9780141182605")
+        );
+        assert_eq!(
+            identifier_in_link("https://books.example.com/search?q=kael&ISBN=978-0-14-118260-5"),
+            isbn("9780141182605")
+        );
+        assert_eq!(
+            identifier_in_link("https://www.amazon.com/dp/B00005JNOG"),
+            None
+        );
+    }
+
+    #[test]
+    fn links_without_identifiers() {
+        assert_eq!(
+            identifier_in_link("https://example.com/bonnie-and-clyde"),
+            None
+        );
+        assert_eq!(identifier_in_link("10.1145/3297858.3304013"), None);
+        assert_eq!(identifier_in_link("https://example.com/doi/"), None);
+    }
+}
@@ -27,7 +27,7 @@ fn scaled_width_and_height(
 }
 
 // Return the image in png format, with 8 bit rgba component pixels (32 bits per pixel), and size 300x169
-pub fn normalize_image(image_bytes: hyper::body::Bytes) -> image::ImageResult<Vec<u8>> {
+pub fn normalize_image(image_bytes: &[u8]) -> image::ImageResult<Vec<u8>> {
     let image_reader = image::io::Reader::new(std::io::Cursor::new(image_bytes))
         .with_guessed_format()
         .expect("Cursor IO never fails.");
@@ -59,3 +59,35 @@ pub fn normalize_image(image_bytes: hyper::body::Bytes) -> image::ImageResult<Ve
 
     Ok(png_encoded_image)
 }
+
+// Return the favicon in png format, with 8 bit rgba component pixels, and size 32x32. Icons that are not square are
+// scaled to fit and centered on a transparent background. Of the images of an ico file, the largest one is used.
+pub fn normalize_favicon(image_bytes: &[u8]) -> image::ImageResult<Vec<u8>> {
+    let image_reader = image::io::Reader::new(std::io::Cursor::new(image_bytes))
+        .with_guessed_format()
+        .expect("Cursor IO never fails.");
+
+    let image = image_reader.decode()?;
+
+    let resized_image = image
+        .resize(32, 32, imageops::FilterType::CatmullRom)
+        .to_rgba8();
+    let (width, height) = resized_image.dimensions();
+
+    let mut square_image = image::RgbaImage::new(32, 32);
+    imageops::overlay(
+        &mut square_image,
+        &resized_image,
+        ((32 - width) / 2) as i64,
+        ((32 - height) / 2) as i64,
+    );
+
+    let mut png_encoded_image = Vec::new();
+
+    square_image.write_to(
+        &mut std::io::Cursor::new(&mut png_encoded_image),
+        image::ImageOutputFormat::Png,
+    )?;
+
+    Ok(png_encoded_image)
+}
@@ -0,0 +1,407 @@
+// Metadata of pages described with schema.org types in <script type="application/ld+json"> blocks, which is where
+// many sites put what they leave out of their meta tags.
+
+use crate::date::{self, Date};
+use crate::entry_type::EntryType;
+use crate::html::{attribute, Token, Tokenizer};
+use crate::html_meta::PageMetadata;
+use crate::identifiers::identifier_of;
+
+use serde_json::Value;
+
+// Parsed contents of every JSON-LD script of the page, skipping those that are not valid JSON.
+pub fn json_ld_blocks(html_source: &str) -> Vec<Value> {
+    let mut blocks = Vec::new();
+    let mut in_json_ld = false;
+    for token in Tokenizer::new(html_source) {
+        match token {
+            Token::StartTag { name, attributes } if name == "script" => {
+                in_json_ld = attribute(&attributes, "type")
+                    .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("application/ld+json"));
+            }
+            Token::Text(text) if in_json_ld => {
+                if let Ok(block) = serde_json::from_str(text.trim()) {
+                    blocks.push(block);
+                }
+            }
+            _ => in_json_ld = false,
+        }
+    }
+    blocks
+}
+
+// Every object of the blocks that has a type, in the order of the page. Lists and @graph are flattened.
+fn typed_objects(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(values) => values.iter().flat_map(typed_objects).collect(),
+        Value::Object(object) => {
+            let mut objects = Vec::new();
+            if object.contains_key("@type") {
+                objects.push(value);
+            }
+            if let Some(graph) = object.get("@graph") {
+                objects.extend(typed_objects(graph));
+            }
+            objects
+        }
+        _ => Vec::new(),
+    }
+}
+
+// Types of the object without their vocabulary, so that "Article", "schema:Article" and
+// "https://schema.org/Article" are all "Article".
+fn types_of(object: &Value) -> Vec<&str> {
+    fn name(kind: &Value) -> Option<&str> {
+        let kind = kind.as_str()?;
+        kind.rsplit(['/', ':']).next()
+    }
+    match &object["@type"] {
+        Value::Array(kinds) => kinds.iter().filter_map(name).collect(),
+        kind => name(kind).into_iter().collect(),
+    }
+}
+
+// The entry type that a schema.org type describes, with zero as its metadata.
+fn entry_type_of(kind: &str) -> Option<EntryType> {
+    match kind {
+        "Article" | "NewsArticle" | "BlogPosting" => Some(EntryType::Article { words: 0 }),
+        "ScholarlyArticle" => Some(EntryType::Paper { pages: 0 }),
+        "Book" => Some(EntryType::Book { pages: 0 }),
+        "VideoObject" => Some(EntryType::Video {
+            length_in_seconds: 0,
+        }),
+        "PodcastEpisode" => Some(EntryType::Audio {
+            length_in_seconds: 0,
+        }),
+        _ => None,
+    }
+}
+
+fn text(value: &Value) -> Option<String> {
+    value
+        .as_str()
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|text| !text.is_empty())
+}
+
+// Numbers are sometimes written as strings.
+fn number(value: &Value) -> Option<i32> {
+    match value {
+        Value::Number(number) => number.as_f64().map(|number| number as i32),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+// Names of people and organizations, which may be given as text, objects with a name, or lists of either.
+fn names(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(values) => values.iter().flat_map(names).collect(),
+        Value::Object(object) => object.get("name").and_then(text).into_iter().collect(),
+        value => text(value).into_iter().collect(),
+    }
+}
+
+// Link of an image, which may be given as text, an ImageObject, or a list of either.
+fn image_link(value: &Value) -> Option<String> {
+    match value {
+        Value::Array(values) => values.iter().find_map(image_link),
+        Value::Object(object) => object
+            .get("url")
+            .or(object.get("contentUrl"))
+            .and_then(text),
+        value => text(value),
+    }
+}
+
+// Dates like 2016-06-01 or 2016-06-01T10:00:00+02:00, ignoring the time.
+fn date(value: &Value) -> Option<Date> {
+    let text = text(value)?;
+    date::read_sql_date(text.split('T').next().unwrap_or_default())
+        .filter(|date| date::is_valid(*date))
+}
+
+// ISO 8601 durations like PT1H2M3S or P1DT30M, in seconds. Plain numbers are taken to be seconds.
+pub fn parse_duration(text: &str) -> Option<i32> {
+    let text = text.trim();
+    if let Ok(seconds) = text.parse::<f64>() {
+        return Some(seconds as i32);
+    }
+
+    let mut seconds = 0.0;
+    let mut in_time = false;
+    let mut number = String::new();
+    for c in text.strip_prefix(['P', 'p'])?.chars() {
+        match c.to_ascii_uppercase() {
+            'T' if number.is_empty() => in_time = true,
+            '0'..='9' | '.' | ',' => number.push(if c == ',' { '.' } else { c }),
+            unit => {
+                let value: f64 = number.parse().ok()?;
+                number.clear();
+                seconds += value
+                    * match (unit, in_time) {
+                        ('W', false) => 604800.0,
+                        ('D', false) => 86400.0,
+                        ('H', true) => 3600.0,
+                        ('M', true) => 60.0,
+                        ('S', true) => 1.0,
+                        _ => return None,
+                    };
+            }
+        }
+    }
+    number.is_empty().then_some(seconds as i32)
+}
+
+fn duration(value: &Value) -> Option<i32> {
+    match value {
+        Value::Number(_) => number(value),
+        value => parse_duration(value.as_str()?),
+    }
+}
+
+// Page count of a paper from its numberOfPages or its pagination, like "112-131".
+fn pages(object: &Value) -> Option<i32> {
+    number(&object["numberOfPages"]).or_else(|| {
+        let pagination = object["pagination"].as_str()?;
+        let (start, end) = pagination.split_once(['-', '–'])?;
+        let (start, end): (i32, i32) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+        (end >= start).then_some(end - start + 1)
+    })
+}
+
+fn metadata_of(object: &Value, entry_type: EntryType) -> PageMetadata {
+    let entry_type = match entry_type {
+        EntryType::Article { .. } => EntryType::Article {
+            words: number(&object["wordCount"]).unwrap_or(0),
+        },
+        EntryType::Paper { .. } => EntryType::Paper {
+            pages: pages(object).unwrap_or(0),
+        },
+        EntryType::Book { .. } => EntryType::Book {
+            pages: number(&object["numberOfPages"]).unwrap_or(0),
+        },
+        EntryType::Video { .. } => EntryType::Video {
+            length_in_seconds: duration(&object["duration"]).unwrap_or(0),
+        },
+        // Podcast episodes usually give their duration in their audio instead of themselves.
+        EntryType::Audio { .. } => EntryType::Audio {
+            length_in_seconds: ["duration", "timeRequired"]
+                .iter()
+                .find_map(|field| duration(&object[field]))
+                .or_else(|| duration(&object["associatedMedia"]["duration"]))
+                .or_else(|| duration(&object["audio"]["duration"]))
+                .unwrap_or(0),
+        },
+    };
+
+    PageMetadata {
+        title: text(&object["headline"]).or_else(|| text(&object["name"])),
+        description: text(&object["description"]),
+        authors: names(&object["author"]),
+        date_published: ["datePublished", "uploadDate", "dateCreated"]
+            .iter()
+            .find_map(|field| date(&object[field])),
+        entry_type: Some(entry_type),
+        image: image_link(&object["image"]).or_else(|| image_link(&object["thumbnailUrl"])),
+        canonical_link: text(&object["url"]),
+        // The identifier is a text or a PropertyValue like {"propertyID": "DOI", "value": "10.1145/3297858.3304013"}.
+        identifier: [
+            &object["isbn"],
+            &object["identifier"],
+            &object["identifier"]["value"],
+        ]
+        .iter()
+        .find_map(|value| identifier_of(&text(value)?))
+        .map(|identifier| identifier.to_string()),
+    }
+}
+
+// Metadata of the first object of the page with one of the types that entries can be, if any.
+pub fn json_ld_metadata(html_source: &str) -> Option<PageMetadata> {
+    let blocks = json_ld_blocks(html_source);
+    blocks.iter().flat_map(typed_objects).find_map(|object| {
+        let entry_type = types_of(object).into_iter().find_map(entry_type_of)?;
+        Some(metadata_of(object, entry_type))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::Month;
+
+    fn script(json: &str) -> String {
+        format!(
+            r#"<html><head><script type="application/ld+json">{}</script></head></html>"#,
+            json
+        )
+    }
+
+    // json_ld_blocks
+
+    #[test]
+    fn only_valid_json_ld_scripts_are_read() {
+        let html = r#"
+            <script>{"@type": "Article"}</script>
+            <script type="application/ld+json">{"@type": "NewsArticle"}</script>
+            <script type="application/ld+json">{"@type": </script>
+            <script TYPE="Application/LD+JSON">[{"@type": "Person"}]</script>
+        "#;
+        assert_eq!(
+            json_ld_blocks(html),
+            [
+                serde_json::json!({"@type": "NewsArticle"}),
+                serde_json::json!([{"@type": "Person"}])
+            ]
+        );
+    }
+
+    // parse_duration
+
+    #[test]
+    fn durations_in_every_format() {
+        assert_eq!(parse_duration("PT12M34S"), Some(754));
+        assert_eq!(parse_duration("PT1H"), Some(3600));
+        assert_eq!(parse_duration("P1DT30M"), Some(88200));
+        assert_eq!(parse_duration("PT1.5S"), Some(1));
+        assert_eq!(parse_duration("754"), Some(754));
+        assert_eq!(parse_duration("PT12"), None);
+        assert_eq!(parse_duration("P1H"), None);
+        assert_eq!(parse_duration("12 minutes"), None);
+    }
+
+    // json_ld_metadata
+
+    #[test]
+    fn metadata_of_a_news_article_in_a_graph() {
+        let html = script(
+            r#"{
+                "@context": "https://schema.org",
+                "@graph": [
+                    {"@type": "WebSite", "name": "El País"},
+                    {
+                        "@type": ["NewsArticle", "Article"],
+                        "headline": "Crítica: «Bonnie y Clyde»",
+                        "name": "Bonnie y Clyde",
+                        "author": [{"@type": "Person", "name": "Carlos Boyero"}, "Elsa Fernández"],
+                        "datePublished": "2017-08-13T22:00:00+02:00",
+                        "wordCount": "1250",
+                        "image": [{"@type": "ImageObject", "url": "https://elpais.com/bonnie.jpg"}]
+                    }
+                ]
+            }"#,
+        );
+
+        assert_eq!(
+            json_ld_metadata(&html),
+            Some(PageMetadata {
+                title: Some(String::from("Crítica: «Bonnie y Clyde»")),
+                description: None,
+                authors: vec![
+                    String::from("Carlos Boyero"),
+                    String::from("Elsa Fernández")
+                ],
+                date_published: Some(Date {
+                    day: 13,
+                    month: Month::August,
+                    year: 2017
+                }),
+                entry_type: Some(EntryType::Article { words: 1250 }),
+                image: Some(String::from("https://elpais.com/bonnie.jpg")),
+                canonical_link: None,
+                identifier: None,
+            })
+        );
+    }
+
+    #[test]
+    fn identifiers_of_books_and_papers() {
+        let identifier = |json: &str| json_ld_metadata(&script(json)).unwrap().identifier;
+
+        assert_eq!(
+            identifier(
+                r#"{"@type": "Book", "name": "Kiss Kiss Bang Bang", "isbn": "0-316-48163-7"}"#
+            ),
+            Some(String::from("isbn:9780316481632"))
+        );
+        assert_eq!(
+            identifier(
+                r#"{"@type": "ScholarlyArticle", "identifier": {"@type": "PropertyValue", "propertyID": "DOI", "value": "10.2307/1210357"}}"#
+            ),
+            Some(String::from("doi:10.2307/1210357"))
+        );
+        assert_eq!(
+            identifier(
+                r#"{"@type": "ScholarlyArticle", "identifier": "https://arxiv.org/abs/1706.03762"}"#
+            ),
+            Some(String::from("arxiv:1706.03762"))
+        );
+        assert_eq!(
+            identifier(r#"{"@type": "Article", "identifier": "1234"}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn entry_types_and_their_metadata() {
+        let entry_type = |json: &str| json_ld_metadata(&script(json)).unwrap().entry_type;
+
+        assert_eq!(
+            entry_type(r#"{"@type": "BlogPosting"}"#),
+            Some(EntryType::Article { words: 0 })
+        );
+        assert_eq!(
+            entry_type(r#"{"@type": "ScholarlyArticle", "pagination": "112-131"}"#),
+            Some(EntryType::Paper { pages: 20 })
+        );
+        assert_eq!(
+            entry_type(r#"{"@type": "http://schema.org/Book", "numberOfPages": 356}"#),
+            Some(EntryType::Book { pages: 356 })
+        );
+        assert_eq!(
+            entry_type(r#"{"@type": "VideoObject", "duration": "PT12M34S"}"#),
+            Some(EntryType::Video {
+                length_in_seconds: 754
+            })
+        );
+        assert_eq!(
+            entry_type(
+                r#"{"@type": "PodcastEpisode", "associatedMedia": {"@type": "MediaObject", "duration": "PT1H2M"}}"#
+            ),
+            Some(EntryType::Audio {
+                length_in_seconds: 3720
+            })
+        );
+    }
+
+    #[test]
+    fn videos_are_dated_by_their_upload() {
+        let metadata = json_ld_metadata(&script(
+            r#"{"@type": "VideoObject", "name": "Arthur Penn", "uploadDate": "2011-03-02", "thumbnailUrl": ["https://i.ytimg.com/1.jpg"]}"#,
+        ))
+        .unwrap();
+
+        assert_eq!(metadata.title.as_deref(), Some("Arthur Penn"));
+        assert_eq!(
+            metadata.date_published,
+            Some(Date {
+                day: 2,
+                month: Month::March,
+                year: 2011
+            })
+        );
+        assert_eq!(metadata.image.as_deref(), Some("https://i.ytimg.com/1.jpg"));
+    }
+
+    #[test]
+    fn pages_without_entry_types_have_no_metadata() {
+        assert_eq!(
+            json_ld_metadata(&script(
+                r#"{"@type": "Organization", "name": "The New Yorker"}"#
+            )),
+            None
+        );
+        assert_eq!(json_ld_metadata("<html></html>"), None);
+    }
+}
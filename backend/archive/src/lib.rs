@@ -0,0 +1,32 @@
+// Core of the archive: the entries and their metadata, how they are stored in the database and searched, and
+// how metadata and images are extracted from the web. The HTTP server and the command line tool are built on it.
+
+pub mod bulk;
+pub mod charset;
+pub mod config;
+pub mod database;
+pub mod date;
+pub mod duplicates;
+pub mod entry;
+pub mod entry_type;
+pub mod history;
+pub mod html;
+pub mod html_meta;
+pub mod identifiers;
+pub mod images;
+pub mod json_ld;
+pub mod links;
+pub mod media;
+pub mod merge_patch;
+pub mod oembed;
+pub mod page_entry;
+pub mod pdf;
+pub mod readability;
+pub mod resolvers;
+pub mod sites;
+pub mod sql_array;
+pub mod suggestion;
+pub mod taxonomy;
+pub mod url_to_sql_query;
+pub mod validation;
+pub mod word_counts;
@@ -0,0 +1,150 @@
+// Canonical form of links, so that different ways of writing the link to the same page compare equal.
+
+// Query parameters that only track where a visit came from and don't change the page.
+const TRACKING_PARAMETERS: [&str; 7] = [
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid",
+];
+
+fn is_tracking_parameter(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMETERS.contains(&name.as_str())
+}
+
+// The link without scheme, www., fragment, tracking parameters or trailing slash, and with the host in lowercase.
+// http://www.Example.com/article/?utm_source=feed#comments becomes example.com/article.
+pub fn canonical_link(link: &str) -> String {
+    let link = link.trim();
+
+    let without_scheme = match link.find("://") {
+        Some(end) => &link[end + 3..],
+        None => link,
+    };
+    let without_fragment = match without_scheme.find('#') {
+        Some(start) => &without_scheme[..start],
+        None => without_scheme,
+    };
+
+    let (host_and_path, query) = match without_fragment.find('?') {
+        Some(start) => (&without_fragment[..start], &without_fragment[start + 1..]),
+        None => (without_fragment, ""),
+    };
+    let (host, path) = match host_and_path.find('/') {
+        Some(start) => (&host_and_path[..start], &host_and_path[start..]),
+        None => (host_and_path, ""),
+    };
+
+    let host = host.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let path = path.trim_end_matches('/');
+    let query = query
+        .split('&')
+        .filter(|parameter| {
+            let name = parameter.split('=').next().unwrap_or_default();
+            !name.is_empty() && !is_tracking_parameter(name)
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+
+    if query.is_empty() {
+        format!("{}{}", host, path)
+    } else {
+        format!("{}{}?{}", host, path, query)
+    }
+}
+
+// The host of the canonical link of a web page, like example.com for https://www.Example.com/article. None for links
+// that are not http or https urls, like the ones of books that are not online.
+pub fn canonical_domain(link: &str) -> Option<String> {
+    let link = link.trim();
+    let scheme_end = link.find("://")?;
+    if !["http", "https"]
+        .iter()
+        .any(|scheme| link[..scheme_end].eq_ignore_ascii_case(scheme))
+    {
+        return None;
+    }
+
+    let canonical = canonical_link(link);
+    let domain = canonical.split(['/', '?']).next().unwrap_or_default();
+    (!domain.is_empty()).then(|| String::from(domain))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // canonical_link
+
+    #[test]
+    fn canonical_link_ignores_scheme_and_www() {
+        assert_eq!(canonical_link("https://example.com/a"), "example.com/a");
+        assert_eq!(canonical_link("http://www.example.com/a"), "example.com/a");
+        assert_eq!(canonical_link("www.example.com/a"), "example.com/a");
+    }
+
+    #[test]
+    fn canonical_link_lowercases_only_the_host() {
+        assert_eq!(
+            canonical_link("https://WWW.Example.COM/Some/Path"),
+            "example.com/Some/Path"
+        );
+    }
+
+    #[test]
+    fn canonical_link_removes_trailing_slash_and_fragment() {
+        assert_eq!(canonical_link("https://example.com/a/"), "example.com/a");
+        assert_eq!(canonical_link("https://example.com/"), "example.com");
+        assert_eq!(
+            canonical_link("https://example.com/a#comments"),
+            "example.com/a"
+        );
+    }
+
+    #[test]
+    fn canonical_link_removes_tracking_parameters() {
+        assert_eq!(
+            canonical_link("https://example.com/a?utm_source=feed&utm_medium=rss"),
+            "example.com/a"
+        );
+        assert_eq!(
+            canonical_link("https://example.com/watch?v=5&fbclid=abc&UTM_campaign=x"),
+            "example.com/watch?v=5"
+        );
+        assert_eq!(
+            canonical_link("https://example.com/a/?id=1&page=2#top"),
+            "example.com/a?id=1&page=2"
+        );
+    }
+
+    #[test]
+    fn canonical_link_of_text_that_is_not_a_url() {
+        assert_eq!(canonical_link("  Some book  "), "some book");
+        assert_eq!(canonical_link(""), "");
+    }
+
+    // canonical_domain
+
+    #[test]
+    fn canonical_domain_of_web_pages() {
+        assert_eq!(
+            canonical_domain("https://www.Example.com/article/?id=1"),
+            Some(String::from("example.com"))
+        );
+        assert_eq!(
+            canonical_domain("HTTP://blog.example.com?page=2"),
+            Some(String::from("blog.example.com"))
+        );
+        assert_eq!(
+            canonical_domain("http://127.0.0.1:8080/a"),
+            Some(String::from("127.0.0.1:8080"))
+        );
+    }
+
+    #[test]
+    fn canonical_domain_of_links_that_are_not_web_pages() {
+        assert_eq!(canonical_domain("Some book"), None);
+        assert_eq!(canonical_domain("www.example.com/a"), None);
+        assert_eq!(canonical_domain("ftp://example.com/a"), None);
+        assert_eq!(canonical_domain("https://"), None);
+    }
+}
@@ -0,0 +1,1083 @@
+// Length and tags of audio and video files, read from the headers of their containers: MP3 with its ID3 tags, MP4
+// and M4A, Ogg with Vorbis or Opus, and WebM or any other Matroska file.
+
+use crate::entry_type::EntryType;
+use crate::html_meta::PageMetadata;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Container {
+    Mp3,
+    Mp4,
+    Ogg,
+    Webm,
+}
+
+#[derive(Serialize, ToSchema, Debug, PartialEq, Eq)]
+pub struct MediaMetadata {
+    pub container: Container,
+    pub has_video: bool,
+    // Rounded to the nearest second. Unknown for files that don't say, like WebM recorded live.
+    pub length_in_seconds: Option<i32>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+}
+
+// Big-endian unsigned integer of the given number of bytes at the position.
+fn be(bytes: &[u8], at: usize, length: usize) -> Option<u64> {
+    let field = bytes.get(at..at.checked_add(length)?)?;
+    Some(field.iter().fold(0, |n, byte| n << 8 | u64::from(*byte)))
+}
+
+// Little-endian unsigned integer of the given number of bytes at the position.
+fn le(bytes: &[u8], at: usize, length: usize) -> Option<u64> {
+    let field = bytes.get(at..at.checked_add(length)?)?;
+    Some(
+        field
+            .iter()
+            .rev()
+            .fold(0, |n, byte| n << 8 | u64::from(*byte)),
+    )
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| char::from(*byte)).collect()
+}
+
+fn utf16(bytes: &[u8], big_endian: bool) -> String {
+    let units = bytes.chunks_exact(2).map(|unit| {
+        if big_endian {
+            u16::from_be_bytes([unit[0], unit[1]])
+        } else {
+            u16::from_le_bytes([unit[0], unit[1]])
+        }
+    });
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+// Value of a tag, without the padding and byte order marks that are left around it.
+fn tag_value(text: &str) -> Option<String> {
+    let text = text.trim_matches(|c: char| c == '\0' || c == '\u{FEFF}' || c.is_whitespace());
+    (!text.is_empty()).then(|| String::from(text))
+}
+
+fn rounded_seconds(seconds: f64) -> Option<i32> {
+    (seconds.is_finite() && seconds >= 0.0 && seconds < f64::from(i32::MAX))
+        .then(|| seconds.round() as i32)
+}
+
+// MP3
+
+// Bit rates in kbit/s of MPEG-1 layers I, II and III, by the index in the frame header.
+const MPEG1_BIT_RATES: [[u32; 15]; 3] = [
+    [
+        0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+    ],
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+    ],
+    [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ],
+];
+
+// Bit rates in kbit/s of MPEG-2 and 2.5 layer I, then layers II and III.
+const MPEG2_BIT_RATES: [[u32; 15]; 2] = [
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+    ],
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+
+// Sample rates of MPEG-1. MPEG-2 halves them and MPEG-2.5 quarters them.
+const MPEG1_SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+
+// How far past the ID3 tag to look for the first frame, for files with junk before it.
+const FRAME_SEARCH_LENGTH: usize = 64 * 1024;
+
+struct FrameHeader {
+    mpeg1: bool,
+    mono: bool,
+    bit_rate: u32,
+    sample_rate: u32,
+    samples: u32,
+    length: usize,
+}
+
+fn frame_header(bytes: &[u8], at: usize) -> Option<FrameHeader> {
+    let header = bytes.get(at..at.checked_add(4)?)?;
+    if header[0] != 0xFF || header[1] & 0xE0 != 0xE0 {
+        return None;
+    }
+    let version = (header[1] >> 3) & 3;
+    let layer = match (header[1] >> 1) & 3 {
+        3 => 1,
+        2 => 2,
+        1 => 3,
+        _ => return None,
+    };
+    let bit_rate_index = usize::from(header[2] >> 4);
+    let sample_rate_index = usize::from((header[2] >> 2) & 3);
+    if version == 1 || bit_rate_index == 0 || bit_rate_index == 15 || sample_rate_index == 3 {
+        return None;
+    }
+
+    let mpeg1 = version == 3;
+    let bit_rate = 1000
+        * if mpeg1 {
+            MPEG1_BIT_RATES[layer - 1][bit_rate_index]
+        } else {
+            MPEG2_BIT_RATES[usize::from(layer != 1)][bit_rate_index]
+        };
+    let sample_rate = match version {
+        3 => MPEG1_SAMPLE_RATES[sample_rate_index],
+        2 => MPEG1_SAMPLE_RATES[sample_rate_index] / 2,
+        _ => MPEG1_SAMPLE_RATES[sample_rate_index] / 4,
+    };
+    let samples = match layer {
+        1 => 384,
+        3 if !mpeg1 => 576,
+        _ => 1152,
+    };
+    let padding = usize::from((header[2] >> 1) & 1);
+    let length = if layer == 1 {
+        (12 * bit_rate / sample_rate) as usize * 4 + padding * 4
+    } else {
+        (samples / 8 * bit_rate / sample_rate) as usize + padding
+    };
+    Some(FrameHeader {
+        mpeg1,
+        mono: header[3] >> 6 == 3,
+        bit_rate,
+        sample_rate,
+        samples,
+        length,
+    })
+}
+
+// Position and header of the first frame at or after the position, which must be followed by another frame or the
+// end of the file, so that bytes that happen to look like a header are not taken for one.
+fn first_frame(bytes: &[u8], from: usize) -> Option<(usize, FrameHeader)> {
+    let end = bytes.len().min(from.saturating_add(FRAME_SEARCH_LENGTH));
+    (from..end).find_map(|at| {
+        let header = frame_header(bytes, at)?;
+        let next = at + header.length;
+        (next >= bytes.len() || frame_header(bytes, next).is_some()).then_some((at, header))
+    })
+}
+
+// Number of frames written by encoders of variable bit rate files in a Xing, Info or VBRI header in the first frame.
+fn frame_count(bytes: &[u8], at: usize, header: &FrameHeader) -> Option<u64> {
+    let side_information = match (header.mpeg1, header.mono) {
+        (true, false) => 32,
+        (true, true) | (false, false) => 17,
+        (false, true) => 9,
+    };
+    let xing = at + 4 + side_information;
+    match bytes.get(xing..xing + 4) {
+        Some(b"Xing") | Some(b"Info") if be(bytes, xing + 4, 4)? & 1 == 1 => be(bytes, xing + 8, 4),
+        _ => {
+            let vbri = at + 4 + 32;
+            (bytes.get(vbri..vbri + 4)? == b"VBRI").then(|| be(bytes, vbri + 14, 4))?
+        }
+    }
+}
+
+// Text of an ID3v2 text frame, whose first byte says how it is encoded. Values are separated by nulls.
+fn id3_text(frame: &[u8]) -> Option<String> {
+    let (encoding, text) = frame.split_first()?;
+    let text = match encoding {
+        0 => latin1(text),
+        1 => match text {
+            [0xFF, 0xFE, ..] => utf16(text, false),
+            _ => utf16(text, true),
+        },
+        2 => utf16(text, true),
+        _ => String::from_utf8_lossy(text).into_owned(),
+    };
+    tag_value(
+        &text
+            .split('\0')
+            .filter_map(tag_value)
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
+
+#[derive(Default)]
+struct Tags {
+    title: Option<String>,
+    artist: Option<String>,
+}
+
+// Title and artist of the ID3v2 tag at the start of the file, and where the tag ends.
+fn id3v2_tags(bytes: &[u8]) -> (usize, Tags) {
+    let mut tags = Tags::default();
+    if !bytes.starts_with(b"ID3") || bytes.len() < 10 {
+        return (0, tags);
+    }
+    let major = bytes[3];
+    let flags = bytes[5];
+    let syncsafe = |at| {
+        bytes.get(at..at + 4).map(|size: &[u8]| {
+            size.iter()
+                .fold(0, |n, byte| n << 7 | usize::from(byte & 0x7F))
+        })
+    };
+    let size = syncsafe(6).unwrap_or(0);
+    let end = 10 + size + if flags & 0x10 != 0 { 10 } else { 0 };
+    let tag = &bytes[10..bytes.len().min(10 + size)];
+
+    let (id_length, header_length) = if major == 2 { (3, 6) } else { (4, 10) };
+    let mut at = match (major, flags & 0x40 != 0) {
+        (3, true) => be(tag, 0, 4).unwrap_or(0) as usize + 4,
+        (4, true) => syncsafe(10).unwrap_or(0),
+        _ => 0,
+    };
+    while let Some(id) = tag.get(at..at + id_length) {
+        if id[0] == 0 {
+            break;
+        }
+        let frame_size = match major {
+            2 => be(tag, at + 3, 3).map(|size| size as usize),
+            3 => be(tag, at + 4, 4).map(|size| size as usize),
+            _ => tag.get(at + 4..at + 8).map(|size| {
+                size.iter()
+                    .fold(0, |n, byte| n << 7 | usize::from(byte & 0x7F))
+            }),
+        };
+        let Some(frame_size) = frame_size else { break };
+        let start = at + header_length;
+        let frame = &tag[start.min(tag.len())..(start + frame_size).min(tag.len())];
+        match id {
+            b"TIT2" | b"TT2" => tags.title = tags.title.take().or_else(|| id3_text(frame)),
+            b"TPE1" | b"TP1" => tags.artist = tags.artist.take().or_else(|| id3_text(frame)),
+            _ => {}
+        }
+        at = start + frame_size;
+    }
+    (end, tags)
+}
+
+fn mp3_metadata(bytes: &[u8]) -> Result<MediaMetadata, String> {
+    let (tag_end, mut tags) = id3v2_tags(bytes);
+    let (at, header) = first_frame(bytes, tag_end)
+        .ok_or_else(|| String::from("No MPEG audio frames were found"))?;
+
+    // The old ID3v1 tag is the last 128 bytes of the file.
+    let mut end = bytes.len();
+    if let Some(id3v1) = bytes.len().checked_sub(128).map(|start| &bytes[start..]) {
+        if id3v1.starts_with(b"TAG") {
+            end -= 128;
+            tags.title = tags.title.or_else(|| tag_value(&latin1(&id3v1[3..33])));
+            tags.artist = tags.artist.or_else(|| tag_value(&latin1(&id3v1[33..63])));
+        }
+    }
+
+    // Files of constant bit rate have no frame count, but the same number of bytes every second.
+    let seconds = match frame_count(bytes, at, &header) {
+        Some(frames) => frames as f64 * f64::from(header.samples) / f64::from(header.sample_rate),
+        None => end.saturating_sub(at) as f64 * 8.0 / f64::from(header.bit_rate),
+    };
+    Ok(MediaMetadata {
+        container: Container::Mp3,
+        has_video: false,
+        length_in_seconds: rounded_seconds(seconds),
+        title: tags.title,
+        artist: tags.artist,
+    })
+}
+
+// MP4
+
+// Boxes of an MP4 file or of the content of another box, as their type and content.
+fn mp4_boxes(bytes: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut boxes = Vec::new();
+    let mut at = 0;
+    while let (Some(size), Some(kind)) = (be(bytes, at, 4), bytes.get(at + 4..at + 8)) {
+        let (header_length, size) = match size {
+            0 => (8, (bytes.len() - at) as u64),
+            1 => match be(bytes, at + 8, 8) {
+                Some(size) => (16, size),
+                None => break,
+            },
+            size => (8, size),
+        };
+        if size < header_length as u64 {
+            break;
+        }
+        let end = usize::try_from(size)
+            .ok()
+            .and_then(|size| at.checked_add(size))
+            .map_or(bytes.len(), |end| end.min(bytes.len()));
+        boxes.push((kind, &bytes[at + header_length..end]));
+        at = end;
+    }
+    boxes
+}
+
+// Content of the box at the path of box types.
+fn mp4_box<'a>(bytes: &'a [u8], path: &[&[u8]]) -> Option<&'a [u8]> {
+    path.iter().try_fold(bytes, |content, kind| {
+        mp4_boxes(content)
+            .into_iter()
+            .find(|(found, _)| found == kind)
+            .map(|(_, content)| content)
+    })
+}
+
+// Text of an item of the iTunes metadata list, which is in the data box inside it.
+fn mp4_item_text(item: &[u8]) -> Option<String> {
+    let data = mp4_box(item, &[b"data"])?;
+    tag_value(&String::from_utf8_lossy(data.get(8..)?))
+}
+
+fn mp4_metadata(bytes: &[u8]) -> Result<MediaMetadata, String> {
+    let movie =
+        mp4_box(bytes, &[b"moov"]).ok_or_else(|| String::from("The file has no movie box"))?;
+
+    let seconds = mp4_box(movie, &[b"mvhd"]).and_then(|header| {
+        let (time_scale, duration) = match header.first()? {
+            1 => (be(header, 20, 4)?, be(header, 24, 8)?),
+            _ => (
+                be(header, 12, 4)?,
+                be(header, 16, 4).filter(|d| *d != 0xFFFF_FFFF)?,
+            ),
+        };
+        (time_scale != 0).then(|| duration as f64 / time_scale as f64)
+    });
+    let has_video = mp4_boxes(movie)
+        .into_iter()
+        .filter(|(kind, _)| *kind == b"trak")
+        .filter_map(|(_, track)| mp4_box(track, &[b"mdia", b"hdlr"]))
+        .any(|handler| handler.get(8..12) == Some(b"vide"));
+
+    // The meta box has a version and flags before its children, except in some files written by QuickTime.
+    let items = mp4_box(movie, &[b"udta", b"meta"])
+        .and_then(|meta| match meta.get(4..8) {
+            Some(b"hdlr") => Some(meta),
+            _ => meta.get(4..),
+        })
+        .and_then(|meta| mp4_box(meta, &[b"ilst"]))
+        .map(mp4_boxes)
+        .unwrap_or_default();
+    let item = |name: &[u8]| {
+        items
+            .iter()
+            .find(|(kind, _)| *kind == name)
+            .and_then(|(_, item)| mp4_item_text(item))
+    };
+
+    Ok(MediaMetadata {
+        container: Container::Mp4,
+        has_video,
+        length_in_seconds: seconds.and_then(rounded_seconds),
+        title: item(b"\xA9nam"),
+        artist: item(b"\xA9ART"),
+    })
+}
+
+// Ogg
+
+struct OggPage<'a> {
+    beginning_of_stream: bool,
+    granule_position: u64,
+    serial: u32,
+    segments: &'a [u8],
+    data: &'a [u8],
+    length: usize,
+}
+
+fn ogg_page(bytes: &[u8], at: usize) -> Option<OggPage<'_>> {
+    if bytes.get(at..at + 4)? != b"OggS" {
+        return None;
+    }
+    let segment_count = usize::from(*bytes.get(at + 26)?);
+    let segments = bytes.get(at + 27..at + 27 + segment_count)?;
+    let data_start = at + 27 + segment_count;
+    let data_length = segments
+        .iter()
+        .map(|length| usize::from(*length))
+        .sum::<usize>();
+    Some(OggPage {
+        beginning_of_stream: bytes.get(at + 5)? & 2 != 0,
+        granule_position: le(bytes, at + 6, 8)?,
+        serial: le(bytes, at + 14, 4)? as u32,
+        segments,
+        data: bytes.get(data_start..data_start + data_length)?,
+        length: 27 + segment_count + data_length,
+    })
+}
+
+// The first packets of the logical stream, which are its headers. Packets go on through segments of 255 bytes, and
+// across pages.
+fn ogg_packets(pages: &[OggPage], serial: u32, count: usize) -> Vec<Vec<u8>> {
+    let mut packets = Vec::new();
+    let mut packet = Vec::new();
+    for page in pages.iter().filter(|page| page.serial == serial) {
+        let mut at = 0;
+        for length in page.segments {
+            let length = usize::from(*length);
+            packet.extend_from_slice(&page.data[at..at + length]);
+            at += length;
+            if length < 255 {
+                packets.push(std::mem::take(&mut packet));
+                if packets.len() == count {
+                    return packets;
+                }
+            }
+        }
+    }
+    packets
+}
+
+// Title and artist of the Vorbis comments that Vorbis and Opus streams have, a list of "NAME=value".
+fn vorbis_comments(comments: &[u8]) -> Tags {
+    let mut tags = Tags::default();
+    let Some(vendor_length) = le(comments, 0, 4) else {
+        return tags;
+    };
+    let mut at = 4 + vendor_length as usize;
+    let count = le(comments, at, 4).unwrap_or(0);
+    at += 4;
+    for _ in 0..count {
+        let Some(length) = le(comments, at, 4) else {
+            break;
+        };
+        let Some(comment) = comments.get(at + 4..at + 4 + length as usize) else {
+            break;
+        };
+        if let Some((name, value)) = String::from_utf8_lossy(comment).split_once('=') {
+            if name.eq_ignore_ascii_case("title") {
+                tags.title = tags.title.take().or_else(|| tag_value(value));
+            } else if name.eq_ignore_ascii_case("artist") {
+                tags.artist = tags.artist.take().or_else(|| tag_value(value));
+            }
+        }
+        at += 4 + length as usize;
+    }
+    tags
+}
+
+fn ogg_metadata(bytes: &[u8]) -> Result<MediaMetadata, String> {
+    let mut pages = Vec::new();
+    let mut at = 0;
+    while let Some(page) = ogg_page(bytes, at) {
+        at += page.length;
+        pages.push(page);
+    }
+
+    // Every logical stream starts on a page of its own, whose packet identifies its codec.
+    let identifications = pages
+        .iter()
+        .filter(|page| page.beginning_of_stream)
+        .filter_map(|page| {
+            let packet = ogg_packets(&pages, page.serial, 1).pop()?;
+            Some((page.serial, packet))
+        })
+        .collect::<Vec<_>>();
+    let has_video = identifications
+        .iter()
+        .any(|(_, packet)| packet.starts_with(b"\x80theora"));
+    let (serial, identification) = identifications
+        .into_iter()
+        .find(|(_, packet)| packet.starts_with(b"\x01vorbis") || packet.starts_with(b"OpusHead"))
+        .ok_or_else(|| String::from("The file has no Vorbis or Opus stream"))?;
+
+    // Granule positions count samples, at the sample rate of Vorbis streams, or always at 48kHz for Opus, which skips
+    // some samples at the start.
+    let (sample_rate, skipped, comments) = if identification.starts_with(b"OpusHead") {
+        (48000, le(&identification, 10, 2).unwrap_or(0), 8)
+    } else {
+        (le(&identification, 12, 4).unwrap_or(0), 0, 7)
+    };
+    let last_position = pages
+        .iter()
+        .rev()
+        .filter(|page| page.serial == serial && page.granule_position != u64::MAX)
+        .map(|page| page.granule_position)
+        .next();
+    let seconds = last_position
+        .filter(|_| sample_rate != 0)
+        .map(|position| position.saturating_sub(skipped) as f64 / sample_rate as f64);
+
+    let tags = ogg_packets(&pages, serial, 2)
+        .get(1)
+        .and_then(|packet| packet.get(comments..))
+        .map(vorbis_comments)
+        .unwrap_or_default();
+    Ok(MediaMetadata {
+        container: Container::Ogg,
+        has_video,
+        length_in_seconds: seconds.and_then(rounded_seconds),
+        title: tags.title,
+        artist: tags.artist,
+    })
+}
+
+// WebM
+
+const EBML_HEADER: u64 = 0x1A45DFA3;
+const SEGMENT: u64 = 0x18538067;
+const INFO: u64 = 0x1549A966;
+const TIMECODE_SCALE: u64 = 0x2AD7B1;
+const DURATION: u64 = 0x4489;
+const TITLE: u64 = 0x7BA9;
+const TRACKS: u64 = 0x1654AE6B;
+const TRACK_ENTRY: u64 = 0xAE;
+const TRACK_TYPE: u64 = 0x83;
+const TAGS: u64 = 0x1254C367;
+const TAG: u64 = 0x7373;
+const SIMPLE_TAG: u64 = 0x67C8;
+const TAG_NAME: u64 = 0x45A3;
+const TAG_STRING: u64 = 0x4487;
+
+// EBML variable length integer at the position, and its length. The leading zeros and the one after them say how
+// long it is. Ids keep that marker, sizes don't.
+fn vint(bytes: &[u8], at: usize, keep_marker: bool) -> Option<(u64, usize)> {
+    let first = *bytes.get(at)?;
+    if first == 0 {
+        return None;
+    }
+    let length = first.leading_zeros() as usize + 1;
+    let first = if keep_marker {
+        first
+    } else {
+        first & 0xFFu8.checked_shr(length as u32).unwrap_or(0)
+    };
+    let value = bytes
+        .get(at + 1..at + length)?
+        .iter()
+        .fold(u64::from(first), |n, byte| n << 8 | u64::from(*byte));
+    Some((value, length))
+}
+
+// Elements of a Matroska file or of the content of another element, as their id and content. Elements of unknown
+// size, like the segment of a file being recorded, go on to the end.
+fn ebml_elements(bytes: &[u8]) -> Vec<(u64, &[u8])> {
+    let mut elements = Vec::new();
+    let mut at = 0;
+    while let Some((id, id_length)) = vint(bytes, at, true) {
+        let Some((size, size_length)) = vint(bytes, at + id_length, false) else {
+            break;
+        };
+        let start = at + id_length + size_length;
+        let unknown_size = size == (1 << (7 * size_length)) - 1;
+        let end = match usize::try_from(size)
+            .ok()
+            .and_then(|size| start.checked_add(size))
+        {
+            Some(end) if !unknown_size => end.min(bytes.len()),
+            _ => bytes.len(),
+        };
+        elements.push((id, &bytes[start.min(end)..end]));
+        at = end;
+    }
+    elements
+}
+
+fn ebml_element(bytes: &[u8], id: u64) -> Option<&[u8]> {
+    ebml_elements(bytes)
+        .into_iter()
+        .find(|(found, _)| *found == id)
+        .map(|(_, content)| content)
+}
+
+fn ebml_float(content: &[u8]) -> Option<f64> {
+    match content.len() {
+        4 => Some(f64::from(f32::from_bits(be(content, 0, 4)? as u32))),
+        8 => Some(f64::from_bits(be(content, 0, 8)?)),
+        _ => None,
+    }
+}
+
+fn webm_metadata(bytes: &[u8]) -> Result<MediaMetadata, String> {
+    let segment =
+        ebml_element(bytes, SEGMENT).ok_or_else(|| String::from("The file has no segment"))?;
+    let info = ebml_element(segment, INFO).unwrap_or_default();
+
+    // Durations are counted in ticks of the timecode scale, in nanoseconds.
+    let scale = ebml_element(info, TIMECODE_SCALE)
+        .and_then(|scale| be(scale, 0, scale.len()))
+        .unwrap_or(1_000_000);
+    let seconds = ebml_element(info, DURATION)
+        .and_then(ebml_float)
+        .map(|duration| duration * scale as f64 / 1e9);
+
+    let has_video = ebml_element(segment, TRACKS)
+        .map(ebml_elements)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(id, _)| *id == TRACK_ENTRY)
+        .any(|(_, track)| ebml_element(track, TRACK_TYPE) == Some(&[1]));
+
+    let mut tags = Tags {
+        title: ebml_element(info, TITLE)
+            .and_then(|title| tag_value(&String::from_utf8_lossy(title))),
+        artist: None,
+    };
+    let simple_tags = ebml_elements(segment)
+        .into_iter()
+        .filter(|(id, _)| *id == TAGS)
+        .flat_map(|(_, content)| ebml_elements(content))
+        .filter(|(id, _)| *id == TAG)
+        .flat_map(|(_, content)| ebml_elements(content))
+        .filter(|(id, _)| *id == SIMPLE_TAG);
+    for (_, simple_tag) in simple_tags {
+        let text = |id| ebml_element(simple_tag, id).map(String::from_utf8_lossy);
+        let (Some(name), Some(value)) = (text(TAG_NAME), text(TAG_STRING)) else {
+            continue;
+        };
+        if name.eq_ignore_ascii_case("title") {
+            tags.title = tags.title.take().or_else(|| tag_value(&value));
+        } else if name.eq_ignore_ascii_case("artist") {
+            tags.artist = tags.artist.take().or_else(|| tag_value(&value));
+        }
+    }
+
+    Ok(MediaMetadata {
+        container: Container::Webm,
+        has_video,
+        length_in_seconds: seconds.and_then(rounded_seconds),
+        title: tags.title,
+        artist: tags.artist,
+    })
+}
+
+// Container of the file, from the signature it starts with.
+fn container_of(content: &[u8]) -> Option<Container> {
+    if content.starts_with(b"ID3") || frame_header(content, 0).is_some() {
+        Some(Container::Mp3)
+    } else if content.get(4..8) == Some(b"ftyp") {
+        Some(Container::Mp4)
+    } else if content.starts_with(b"OggS") {
+        Some(Container::Ogg)
+    } else if be(content, 0, 4) == Some(EBML_HEADER) {
+        Some(Container::Webm)
+    } else {
+        None
+    }
+}
+
+pub fn is_media(content_type: &str, content: &[u8]) -> bool {
+    content_type.starts_with("audio/")
+        || content_type.starts_with("video/")
+        || container_of(content).is_some()
+}
+
+pub fn read_media_metadata(content_type: &str, content: &[u8]) -> Result<MediaMetadata, String> {
+    // Some MP3 files start with junk instead of a tag or a frame.
+    let container = container_of(content).or_else(|| {
+        content_type
+            .starts_with("audio/mpeg")
+            .then_some(Container::Mp3)
+    });
+    match container {
+        Some(Container::Mp3) => mp3_metadata(content),
+        Some(Container::Mp4) => mp4_metadata(content),
+        Some(Container::Ogg) => ogg_metadata(content),
+        Some(Container::Webm) => webm_metadata(content),
+        None => Err(String::from(
+            "The file is not an MP3, MP4, Ogg or WebM file",
+        )),
+    }
+}
+
+// What the file says about the entry it is the recording of.
+pub fn page_metadata_of_media(media: &MediaMetadata) -> PageMetadata {
+    let length_in_seconds = media.length_in_seconds.unwrap_or(0);
+    PageMetadata {
+        title: media.title.clone(),
+        authors: media.artist.iter().cloned().collect(),
+        entry_type: Some(if media.has_video {
+            EntryType::Video { length_in_seconds }
+        } else {
+            EntryType::Audio { length_in_seconds }
+        }),
+        ..PageMetadata::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Header of an MPEG-1 layer III frame at 128kbit/s and 44.1kHz, which is 417 bytes long.
+    const FRAME_HEADER: [u8; 4] = [0xFF, 0xFB, 0x90, 0x00];
+
+    fn frame(content: &[u8]) -> Vec<u8> {
+        let mut frame = FRAME_HEADER.to_vec();
+        frame.extend_from_slice(content);
+        frame.resize(417, 0);
+        frame
+    }
+
+    // First frame of a variable bit rate file, with the number of frames of the file.
+    fn xing_frame(frames: u32) -> Vec<u8> {
+        let mut content = vec![0; 32];
+        content.extend_from_slice(b"Xing");
+        content.extend_from_slice(&1u32.to_be_bytes());
+        content.extend_from_slice(&frames.to_be_bytes());
+        frame(&content)
+    }
+
+    fn id3v2(frames: &[(&[u8], Vec<u8>)]) -> Vec<u8> {
+        let mut tag = Vec::new();
+        for (id, content) in frames {
+            tag.extend_from_slice(id);
+            tag.extend_from_slice(&(content.len() as u32).to_be_bytes());
+            tag.extend_from_slice(&[0, 0]);
+            tag.extend_from_slice(content);
+        }
+        // Padding
+        tag.extend_from_slice(&[0; 20]);
+        let size = tag.len();
+        let mut bytes = b"ID3\x03\x00\x00".to_vec();
+        bytes.extend((0..4).rev().map(|i| ((size >> (7 * i)) & 0x7F) as u8));
+        bytes.extend(tag);
+        bytes
+    }
+
+    fn utf16_text(text: &str) -> Vec<u8> {
+        let mut frame = vec![1, 0xFF, 0xFE];
+        frame.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        frame
+    }
+
+    fn boxed(kind: &[u8], contents: &[&[u8]]) -> Vec<u8> {
+        let content = contents.concat();
+        let mut bytes = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(kind);
+        bytes.extend(content);
+        bytes
+    }
+
+    fn mp4_item(kind: &[u8], text: &str) -> Vec<u8> {
+        boxed(
+            kind,
+            &[&boxed(
+                b"data",
+                &[&[0, 0, 0, 1, 0, 0, 0, 0], text.as_bytes()],
+            )],
+        )
+    }
+
+    fn mp4(handler: &[u8], time_scale: u32, duration: u32, items: &[Vec<u8>]) -> Vec<u8> {
+        let mut header = vec![0; 12];
+        header.extend_from_slice(&time_scale.to_be_bytes());
+        header.extend_from_slice(&duration.to_be_bytes());
+        header.extend_from_slice(&[0; 80]);
+        let handler = boxed(b"hdlr", &[&[0; 8], handler, &[0; 12]]);
+        let items = items.concat();
+        let meta = boxed(b"meta", &[&[0; 4], &handler, &boxed(b"ilst", &[&items])]);
+        [
+            boxed(b"ftyp", &[b"M4A \0\0\0\0isomM4A "]),
+            boxed(
+                b"moov",
+                &[
+                    &boxed(b"mvhd", &[&header]),
+                    &boxed(b"trak", &[&boxed(b"mdia", &[&handler])]),
+                    &boxed(b"udta", &[&meta]),
+                ],
+            ),
+            boxed(b"mdat", &[&[0; 100]]),
+        ]
+        .concat()
+    }
+
+    // Page with the packets, the last of which goes on in the next page if it is unfinished.
+    fn page(
+        flags: u8,
+        granule_position: u64,
+        serial: u32,
+        packets: &[&[u8]],
+        unfinished: bool,
+    ) -> Vec<u8> {
+        let mut segments = Vec::new();
+        for packet in packets {
+            segments.extend(std::iter::repeat_n(255, packet.len() / 255));
+            segments.push((packet.len() % 255) as u8);
+        }
+        if unfinished {
+            assert_eq!(segments.pop(), Some(0));
+        }
+        let mut bytes = b"OggS\0".to_vec();
+        bytes.push(flags);
+        bytes.extend_from_slice(&granule_position.to_le_bytes());
+        bytes.extend_from_slice(&serial.to_le_bytes());
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.push(segments.len() as u8);
+        bytes.extend(segments);
+        bytes.extend(packets.concat());
+        bytes
+    }
+
+    fn comment_list(vendor: &str, comments: &[&str]) -> Vec<u8> {
+        let mut bytes = (vendor.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(vendor.as_bytes());
+        bytes.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+        for comment in comments {
+            bytes.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(comment.as_bytes());
+        }
+        bytes
+    }
+
+    fn ebml(id: u64, contents: &[&[u8]]) -> Vec<u8> {
+        let content = contents.concat();
+        let mut bytes = id
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|byte| *byte == 0)
+            .collect::<Vec<_>>();
+        bytes.push(1);
+        bytes.extend_from_slice(&(content.len() as u64).to_be_bytes()[1..]);
+        bytes.extend(content);
+        bytes
+    }
+
+    fn webm(info: &[&[u8]], track_types: &[u8], tags: &[(&str, &str)]) -> Vec<u8> {
+        let tracks = track_types
+            .iter()
+            .map(|track_type| ebml(TRACK_ENTRY, &[&ebml(TRACK_TYPE, &[&[*track_type]])]))
+            .collect::<Vec<_>>()
+            .concat();
+        let simple_tags = tags
+            .iter()
+            .map(|(name, value)| {
+                ebml(
+                    SIMPLE_TAG,
+                    &[
+                        &ebml(TAG_NAME, &[name.as_bytes()]),
+                        &ebml(TAG_STRING, &[value.as_bytes()]),
+                    ],
+                )
+            })
+            .collect::<Vec<_>>()
+            .concat();
+        // The segment of a file being recorded has an unknown size.
+        let mut segment = vec![
+            0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        ];
+        segment.extend(ebml(INFO, info));
+        segment.extend(ebml(TRACKS, &[&tracks]));
+        segment.extend(ebml(0x1F43B675, &[&[0; 100]]));
+        segment.extend(ebml(TAGS, &[&ebml(TAG, &[&simple_tags])]));
+        [ebml(EBML_HEADER, &[&ebml(0x4282, &[b"webm"])]), segment].concat()
+    }
+
+    // read_media_metadata
+
+    #[test]
+    fn mp3_of_variable_bit_rate_with_id3v2_tags() {
+        let content = include_bytes!("../fixtures/media/foggy-mountain-breakdown.mp3");
+        assert_eq!(
+            read_media_metadata("audio/mpeg", content).unwrap(),
+            MediaMetadata {
+                container: Container::Mp3,
+                has_video: false,
+                length_in_seconds: Some(165),
+                title: Some(String::from("Foggy Mountain Breakdown")),
+                artist: Some(String::from("Flatt & Scruggs")),
+            }
+        );
+    }
+
+    #[test]
+    fn mp3_of_constant_bit_rate_with_id3v1_tag() {
+        // 10 seconds at 16000 bytes a second.
+        let mut content = vec![0x00, 0x13, 0x37];
+        content.extend((0..384).flat_map(|_| frame(&[])));
+        let mut id3v1 = b"TAGBonnie and Clyde".to_vec();
+        id3v1.resize(33, 0);
+        id3v1.extend_from_slice(b"Charles Strouse");
+        id3v1.resize(128, 0);
+        content.extend(id3v1);
+
+        let metadata = read_media_metadata("audio/mpeg", &content).unwrap();
+        assert_eq!(metadata.length_in_seconds, Some(10));
+        assert_eq!(metadata.title.as_deref(), Some("Bonnie and Clyde"));
+        assert_eq!(metadata.artist.as_deref(), Some("Charles Strouse"));
+    }
+
+    #[test]
+    fn id3v2_text_in_every_encoding() {
+        let content = [
+            id3v2(&[
+                (b"TIT2", utf16_text("Balada de la película")),
+                (b"TPE1", b"\x00Flatt\x00Scruggs".to_vec()),
+            ]),
+            xing_frame(383),
+        ]
+        .concat();
+        let metadata = read_media_metadata("application/octet-stream", &content).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Balada de la película"));
+        assert_eq!(metadata.artist.as_deref(), Some("Flatt, Scruggs"));
+        assert_eq!(metadata.length_in_seconds, Some(10));
+    }
+
+    #[test]
+    fn mp4_audio_and_video() {
+        let audio = mp4(
+            b"soun",
+            44100,
+            44100 * 165,
+            &[
+                mp4_item(b"\xA9nam", "Foggy Mountain Breakdown"),
+                mp4_item(b"\xA9ART", "Flatt & Scruggs"),
+            ],
+        );
+        assert_eq!(
+            read_media_metadata("audio/mp4", &audio).unwrap(),
+            MediaMetadata {
+                container: Container::Mp4,
+                has_video: false,
+                length_in_seconds: Some(165),
+                title: Some(String::from("Foggy Mountain Breakdown")),
+                artist: Some(String::from("Flatt & Scruggs")),
+            }
+        );
+
+        let video = mp4(b"vide", 600, 452_400, &[]);
+        let metadata = read_media_metadata("video/mp4", &video).unwrap();
+        assert!(metadata.has_video);
+        assert_eq!(metadata.length_in_seconds, Some(754));
+        assert_eq!(metadata.title, None);
+    }
+
+    #[test]
+    fn ogg_vorbis_with_comments_across_pages() {
+        let mut identification = b"\x01vorbis\0\0\0\0\x02".to_vec();
+        identification.extend_from_slice(&44100u32.to_le_bytes());
+        identification.resize(30, 0);
+        let mut comments = b"\x03vorbis".to_vec();
+        comments.extend(comment_list(
+            &"Xiph.Org libVorbis ".repeat(20),
+            &["TITLE=Foggy Mountain Breakdown", "artist=Flatt & Scruggs"],
+        ));
+        // The comments don't fit in a page.
+        let (first, second) = comments.split_at(255);
+
+        let content = [
+            page(2, 0, 7, &[&identification], false),
+            page(0, 0, 7, &[first], true),
+            page(1, u64::MAX, 7, &[second], false),
+            page(0, 44100 * 100, 7, &[&[0; 50]], false),
+            page(4, 44100 * 165, 7, &[&[0; 50]], false),
+        ]
+        .concat();
+        assert_eq!(
+            read_media_metadata("audio/ogg", &content).unwrap(),
+            MediaMetadata {
+                container: Container::Ogg,
+                has_video: false,
+                length_in_seconds: Some(165),
+                title: Some(String::from("Foggy Mountain Breakdown")),
+                artist: Some(String::from("Flatt & Scruggs")),
+            }
+        );
+    }
+
+    #[test]
+    fn ogg_opus_skips_the_first_samples() {
+        let mut identification = b"OpusHead\x01\x02".to_vec();
+        identification.extend_from_slice(&3840u16.to_le_bytes());
+        identification.extend_from_slice(&48000u32.to_le_bytes());
+        identification.extend_from_slice(&[0; 3]);
+        let comments = [
+            b"OpusTags".to_vec(),
+            comment_list("libopus", &["ARTIST=Flatt & Scruggs"]),
+        ]
+        .concat();
+
+        let content = [
+            page(2, 0, 9, &[&identification], false),
+            page(0, 0, 9, &[&comments], false),
+            page(4, 48000 * 10 + 3840, 9, &[&[0; 50]], false),
+        ]
+        .concat();
+        let metadata = read_media_metadata("audio/ogg", &content).unwrap();
+        assert_eq!(metadata.length_in_seconds, Some(10));
+        assert_eq!(metadata.artist.as_deref(), Some("Flatt & Scruggs"));
+        assert_eq!(metadata.title, None);
+    }
+
+    #[test]
+    fn webm_with_info_tracks_and_tags() {
+        let content = webm(
+            &[
+                &ebml(TIMECODE_SCALE, &[&[0x0F, 0x42, 0x40]]),
+                &ebml(DURATION, &[&754_000f64.to_be_bytes()]),
+                &ebml(TITLE, &[b"Arthur Penn on Bonnie and Clyde"]),
+            ],
+            &[1, 2],
+            &[("ARTIST", "Arthur Penn"), ("TITLE", "Not this one")],
+        );
+        assert_eq!(
+            read_media_metadata("video/webm", &content).unwrap(),
+            MediaMetadata {
+                container: Container::Webm,
+                has_video: true,
+                length_in_seconds: Some(754),
+                title: Some(String::from("Arthur Penn on Bonnie and Clyde")),
+                artist: Some(String::from("Arthur Penn")),
+            }
+        );
+    }
+
+    #[test]
+    fn webm_without_duration_has_unknown_length() {
+        let content = webm(&[&ebml(DURATION, &[&[0; 2]])], &[2], &[]);
+        let metadata = read_media_metadata("audio/webm", &content).unwrap();
+        assert!(!metadata.has_video);
+        assert_eq!(metadata.length_in_seconds, None);
+    }
+
+    #[test]
+    fn files_that_are_not_media_are_errors() {
+        assert!(read_media_metadata("text/plain", b"Hello").is_err());
+        assert!(read_media_metadata("audio/mpeg", b"ID3\x03\x00\x00\x00\x00\x00\x00").is_err());
+        assert!(read_media_metadata("audio/ogg", b"OggS").is_err());
+        assert!(read_media_metadata("video/mp4", &boxed(b"ftyp", &[b"isom"])).is_err());
+    }
+
+    // page_metadata_of_media
+
+    #[test]
+    fn media_are_videos_or_audios() {
+        let metadata = page_metadata_of_media(&MediaMetadata {
+            container: Container::Mp4,
+            has_video: true,
+            length_in_seconds: Some(754),
+            title: Some(String::from("Arthur Penn on Bonnie and Clyde")),
+            artist: Some(String::from("Arthur Penn")),
+        });
+        assert_eq!(
+            metadata.entry_type,
+            Some(EntryType::Video {
+                length_in_seconds: 754
+            })
+        );
+        assert_eq!(metadata.authors, ["Arthur Penn"]);
+
+        let metadata = page_metadata_of_media(&MediaMetadata {
+            container: Container::Webm,
+            has_video: false,
+            length_in_seconds: None,
+            title: None,
+            artist: None,
+        });
+        assert_eq!(
+            metadata.entry_type,
+            Some(EntryType::Audio {
+                length_in_seconds: 0
+            })
+        );
+        assert!(metadata.authors.is_empty());
+    }
+}
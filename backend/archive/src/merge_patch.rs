@@ -0,0 +1,118 @@
+// JSON Merge Patch as described in RFC 7396. Members of the patch replace the ones of the target with the same name,
+// objects are merged recursively and null removes a member.
+
+use serde_json::Value;
+
+pub fn apply(target: &mut Value, patch: &Value) {
+    let patch_members = match patch {
+        Value::Object(members) => members,
+        _ => {
+            *target = patch.clone();
+            return;
+        }
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    let target_members = target.as_object_mut().unwrap();
+
+    for (name, value) in patch_members {
+        if value.is_null() {
+            target_members.remove(name);
+        } else {
+            apply(
+                target_members.entry(name.clone()).or_insert(Value::Null),
+                value,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn applied(target: Value, patch: Value) -> Value {
+        let mut result = target;
+        apply(&mut result, &patch);
+        result
+    }
+
+    // Examples from appendix A of RFC 7396.
+
+    #[test]
+    fn member_is_replaced() {
+        assert_eq!(
+            applied(json!({"a": "b"}), json!({"a": "c"})),
+            json!({"a": "c"})
+        );
+    }
+
+    #[test]
+    fn member_is_added() {
+        assert_eq!(
+            applied(json!({"a": "b"}), json!({"b": "c"})),
+            json!({"a": "b", "b": "c"})
+        );
+    }
+
+    #[test]
+    fn null_removes_member() {
+        assert_eq!(applied(json!({"a": "b"}), json!({"a": null})), json!({}));
+        assert_eq!(
+            applied(json!({"a": "b", "b": "c"}), json!({"a": null})),
+            json!({"b": "c"})
+        );
+    }
+
+    #[test]
+    fn arrays_are_replaced() {
+        assert_eq!(
+            applied(json!({"a": ["b"]}), json!({"a": "c"})),
+            json!({"a": "c"})
+        );
+        assert_eq!(
+            applied(json!({"a": "c"}), json!({"a": ["b"]})),
+            json!({"a": ["b"]})
+        );
+        assert_eq!(
+            applied(json!({"a": [{"b": "c"}]}), json!({"a": [1]})),
+            json!({"a": [1]})
+        );
+    }
+
+    #[test]
+    fn objects_are_merged() {
+        assert_eq!(
+            applied(
+                json!({"a": {"b": "c"}}),
+                json!({"a": {"b": "d", "c": null}})
+            ),
+            json!({"a": {"b": "d"}})
+        );
+        assert_eq!(
+            applied(json!({"e": null}), json!({"a": 1})),
+            json!({"e": null, "a": 1})
+        );
+        assert_eq!(
+            applied(json!({}), json!({"a": {"bb": {"ccc": null}}})),
+            json!({"a": {"bb": {}}})
+        );
+    }
+
+    #[test]
+    fn non_object_patch_replaces_target() {
+        assert_eq!(
+            applied(json!(["a", "b"]), json!(["c", "d"])),
+            json!(["c", "d"])
+        );
+        assert_eq!(applied(json!({"a": "b"}), json!(["c"])), json!(["c"]));
+        assert_eq!(applied(json!({"a": "foo"}), json!(null)), json!(null));
+        assert_eq!(
+            applied(json!("string"), json!({"a": "b"})),
+            json!({"a": "b"})
+        );
+    }
+}
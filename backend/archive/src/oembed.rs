@@ -0,0 +1,181 @@
+// Metadata of pages from their oEmbed endpoints, which video and audio sites like YouTube, Vimeo and SoundCloud have
+// and which often say who made the recording, and for how long it plays, when their meta tags don't.
+
+use crate::entry_type::EntryType;
+use crate::html_meta::{self, PageMetadata};
+
+use serde_json::Value;
+
+// Sites whose "rich" embeds are players of audio, like songs and podcasts.
+const AUDIO_PROVIDERS: [&str; 8] = [
+    "SoundCloud",
+    "Spotify",
+    "Mixcloud",
+    "Bandcamp",
+    "Audiomack",
+    "Podbean",
+    "Buzzsprout",
+    "Simplecast",
+];
+
+// Link of the oEmbed endpoint of the page, from its <link rel="alternate" type="application/json+oembed">.
+pub fn oembed_link(html_source: &str) -> Option<String> {
+    html_meta::parse_html_head(html_source).oembed_link
+}
+
+fn text(value: &Value) -> Option<String> {
+    value
+        .as_str()
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|text| !text.is_empty())
+}
+
+// Some providers add the length of videos in seconds, as a number or a string.
+fn seconds(value: &Value) -> Option<i32> {
+    match value {
+        Value::Number(number) => number.as_f64().map(|number| number.round() as i32),
+        Value::String(text) => text
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|number| number.round() as i32),
+        _ => None,
+    }
+}
+
+fn entry_type_of(response: &Value) -> Option<EntryType> {
+    let length_in_seconds = seconds(&response["duration"]).unwrap_or(0);
+    match response["type"].as_str()? {
+        "video" => Some(EntryType::Video { length_in_seconds }),
+        "rich" => {
+            let provider = text(&response["provider_name"]).unwrap_or_default();
+            AUDIO_PROVIDERS
+                .iter()
+                .any(|audio| provider.eq_ignore_ascii_case(audio))
+                .then_some(EntryType::Audio { length_in_seconds })
+        }
+        _ => None,
+    }
+}
+
+// What the response of an oEmbed endpoint says about the page, or None if it is not an oEmbed response.
+pub fn oembed_metadata(json: &str) -> Option<PageMetadata> {
+    let response: Value = serde_json::from_str(json).ok()?;
+    if !response["type"].is_string() {
+        return None;
+    }
+
+    Some(PageMetadata {
+        title: text(&response["title"]),
+        description: text(&response["description"]),
+        authors: text(&response["author_name"]).into_iter().collect(),
+        // Vimeo writes dates like "2013-05-06 10:47:05".
+        date_published: text(&response["upload_date"])
+            .and_then(|date| html_meta::parse_date(date.split(' ').next().unwrap_or_default())),
+        entry_type: entry_type_of(&response),
+        image: text(&response["thumbnail_url"]),
+        canonical_link: None,
+        identifier: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::{Date, Month};
+
+    // oembed_metadata
+
+    #[test]
+    fn metadata_of_a_youtube_video() {
+        let json = r#"{
+            "title": "Arthur Penn sobre \"Bonnie & Clyde\" (1967)",
+            "author_name": "Archivo de Cine",
+            "author_url": "https://www.youtube.com/@archivodecine",
+            "type": "video",
+            "height": 113, "width": 200, "version": "1.0",
+            "provider_name": "YouTube", "provider_url": "https://www.youtube.com/",
+            "thumbnail_height": 360, "thumbnail_width": 480,
+            "thumbnail_url": "https://i.ytimg.com/vi/abc123/hqdefault.jpg",
+            "html": "<iframe width=\"200\" height=\"113\" src=\"https://www.youtube.com/embed/abc123\"></iframe>"
+        }"#;
+        assert_eq!(
+            oembed_metadata(json),
+            Some(PageMetadata {
+                title: Some(String::from("Arthur Penn sobre \"Bonnie & Clyde\" (1967)")),
+                description: None,
+                authors: vec![String::from("Archivo de Cine")],
+                date_published: None,
+                entry_type: Some(EntryType::Video {
+                    length_in_seconds: 0
+                }),
+                image: Some(String::from("https://i.ytimg.com/vi/abc123/hqdefault.jpg")),
+                canonical_link: None,
+                identifier: None,
+            })
+        );
+    }
+
+    #[test]
+    fn metadata_of_a_vimeo_video_has_its_length_and_date() {
+        let json = r#"{
+            "type": "video", "version": "1.0", "provider_name": "Vimeo",
+            "title": "Bonnie and Clyde: the ending", "author_name": "Arthur Penn",
+            "description": "The last scene.", "duration": 754,
+            "upload_date": "2013-05-06 10:47:05"
+        }"#;
+        let metadata = oembed_metadata(json).unwrap();
+        assert_eq!(
+            metadata.entry_type,
+            Some(EntryType::Video {
+                length_in_seconds: 754
+            })
+        );
+        assert_eq!(metadata.description.as_deref(), Some("The last scene."));
+        assert_eq!(
+            metadata.date_published,
+            Some(Date {
+                day: 6,
+                month: Month::May,
+                year: 2013
+            })
+        );
+    }
+
+    #[test]
+    fn rich_embeds_of_audio_sites_are_audio() {
+        let json = r#"{"type": "rich", "provider_name": "SoundCloud", "title": "Foggy Mountain Breakdown by Flatt & Scruggs", "author_name": "Flatt & Scruggs", "duration": "165.4"}"#;
+        assert_eq!(
+            oembed_metadata(json).unwrap().entry_type,
+            Some(EntryType::Audio {
+                length_in_seconds: 165
+            })
+        );
+
+        let json = r#"{"type": "rich", "provider_name": "Twitter", "author_name": "Pauline Kael"}"#;
+        let metadata = oembed_metadata(json).unwrap();
+        assert_eq!(metadata.entry_type, None);
+        assert_eq!(metadata.authors, ["Pauline Kael"]);
+    }
+
+    #[test]
+    fn responses_that_are_not_oembed_are_ignored() {
+        assert_eq!(oembed_metadata("<oembed><type>video</type></oembed>"), None);
+        assert_eq!(oembed_metadata(r#"{"error": "Not found"}"#), None);
+    }
+
+    // oembed_link
+
+    #[test]
+    fn link_of_the_json_endpoint() {
+        let html = r#"
+            <link rel="alternate" type="text/xml+oembed" href="https://vimeo.com/api/oembed.xml?url=https%3A%2F%2Fvimeo.com%2F1">
+            <link rel="alternate" type="application/json+oembed" href="https://vimeo.com/api/oembed.json?url=https%3A%2F%2Fvimeo.com%2F1&amp;maxwidth=640">
+        "#;
+        assert_eq!(
+            oembed_link(html).as_deref(),
+            Some("https://vimeo.com/api/oembed.json?url=https%3A%2F%2Fvimeo.com%2F1&maxwidth=640")
+        );
+        assert_eq!(oembed_link("<link rel=alternate href=/feed>"), None);
+    }
+}
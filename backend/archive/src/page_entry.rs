@@ -0,0 +1,275 @@
+// Entry forms filled in from the metadata of the page they link to, for creating entries from just a link.
+
+use crate::entry::NewEntryForm;
+use crate::entry_type::{self, EntryType};
+use crate::html_meta::PageMetadata;
+use crate::validation::{self, ValidationErrors};
+
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+const FORM_FIELDS: [&str; 13] = [
+    "link",
+    "title",
+    "description",
+    "authors",
+    "category",
+    "themes",
+    "works_mentioned",
+    "tags",
+    "date_published",
+    "date_saved",
+    "exceptional",
+    "entry_type",
+    "identifier",
+];
+
+// Fields that neither the page nor a default can fill, so they must be found in the page or given.
+const REQUIRED_FIELDS: [&str; 3] = ["title", "category", "date_published"];
+
+#[derive(Debug)]
+pub struct FilledForm {
+    pub form: NewEntryForm,
+    // Fields of the form whose values were found in the page instead of given, in the order of the form.
+    pub auto_filled: Vec<String>,
+}
+
+// Values of the form found in the metadata of the page.
+fn fields_of_page(metadata: &PageMetadata) -> Map<String, Value> {
+    let mut fields = Map::new();
+    if let Some(title) = &metadata.title {
+        fields.insert(String::from("title"), json!(title));
+    }
+    if let Some(description) = &metadata.description {
+        fields.insert(String::from("description"), json!(description));
+    }
+    if !metadata.authors.is_empty() {
+        fields.insert(String::from("authors"), json!(metadata.authors));
+    }
+    if let Some(date_published) = metadata.date_published {
+        fields.insert(String::from("date_published"), json!(date_published));
+    }
+    if let Some(entry_type) = metadata.entry_type {
+        fields.insert(String::from("entry_type"), json!(entry_type));
+    }
+    if let Some(identifier) = &metadata.identifier {
+        fields.insert(String::from("identifier"), json!(identifier));
+    }
+    fields
+}
+
+// Fills a form for the page at the link with the values found in its metadata, then the overrides, which take
+// precedence, and defaults for what is in neither. The form is normalized and validated.
+pub fn fill_entry_form(
+    link: &str,
+    metadata: &PageMetadata,
+    overrides: &Map<String, Value>,
+) -> Result<FilledForm, ValidationErrors> {
+    let mut fields = Map::new();
+    fields.insert(String::from("link"), json!(link));
+    fields.insert(String::from("description"), json!(""));
+    for list in ["authors", "themes", "works_mentioned", "tags"] {
+        fields.insert(String::from(list), json!([]));
+    }
+    fields.insert(String::from("exceptional"), json!(false));
+    fields.insert(
+        String::from("entry_type"),
+        json!(EntryType::Article { words: 0 }),
+    );
+
+    let found = fields_of_page(metadata);
+    let auto_filled = FORM_FIELDS
+        .iter()
+        .filter(|field| found.contains_key(**field) && !overrides.contains_key(**field))
+        .map(|field| field.to_string())
+        .collect();
+    fields.extend(found);
+
+    let mut errors = ValidationErrors::default();
+    for (name, value) in overrides {
+        if FORM_FIELDS.contains(&name.as_str()) {
+            fields.insert(name.clone(), value.clone());
+        } else {
+            errors.add(name, "Unknown field");
+        }
+    }
+    for field in REQUIRED_FIELDS {
+        if !fields.contains_key(field) {
+            errors.add(field, "Not found in the page, so it must be given");
+        }
+    }
+    errors.into_result()?;
+
+    let form: NewEntryForm =
+        serde_json::from_value(Value::Object(fields)).map_err(|err| ValidationErrors {
+            error_message: err.to_string(),
+            fields: BTreeMap::new(),
+        })?;
+    Ok(FilledForm {
+        form: validation::normalize_and_validate(form)?,
+        auto_filled,
+    })
+}
+
+// Fills the fields of an existing entry that were left empty with what was found in its page or backup, like the
+// number of pages of a paper saved before its PDF was. Returns the fields that were filled, in the order of the form.
+pub fn fill_empty_fields(form: &mut NewEntryForm, metadata: &PageMetadata) -> Vec<String> {
+    let mut filled = Vec::new();
+    if form.description.is_empty() {
+        if let Some(description) = &metadata.description {
+            form.description = description.clone();
+            filled.push(String::from("description"));
+        }
+    }
+    if form.authors.is_empty() && !metadata.authors.is_empty() {
+        form.authors = metadata.authors.clone();
+        filled.push(String::from("authors"));
+    }
+    if let Some(found) = metadata.entry_type {
+        if entry_type::metadata(form.entry_type) == 0
+            && entry_type::metadata(found) != 0
+            && entry_type::same_unit(form.entry_type, found)
+        {
+            form.entry_type = entry_type::from_index_and_metadata(
+                entry_type::index(form.entry_type),
+                entry_type::metadata(found),
+            );
+            filled.push(String::from("entry_type"));
+        }
+    }
+    if form.identifier.as_deref().unwrap_or_default().is_empty() {
+        if let Some(identifier) = &metadata.identifier {
+            form.identifier = Some(identifier.clone());
+            filled.push(String::from("identifier"));
+        }
+    }
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::{Date, Month};
+
+    fn metadata() -> PageMetadata {
+        PageMetadata {
+            title: Some(String::from("Bonnie and Clyde")),
+            description: Some(String::from("The frontier of movies")),
+            authors: vec![String::from("Pauline Kael")],
+            date_published: Some(Date {
+                day: 21,
+                month: Month::October,
+                year: 1967,
+            }),
+            entry_type: None,
+            image: None,
+            canonical_link: None,
+            identifier: None,
+        }
+    }
+
+    fn overrides(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    // fill_entry_form
+
+    #[test]
+    fn form_is_filled_from_the_page_and_the_overrides() {
+        let filled = fill_entry_form(
+            "https://example.com/bonnie-and-clyde",
+            &metadata(),
+            &overrides(json!({ "category": "Cinema", "title": "Bonnie and Clyde " })),
+        )
+        .unwrap();
+
+        assert_eq!(filled.form.title, "Bonnie and Clyde");
+        assert_eq!(filled.form.authors, ["Pauline Kael"]);
+        assert_eq!(filled.form.category, "Cinema");
+        assert_eq!(filled.form.entry_type, EntryType::Article { words: 0 });
+        assert_eq!(
+            filled.auto_filled,
+            ["description", "authors", "date_published"]
+        );
+    }
+
+    #[test]
+    fn fields_missing_from_the_page_must_be_given() {
+        let errors = fill_entry_form(
+            "https://example.com/bonnie-and-clyde",
+            &PageMetadata::default(),
+            &overrides(json!({ "rating": 5 })),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            errors.fields.keys().collect::<Vec<_>>(),
+            ["category", "date_published", "rating", "title"]
+        );
+    }
+
+    #[test]
+    fn filled_form_is_validated() {
+        let errors = fill_entry_form(
+            "example.com",
+            &metadata(),
+            &overrides(json!({ "category": "Cinema" })),
+        )
+        .unwrap_err();
+        assert_eq!(errors.fields.keys().collect::<Vec<_>>(), ["link"]);
+    }
+
+    // fill_empty_fields
+
+    #[test]
+    fn only_empty_fields_are_filled() {
+        let mut form = fill_entry_form(
+            "https://example.com/bonnie-and-clyde",
+            &metadata(),
+            &overrides(
+                json!({ "category": "Cinema", "description": "", "authors": [],
+                "entry_type": { "Book": { "pages": 0 } } }),
+            ),
+        )
+        .unwrap()
+        .form;
+        let found = PageMetadata {
+            title: Some(String::from("The Frontier of Movies")),
+            authors: vec![String::from("Arthur Penn")],
+            entry_type: Some(EntryType::Paper { pages: 20 }),
+            ..metadata()
+        };
+
+        assert_eq!(
+            fill_empty_fields(&mut form, &found),
+            ["description", "authors", "entry_type"]
+        );
+        assert_eq!(form.title, "Bonnie and Clyde");
+        assert_eq!(form.description, "The frontier of movies");
+        assert_eq!(form.authors, ["Arthur Penn"]);
+        assert_eq!(form.entry_type, EntryType::Book { pages: 20 });
+
+        assert!(fill_empty_fields(&mut form, &metadata()).is_empty());
+        assert_eq!(form.authors, ["Arthur Penn"]);
+    }
+
+    #[test]
+    fn lengths_in_other_units_are_not_filled() {
+        let mut form = fill_entry_form(
+            "https://example.com/bonnie-and-clyde",
+            &metadata(),
+            &overrides(json!({ "category": "Cinema" })),
+        )
+        .unwrap()
+        .form;
+        let found = PageMetadata {
+            entry_type: Some(EntryType::Video {
+                length_in_seconds: 754,
+            }),
+            ..PageMetadata::default()
+        };
+
+        assert!(fill_empty_fields(&mut form, &found).is_empty());
+        assert_eq!(form.entry_type, EntryType::Article { words: 0 });
+    }
+}
@@ -0,0 +1,310 @@
+// Metadata of PDF documents, which is what papers and books are usually backed up as: their number of pages, the
+// title, authors and creation date of their document information dictionary, and the text of their first page.
+
+use crate::date::{self, Date};
+use crate::entry_type::EntryType;
+use crate::html_meta::{author_name, PageMetadata};
+
+use lopdf::{decode_text_string, Dictionary, Document};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// How much of the text of the first page is kept, which is enough for the title, authors and abstract of a paper.
+const FIRST_PAGE_TEXT_LENGTH: usize = 2000;
+
+// Documents longer than this are taken to be books rather than papers.
+const MAX_PAPER_PAGES: i32 = 150;
+
+#[derive(Serialize, ToSchema, Debug, Default, PartialEq, Eq)]
+pub struct PdfMetadata {
+    pub page_count: i32,
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    // When the file was made, which is usually some time after the text was written.
+    pub creation_date: Option<Date>,
+    pub first_page_text: Option<String>,
+}
+
+// PDFs are often served as application/octet-stream, so their signature is checked as well as the content type.
+pub fn is_pdf(content_type: &str, content: &[u8]) -> bool {
+    content_type.starts_with("application/pdf") || content.starts_with(b"%PDF-")
+}
+
+// Text string of the document information dictionary, with its whitespace collapsed.
+fn info_text(document: &Document, info: &Dictionary, key: &[u8]) -> Option<String> {
+    let (_, value) = document.dereference(info.get(key).ok()?).ok()?;
+    let text = decode_text_string(value).ok()?;
+    let text = text
+        .trim_start_matches('\u{FEFF}')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+// Titles that word processors fill in with the name of the file or of the template, instead of the title of the text.
+fn is_placeholder_title(title: &str) -> bool {
+    let title = title.to_lowercase();
+    title == "untitled"
+        || title.starts_with("microsoft word - ")
+        || [".doc", ".docx", ".odt", ".rtf", ".tex", ".dvi", ".pdf"]
+            .iter()
+            .any(|extension| title.ends_with(extension))
+}
+
+// Authors of the information dictionary, which are written as a single string, separated by semicolons or "and".
+fn authors_of(author: &str) -> Vec<String> {
+    author
+        .split(';')
+        .flat_map(|authors| authors.split(" and "))
+        .map(str::trim)
+        .filter(|author| !author.is_empty())
+        .map(author_name)
+        .collect()
+}
+
+// PDF dates are written as "D:YYYYMMDDHHmmSSOHH'mm'", where everything after the year is optional.
+fn parse_pdf_date(text: &str) -> Option<Date> {
+    let digits = text.strip_prefix("D:").unwrap_or(text);
+    let field = |start: usize, default: &'static str| match digits.get(start..start + 2) {
+        Some(field) if field.bytes().all(|byte| byte.is_ascii_digit()) => field,
+        _ => default,
+    };
+    let year = digits
+        .get(0..4)
+        .filter(|year| year.bytes().all(|byte| byte.is_ascii_digit()))?;
+    date::read_sql_date(&format!("{}-{}-{}", year, field(4, "01"), field(6, "01")))
+        .filter(|date| date::is_valid(*date))
+}
+
+// Text of the first page, one line per text object, cut to the first FIRST_PAGE_TEXT_LENGTH characters.
+fn first_page_text(document: &Document) -> Option<String> {
+    let text = document.extract_text(&[1]).ok()?;
+    let text = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = match text.char_indices().nth(FIRST_PAGE_TEXT_LENGTH) {
+        Some((end, _)) => String::from(&text[..end]),
+        None => text,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+pub fn read_pdf_metadata(content: &[u8]) -> Result<PdfMetadata, String> {
+    let document =
+        Document::load_mem(content).map_err(|err| format!("The PDF could not be read: {}", err))?;
+
+    let info = document
+        .trailer
+        .get(b"Info")
+        .and_then(|info| document.dereference(info))
+        .and_then(|(_, info)| info.as_dict())
+        .ok();
+    let text = |key: &[u8]| info.and_then(|info| info_text(&document, info, key));
+
+    Ok(PdfMetadata {
+        page_count: document.get_pages().len() as i32,
+        title: text(b"Title").filter(|title| !is_placeholder_title(title)),
+        authors: text(b"Author")
+            .map(|author| authors_of(&author))
+            .unwrap_or_default(),
+        creation_date: text(b"CreationDate").and_then(|date| parse_pdf_date(&date)),
+        first_page_text: first_page_text(&document),
+    })
+}
+
+// What the PDF says about the entry it is the text of.
+pub fn page_metadata_of_pdf(pdf: &PdfMetadata) -> PageMetadata {
+    let entry_type = match pdf.page_count {
+        0 => None,
+        pages if pages > MAX_PAPER_PAGES => Some(EntryType::Book { pages }),
+        pages => Some(EntryType::Paper { pages }),
+    };
+    PageMetadata {
+        title: pdf.title.clone(),
+        authors: pdf.authors.clone(),
+        date_published: pdf.creation_date,
+        entry_type,
+        ..PageMetadata::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::Month;
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, text_string, Object, Stream};
+
+    const PAPER: &[u8] = include_bytes!("../fixtures/pdf/the-frontier-of-movies.pdf");
+
+    // A PDF with a page for each text and the information dictionary, if any.
+    fn pdf(pages: &[&str], info: Option<Dictionary>) -> Vec<u8> {
+        let mut document = Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+        let font_id = document.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let resources_id = document.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+        let kids = pages
+            .iter()
+            .map(|text| {
+                let content = Content {
+                    operations: vec![
+                        Operation::new("BT", vec![]),
+                        Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                        Operation::new("Td", vec![72.into(), 720.into()]),
+                        Operation::new("Tj", vec![Object::string_literal(*text)]),
+                        Operation::new("ET", vec![]),
+                    ],
+                };
+                let content_id =
+                    document.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+                document
+                    .add_object(dictionary! {
+                        "Type" => "Page",
+                        "Parent" => pages_id,
+                        "Contents" => content_id,
+                    })
+                    .into()
+            })
+            .collect::<Vec<Object>>();
+        document.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => kids.len() as i64,
+                "Kids" => kids,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            }),
+        );
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        document.trailer.set("Root", catalog_id);
+        if let Some(info) = info {
+            let info_id = document.add_object(info);
+            document.trailer.set("Info", info_id);
+        }
+
+        let mut bytes = Vec::new();
+        document.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    // read_pdf_metadata
+
+    #[test]
+    fn metadata_of_a_paper() {
+        assert_eq!(
+            read_pdf_metadata(PAPER).unwrap(),
+            PdfMetadata {
+                page_count: 3,
+                title: Some(String::from("The Frontier of Movies: Bonnie and Clyde")),
+                authors: vec![String::from("Pauline Kael"), String::from("Arthur Penn")],
+                creation_date: Some(Date {
+                    day: 15,
+                    month: Month::January,
+                    year: 1968
+                }),
+                first_page_text: Some(String::from(
+                    "The Frontier of Movies\nPauline Kael\nHow do you make a good movie in this country without being jumped on?"
+                )),
+            }
+        );
+    }
+
+    #[test]
+    fn info_strings_in_utf_16_are_decoded() {
+        let content = pdf(
+            &["Critica"],
+            Some(dictionary! {
+                "Title" => text_string("Bonnie y Clyde: crítica de la película"),
+                "Author" => text_string("Kael, Pauline and  Penn, Arthur"),
+            }),
+        );
+        let metadata = read_pdf_metadata(&content).unwrap();
+        assert_eq!(
+            metadata.title.as_deref(),
+            Some("Bonnie y Clyde: crítica de la película")
+        );
+        assert_eq!(metadata.authors, ["Pauline Kael", "Arthur Penn"]);
+    }
+
+    #[test]
+    fn titles_that_are_file_names_are_ignored() {
+        for title in [
+            "Microsoft Word - kael_final.docx",
+            "bonnie-and-clyde.tex",
+            "Untitled",
+        ] {
+            let content = pdf(&["Hello"], Some(dictionary! { "Title" => title }));
+            assert_eq!(
+                read_pdf_metadata(&content).unwrap().title,
+                None,
+                "{}",
+                title
+            );
+        }
+    }
+
+    #[test]
+    fn pdf_without_information_dictionary_has_pages_and_text() {
+        let metadata = read_pdf_metadata(&pdf(&["Bonnie", "and", "Clyde"], None)).unwrap();
+        assert_eq!(
+            metadata,
+            PdfMetadata {
+                page_count: 3,
+                first_page_text: Some(String::from("Bonnie")),
+                ..PdfMetadata::default()
+            }
+        );
+    }
+
+    #[test]
+    fn content_that_is_not_a_pdf_is_an_error() {
+        assert!(read_pdf_metadata(b"%PDF-1.4 and nothing else").is_err());
+        assert!(read_pdf_metadata(b"<html></html>").is_err());
+    }
+
+    // parse_pdf_date
+
+    #[test]
+    fn pdf_dates_with_and_without_time() {
+        let date = |day, month, year| Some(Date { day, month, year });
+        assert_eq!(
+            parse_pdf_date("D:19680115120000+01'00'"),
+            date(15, Month::January, 1968)
+        );
+        assert_eq!(parse_pdf_date("D:196710"), date(1, Month::October, 1967));
+        assert_eq!(parse_pdf_date("1967"), date(1, Month::January, 1967));
+        assert_eq!(parse_pdf_date("D:19671332"), None);
+        assert_eq!(parse_pdf_date("Monday"), None);
+    }
+
+    // page_metadata_of_pdf
+
+    #[test]
+    fn long_pdfs_are_books() {
+        let metadata = |page_count| {
+            page_metadata_of_pdf(&PdfMetadata {
+                page_count,
+                ..PdfMetadata::default()
+            })
+            .entry_type
+        };
+        assert_eq!(metadata(20), Some(EntryType::Paper { pages: 20 }));
+        assert_eq!(metadata(320), Some(EntryType::Book { pages: 320 }));
+        assert_eq!(metadata(0), None);
+    }
+}
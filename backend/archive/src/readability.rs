@@ -0,0 +1,405 @@
+// The main text of html pages, told apart from their menus, sidebars, comments and footers the way Readability does:
+// blocks of text score the elements that contain them, and the element with the best score is the article.
+
+use crate::html::{attribute, Token, Tokenizer};
+
+// Elements that are never part of the main text.
+const SKIPPED_ELEMENTS: [&str; 17] = [
+    "head", "script", "style", "noscript", "template", "nav", "aside", "footer", "form", "iframe",
+    "svg", "button", "select", "textarea", "object", "embed", "canvas",
+];
+
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+// Elements that are part of the line of text they are in instead of blocks of their own.
+const INLINE_ELEMENTS: [&str; 22] = [
+    "a", "abbr", "b", "bdi", "bdo", "cite", "code", "dfn", "em", "i", "kbd", "mark", "q", "s",
+    "small", "span", "strong", "sub", "sup", "time", "u", "var",
+];
+
+// Words in the class or id of elements that are unlikely to be part of the main text, unless they also have one of the
+// words of elements that may be.
+const UNLIKELY_HINTS: [&str; 21] = [
+    "banner",
+    "breadcrumbs",
+    "comment",
+    "community",
+    "disqus",
+    "footer",
+    "menu",
+    "related",
+    "remark",
+    "replies",
+    "rss",
+    "share",
+    "shoutbox",
+    "sidebar",
+    "skyscraper",
+    "social",
+    "sponsor",
+    "popup",
+    "pagination",
+    "pager",
+    "promo",
+];
+const MAYBE_HINTS: [&str; 5] = ["article", "body", "column", "content", "main"];
+
+// Words in the class or id of elements that usually hold, or never hold, the main text.
+const POSITIVE_HINTS: [&str; 10] = [
+    "article", "body", "content", "entry", "main", "page", "post", "text", "blog", "story",
+];
+const NEGATIVE_HINTS: [&str; 20] = [
+    "comment", "contact", "foot", "masthead", "media", "meta", "promo", "related", "scroll",
+    "share", "sidebar", "sponsor", "shopping", "tags", "tool", "widget", "nav", "menu", "banner",
+    "advert",
+];
+
+// Blocks with less text than this are not taken into account, like captions or bylines.
+const MIN_BLOCK_LENGTH: usize = 25;
+
+enum Node {
+    Element(usize),
+    Text(String),
+}
+
+struct Element {
+    name: String,
+    // Class and id, lowercased.
+    hints: String,
+    parent: Option<usize>,
+    children: Vec<Node>,
+    // Whether the element or any that contains it is never part of the main text.
+    skipped: bool,
+}
+
+fn has_hint(hints: &str, words: &[&str]) -> bool {
+    words.iter().any(|word| hints.contains(word))
+}
+
+struct Document {
+    elements: Vec<Element>,
+}
+
+impl Document {
+    fn parse(html_source: &str) -> Document {
+        let mut elements = vec![Element {
+            name: String::new(),
+            hints: String::new(),
+            parent: None,
+            children: Vec::new(),
+            skipped: false,
+        }];
+        let mut open = vec![0];
+
+        for token in Tokenizer::new(html_source) {
+            let current = *open.last().unwrap();
+            match token {
+                Token::StartTag { name, attributes } => {
+                    // Paragraphs and list items end where the next one starts.
+                    if (name == "p" || name == "li") && elements[current].name == name {
+                        open.pop();
+                    }
+                    let parent = *open.last().unwrap();
+                    let hints = format!(
+                        "{} {}",
+                        attribute(&attributes, "class").unwrap_or_default(),
+                        attribute(&attributes, "id").unwrap_or_default()
+                    )
+                    .to_lowercase();
+                    let index = elements.len();
+                    elements[parent].children.push(Node::Element(index));
+                    let is_void = VOID_ELEMENTS.contains(&name.as_str());
+                    let skipped = elements[parent].skipped
+                        || SKIPPED_ELEMENTS.contains(&name.as_str())
+                        || (has_hint(&hints, &UNLIKELY_HINTS) && !has_hint(&hints, &MAYBE_HINTS));
+                    elements.push(Element {
+                        name,
+                        hints,
+                        parent: Some(parent),
+                        children: Vec::new(),
+                        skipped,
+                    });
+                    if !is_void {
+                        open.push(index);
+                    }
+                }
+                // End tags of elements that are not open are ignored, and the elements that were left open inside
+                // of the one that ends are closed with it.
+                Token::EndTag { name } => {
+                    if let Some(position) = open
+                        .iter()
+                        .rposition(|index| *index != 0 && elements[*index].name == name)
+                    {
+                        open.truncate(position);
+                    }
+                }
+                Token::Text(text) => elements[current].children.push(Node::Text(text)),
+            }
+        }
+        Document { elements }
+    }
+
+    fn is_skipped(&self, index: usize) -> bool {
+        self.elements[index].skipped
+    }
+
+    fn is_inline(&self, index: usize) -> bool {
+        INLINE_ELEMENTS.contains(&self.elements[index].name.as_str())
+    }
+
+    // Appends the text of the element to the string, with blocks separated by spaces. Only the text of inline
+    // elements is included if only_inline is set, which is the text of the element as a block itself.
+    fn push_text(&self, index: usize, only_inline: bool, text: &mut String) {
+        for child in &self.elements[index].children {
+            match child {
+                Node::Text(child_text) => text.push_str(child_text),
+                Node::Element(child) if self.is_skipped(*child) => {}
+                Node::Element(child) if self.is_inline(*child) => {
+                    self.push_text(*child, only_inline, text)
+                }
+                Node::Element(_) if only_inline => text.push(' '),
+                Node::Element(child) => {
+                    text.push(' ');
+                    self.push_text(*child, only_inline, text);
+                    text.push(' ');
+                }
+            }
+        }
+    }
+
+    fn text(&self, index: usize, only_inline: bool) -> String {
+        let mut text = String::new();
+        self.push_text(index, only_inline, &mut text);
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    // Share of the text of the element that is in links.
+    fn link_density(&self, index: usize) -> f64 {
+        let length = self.text(index, false).len();
+        if length == 0 {
+            return 0.0;
+        }
+        let mut link_length = 0;
+        let mut pending = vec![index];
+        while let Some(element) = pending.pop() {
+            for child in &self.elements[element].children {
+                match child {
+                    Node::Element(child) if self.is_skipped(*child) => {}
+                    Node::Element(child) if self.elements[*child].name == "a" => {
+                        link_length += self.text(*child, false).len();
+                    }
+                    Node::Element(child) => pending.push(*child),
+                    Node::Text(_) => {}
+                }
+            }
+        }
+        link_length as f64 / length as f64
+    }
+
+    // How likely the element is to hold the main text because of its name, class and id alone.
+    fn initial_score(&self, index: usize) -> f64 {
+        let element = &self.elements[index];
+        let mut score = match element.name.as_str() {
+            "article" => 10.0,
+            "div" | "main" => 5.0,
+            "pre" | "td" | "blockquote" => 3.0,
+            "ol" | "ul" | "dl" | "li" | "dd" | "dt" => -3.0,
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+            _ => 0.0,
+        };
+        if has_hint(&element.hints, &POSITIVE_HINTS) {
+            score += 25.0;
+        }
+        if has_hint(&element.hints, &NEGATIVE_HINTS) {
+            score -= 25.0;
+        }
+        score
+    }
+
+    // Scores of the elements that contain blocks of text. Each block adds to the score of its parent, and half as much
+    // to the score of its grandparent.
+    fn scores(&self) -> Vec<Option<f64>> {
+        let mut scores: Vec<Option<f64>> = vec![None; self.elements.len()];
+        for index in 1..self.elements.len() {
+            if self.is_inline(index) || self.is_skipped(index) {
+                continue;
+            }
+            let text = self.text(index, true);
+            if text.chars().count() < MIN_BLOCK_LENGTH {
+                continue;
+            }
+            let block_score =
+                1.0 + text.matches(',').count() as f64 + (text.len() / 100).min(3) as f64;
+
+            let parent = self.elements[index].parent;
+            let grandparent = parent.and_then(|parent| self.elements[parent].parent);
+            for (ancestor, share) in [(parent, 1.0), (grandparent, 0.5)] {
+                if let Some(ancestor) = ancestor.filter(|ancestor| *ancestor != 0) {
+                    let score =
+                        scores[ancestor].get_or_insert_with(|| self.initial_score(ancestor));
+                    *score += block_score * share;
+                }
+            }
+        }
+
+        for (index, score) in scores.iter_mut().enumerate() {
+            if let Some(score) = score {
+                *score *= 1.0 - self.link_density(index);
+            }
+        }
+        scores
+    }
+}
+
+// Text of the article of the page, or None if no part of the page has enough text to be one.
+pub fn main_text(html_source: &str) -> Option<String> {
+    let document = Document::parse(html_source);
+    let scores = document.scores();
+
+    let (top, top_score) = scores
+        .iter()
+        .enumerate()
+        .filter_map(|(index, score)| Some((index, (*score)?)))
+        .fold(
+            None,
+            |best: Option<(usize, f64)>, (index, score)| match best {
+                Some((_, best_score)) if best_score >= score => best,
+                _ => Some((index, score)),
+            },
+        )?;
+
+    // Articles are often split in several elements next to each other, like pages or sections.
+    let threshold = (top_score * 0.2).max(10.0);
+    let siblings = match document.elements[top].parent {
+        Some(parent) if parent != 0 => document.elements[parent]
+            .children
+            .iter()
+            .filter_map(|child| match child {
+                Node::Element(child) => Some(*child),
+                Node::Text(_) => None,
+            })
+            .collect(),
+        _ => vec![top],
+    };
+    let text = siblings
+        .into_iter()
+        .filter(|sibling| {
+            *sibling == top
+                || scores[*sibling].is_some_and(|score| score >= threshold)
+                || (document.elements[*sibling].name == "p"
+                    && document.text(*sibling, false).len() > 80
+                    && document.link_density(*sibling) < 0.25)
+        })
+        .map(|sibling| document.text(sibling, false))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Some(text)
+}
+
+// Words of the text, not counting punctuation on its own.
+pub fn count_words(text: &str) -> i32 {
+    text.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count() as i32
+}
+
+// Words of the article of the page, or None if the page has no article.
+pub fn article_word_count(html_source: &str) -> Option<i32> {
+    main_text(html_source)
+        .map(|text| count_words(&text))
+        .filter(|words| *words > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE: &str = r#"
+        <html><head><title>Bonnie and Clyde | The New Yorker</title></head>
+        <body>
+            <header><a href="/">The New Yorker</a></header>
+            <nav><ul><li><a href="/news">News</a></li><li><a href="/culture">Culture</a></li></ul></nav>
+            <div class="page">
+                <article class="article-body">
+                    <h1>Bonnie and Clyde</h1>
+                    <p class="byline">By Pauline Kael</p>
+                    <p>How do you make a good movie in this country without being jumped on? <em>Bonnie and Clyde</em>
+                    is the most excitingly American American movie since The Manchurian Candidate.
+                    <p>The audience is alive to it. Our experience as we watch it has some connection with the way
+                    we reacted to movies in childhood: with how we came to love them and to feel they were ours.
+                    <script>var words = "not, part, of, the, article";</script>
+                </article>
+                <div class="related-stories sidebar">
+                    <p><a href="/1">The Godfather, reviewed, again</a>, <a href="/2">Five Easy Pieces, revisited, once more</a></p>
+                </div>
+            </div>
+            <div id="comments"><p>Great review, I loved it, thank you, Pauline!</p></div>
+            <footer><p>Copyright, Condé Nast, all rights reserved, everywhere, forever.</p></footer>
+        </body></html>
+    "#;
+
+    // main_text
+
+    #[test]
+    fn main_text_is_the_article_without_the_boilerplate() {
+        assert_eq!(
+            main_text(ARTICLE).as_deref(),
+            Some(
+                "Bonnie and Clyde By Pauline Kael How do you make a good movie in this country without being jumped \
+                on? Bonnie and Clyde is the most excitingly American American movie since The Manchurian Candidate. \
+                The audience is alive to it. Our experience as we watch it has some connection with the way we \
+                reacted to movies in childhood: with how we came to love them and to feel they were ours."
+            )
+        );
+    }
+
+    #[test]
+    fn articles_split_in_sections_are_joined() {
+        let html = r#"
+            <body><div id="content">
+                <div class="section"><p>First part, with enough text in it to count as a block of the article.</p>
+                <p>Second paragraph of the first part, which also has enough text to count.</p></div>
+                <div class="section"><p>Second part, with enough text in it to count as a block of the article.</p>
+                <p>Second paragraph of the second part, which also has enough text to count.</p></div>
+                <div class="share-tools"><a href="/share">Share this article with your friends</a></div>
+            </div></body>
+        "#;
+
+        let text = main_text(html).unwrap();
+        assert!(text.starts_with("First part"));
+        assert!(text.contains("Second part"));
+        assert!(!text.contains("Share"));
+    }
+
+    #[test]
+    fn pages_without_blocks_of_text_have_no_main_text() {
+        assert_eq!(main_text("<html><body>Hello</body></html>"), None);
+        assert_eq!(
+            main_text("<html><head><title>A title that is long enough to be a block</title></head></html>"),
+            None
+        );
+    }
+
+    // count_words
+
+    #[test]
+    fn count_words_ignores_punctuation() {
+        assert_eq!(
+            count_words("Bonnie & Clyde — a review, by Pauline Kael."),
+            7
+        );
+        assert_eq!(count_words("  "), 0);
+    }
+
+    // article_word_count
+
+    #[test]
+    fn word_count_of_an_article() {
+        assert_eq!(article_word_count(ARTICLE), Some(71));
+        assert_eq!(article_word_count("<p>Hello</p>"), None);
+    }
+}
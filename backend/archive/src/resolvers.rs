@@ -0,0 +1,562 @@
+// Metadata of papers and books from the registries of their identifiers: CrossRef for DOIs, the arXiv API for arXiv
+// ids and Open Library for ISBNs. Their base urls are configurable, so that a local stand-in can be used instead.
+
+use crate::date::{self, Date};
+use crate::entry_type::EntryType;
+use crate::html::decode_entities;
+use crate::html_meta::{author_name, parse_date, PageMetadata};
+use crate::identifiers::Identifier;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::Deserialize;
+use serde_json::Value;
+
+// Characters of DOIs that can't be in the path of a url as they are.
+const DOI_ESCAPED: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+// CrossRef types of works that are whole books rather than papers or chapters.
+const CROSSREF_BOOK_TYPES: [&str; 5] = [
+    "book",
+    "monograph",
+    "edited-book",
+    "reference-book",
+    "book-set",
+];
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct ResolverUrls {
+    pub crossref: String,
+    pub arxiv: String,
+    pub openlibrary: String,
+}
+
+impl Default for ResolverUrls {
+    fn default() -> ResolverUrls {
+        ResolverUrls {
+            crossref: String::from("https://api.crossref.org/works"),
+            arxiv: String::from("https://export.arxiv.org/api/query"),
+            openlibrary: String::from("https://openlibrary.org/api/books"),
+        }
+    }
+}
+
+pub trait Resolver: Send + Sync {
+    // Url of the record of the identifier in the registry.
+    fn record_url(&self, identifier: &Identifier) -> String;
+    // What the record says about the text, or None if it is not a record of one.
+    fn read_record(&self, record: &str) -> Option<PageMetadata>;
+}
+
+pub struct CrossRef {
+    pub base_url: String,
+}
+
+pub struct Arxiv {
+    pub base_url: String,
+}
+
+pub struct OpenLibrary {
+    pub base_url: String,
+}
+
+impl ResolverUrls {
+    // The resolver of the registry of the identifier.
+    pub fn resolver_for(&self, identifier: &Identifier) -> Box<dyn Resolver> {
+        match identifier {
+            Identifier::Doi(_) => Box::new(CrossRef {
+                base_url: self.crossref.clone(),
+            }),
+            Identifier::Arxiv(_) => Box::new(Arxiv {
+                base_url: self.arxiv.clone(),
+            }),
+            Identifier::Isbn(_) => Box::new(OpenLibrary {
+                base_url: self.openlibrary.clone(),
+            }),
+        }
+    }
+}
+
+// The value of the identifier, without the prefix of its kind.
+fn value_of(identifier: &Identifier) -> &str {
+    match identifier {
+        Identifier::Doi(value) | Identifier::Arxiv(value) | Identifier::Isbn(value) => value,
+    }
+}
+
+fn text(value: &Value) -> Option<String> {
+    value
+        .as_str()
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|text| !text.is_empty())
+}
+
+// Text of markup like the JATS of CrossRef abstracts, without its tags and with its whitespace collapsed.
+fn without_tags(markup: &str) -> String {
+    let mut text = String::with_capacity(markup.len());
+    let mut in_tag = false;
+    for c in markup.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    decode_entities(&text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn date_of_parts(year: i64, month: i64, day: i64) -> Option<Date> {
+    date::read_sql_date(&format!("{:04}-{:02}-{:02}", year, month, day))
+        .filter(|date| date::is_valid(*date))
+}
+
+// Number of pages of a range like "112-131". Ranges of article numbers like "e1001" say nothing.
+fn pages_of_range(range: &str) -> i32 {
+    match range.split_once(['-', '–']) {
+        Some((first, last)) => match (first.trim().parse::<i32>(), last.trim().parse::<i32>()) {
+            (Ok(first), Ok(last)) if last >= first => last - first + 1,
+            _ => 0,
+        },
+        None => 0,
+    }
+}
+
+impl Resolver for CrossRef {
+    fn record_url(&self, identifier: &Identifier) -> String {
+        format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            utf8_percent_encode(value_of(identifier), DOI_ESCAPED)
+        )
+    }
+
+    // Records of works are like {"message": {"title": [...], "author": [{"given": ..., "family": ...}],
+    // "issued": {"date-parts": [[2019, 4, 4]]}, "page": "507-521", "type": "journal-article", ...}}.
+    fn read_record(&self, record: &str) -> Option<PageMetadata> {
+        let record: Value = serde_json::from_str(record).ok()?;
+        let work = record.get("message").filter(|work| work.is_object())?;
+
+        let title = text(&work["title"][0]).map(|title| match text(&work["subtitle"][0]) {
+            Some(subtitle) => format!("{}: {}", title, subtitle),
+            None => title,
+        });
+        let authors = work["author"]
+            .as_array()
+            .map(|authors| {
+                authors
+                    .iter()
+                    .filter_map(
+                        |author| match (text(&author["given"]), text(&author["family"])) {
+                            (Some(given), Some(family)) => Some(format!("{} {}", given, family)),
+                            (None, Some(family)) => Some(family),
+                            _ => text(&author["name"]),
+                        },
+                    )
+                    .collect()
+            })
+            .unwrap_or_default();
+        let parts = &work["issued"]["date-parts"][0];
+        let date_published = parts[0].as_i64().and_then(|year| {
+            date_of_parts(
+                year,
+                parts[1].as_i64().unwrap_or(1),
+                parts[2].as_i64().unwrap_or(1),
+            )
+        });
+        let pages = text(&work["page"])
+            .map(|range| pages_of_range(&range))
+            .unwrap_or(0);
+        let entry_type = match work["type"].as_str() {
+            Some(kind) if CROSSREF_BOOK_TYPES.contains(&kind) => EntryType::Book { pages },
+            _ => EntryType::Paper { pages },
+        };
+
+        Some(PageMetadata {
+            title,
+            description: text(&work["abstract"])
+                .map(|jats| without_tags(&jats))
+                .filter(|description| !description.is_empty()),
+            authors,
+            date_published,
+            entry_type: Some(entry_type),
+            ..PageMetadata::default()
+        })
+    }
+}
+
+// Contents of every element with the name, as written, from the start tag to the end tag.
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let start_tag = format!("<{}", name);
+    let end_tag = format!("</{}>", name);
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&start_tag) {
+        rest = &rest[start + start_tag.len()..];
+        if !rest.starts_with(['>', ' ', '\t', '\r', '\n']) {
+            continue;
+        }
+        let Some(content_start) = rest.find('>') else {
+            break;
+        };
+        rest = &rest[content_start + 1..];
+        let Some(end) = rest.find(&end_tag) else {
+            break;
+        };
+        found.push(&rest[..end]);
+        rest = &rest[end + end_tag.len()..];
+    }
+    found
+}
+
+fn element_text(xml: &str, name: &str) -> Option<String> {
+    elements(xml, name)
+        .first()
+        .map(|content| without_tags(content))
+        .filter(|text| !text.is_empty())
+}
+
+// arXiv comments usually say how long the paper is, like "15 pages, 5 figures".
+fn pages_of_comment(comment: &str) -> i32 {
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    words
+        .windows(2)
+        .find(|pair| pair[1].trim_end_matches([',', '.', ';']) == "pages")
+        .and_then(|pair| pair[0].parse().ok())
+        .unwrap_or(0)
+}
+
+impl Resolver for Arxiv {
+    fn record_url(&self, identifier: &Identifier) -> String {
+        format!("{}?id_list={}", self.base_url, value_of(identifier))
+    }
+
+    // Records are Atom feeds with an <entry> for the paper, or one whose id is that of an error.
+    fn read_record(&self, record: &str) -> Option<PageMetadata> {
+        let entry = *elements(record, "entry").first()?;
+        if !element_text(entry, "id")?.contains("/abs/") {
+            return None;
+        }
+
+        Some(PageMetadata {
+            title: element_text(entry, "title"),
+            description: element_text(entry, "summary"),
+            authors: elements(entry, "author")
+                .iter()
+                .filter_map(|author| element_text(author, "name"))
+                .collect(),
+            date_published: element_text(entry, "published").and_then(|date| parse_date(&date)),
+            entry_type: Some(EntryType::Paper {
+                pages: element_text(entry, "arxiv:comment")
+                    .map(|comment| pages_of_comment(&comment))
+                    .unwrap_or(0),
+            }),
+            ..PageMetadata::default()
+        })
+    }
+}
+
+fn month_of_name(name: &str) -> Option<i64> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let name = name.to_lowercase();
+    MONTHS
+        .iter()
+        .position(|month| name.starts_with(month))
+        .map(|index| index as i64 + 1)
+}
+
+// Open Library writes dates as they are printed in the book, like "1994", "March 1994", "Mar 03, 1994" or
+// "1994-03-03".
+fn parse_publish_date(text: &str) -> Option<Date> {
+    if let Some(date) = parse_date(text) {
+        return Some(date);
+    }
+    let words: Vec<&str> = text
+        .split([' ', ',', '.'])
+        .filter(|word| !word.is_empty())
+        .collect();
+    let year = words
+        .iter()
+        .find(|word| word.len() == 4 && word.bytes().all(|byte| byte.is_ascii_digit()))?
+        .parse()
+        .ok()?;
+    let month = words.iter().find_map(|word| month_of_name(word));
+    let day = words
+        .iter()
+        .filter(|word| word.len() <= 2)
+        .find_map(|word| word.parse::<i64>().ok());
+    date_of_parts(year, month.unwrap_or(1), month.and(day).unwrap_or(1))
+}
+
+impl Resolver for OpenLibrary {
+    fn record_url(&self, identifier: &Identifier) -> String {
+        format!(
+            "{}?bibkeys=ISBN:{}&format=json&jscmd=data",
+            self.base_url,
+            value_of(identifier)
+        )
+    }
+
+    // Records are like {"ISBN:9780141182605": {"title": ..., "authors": [{"name": ...}], "publish_date": ...,
+    // "number_of_pages": 432}}, or {} if there is no book with the ISBN.
+    fn read_record(&self, record: &str) -> Option<PageMetadata> {
+        let record: Value = serde_json::from_str(record).ok()?;
+        let book = record.as_object()?.values().next()?;
+
+        let title = text(&book["title"]).map(|title| match text(&book["subtitle"]) {
+            Some(subtitle) => format!("{}: {}", title, subtitle),
+            None => title,
+        });
+        Some(PageMetadata {
+            title,
+            authors: book["authors"]
+                .as_array()
+                .map(|authors| {
+                    authors
+                        .iter()
+                        .filter_map(|author| text(&author["name"]))
+                        .map(|name| author_name(&name))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            date_published: text(&book["publish_date"]).and_then(|date| parse_publish_date(&date)),
+            entry_type: Some(EntryType::Book {
+                pages: book["number_of_pages"].as_i64().unwrap_or(0) as i32,
+            }),
+            ..PageMetadata::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::Month;
+
+    fn resolvers() -> ResolverUrls {
+        ResolverUrls {
+            crossref: String::from("http://127.0.0.1:9000/works/"),
+            arxiv: String::from("http://127.0.0.1:9000/api/query"),
+            openlibrary: String::from("http://127.0.0.1:9000/api/books"),
+        }
+    }
+
+    fn read(identifier: &Identifier, record: &str) -> Option<PageMetadata> {
+        resolvers().resolver_for(identifier).read_record(record)
+    }
+
+    fn doi() -> Identifier {
+        Identifier::Doi(String::from("10.1145/3297858.3304013"))
+    }
+
+    fn arxiv() -> Identifier {
+        Identifier::Arxiv(String::from("1706.03762"))
+    }
+
+    fn isbn() -> Identifier {
+        Identifier::Isbn(String::from("9780141182605"))
+    }
+
+    // record_url
+
+    #[test]
+    fn record_urls_of_each_registry() {
+        let urls = resolvers();
+        assert_eq!(
+            urls.resolver_for(&doi()).record_url(&doi()),
+            "http://127.0.0.1:9000/works/10.1145/3297858.3304013"
+        );
+        let sici = Identifier::Doi(String::from(
+            "10.1002/(sici)1097-4571(199806)49:8<693::aid-asi4>3.0.co;2-0",
+        ));
+        assert_eq!(
+            urls.resolver_for(&sici).record_url(&sici),
+            "http://127.0.0.1:9000/works/10.1002/(sici)1097-4571(199806)49:8%3C693::aid-asi4%3E3.0.co;2-0"
+        );
+        assert_eq!(
+            urls.resolver_for(&arxiv()).record_url(&arxiv()),
+            "http://127.0.0.1:9000/api/query?id_list=1706.03762"
+        );
+        assert_eq!(
+            urls.resolver_for(&isbn()).record_url(&isbn()),
+            "http://127.0.0.1:9000/api/books?bibkeys=ISBN:9780141182605&format=json&jscmd=data"
+        );
+    }
+
+    // read_record
+
+    #[test]
+    fn crossref_record_of_a_paper() {
+        let record = r#"{"status": "ok", "message-type": "work", "message": {
+            "title": ["Bonnie and Clyde and the  New Hollywood"],
+            "author": [
+                {"given": "Pauline", "family": "Kael", "sequence": "first"},
+                {"given": "Arthur", "family": "Penn", "sequence": "additional"},
+                {"name": "The Film Society", "sequence": "additional"}
+            ],
+            "abstract": "<jats:p>How do you make a good movie in this country &amp; not get jumped on?</jats:p>",
+            "issued": {"date-parts": [[1967, 10]]},
+            "page": "112-131",
+            "type": "journal-article"
+        }}"#;
+        assert_eq!(
+            read(&doi(), record),
+            Some(PageMetadata {
+                title: Some(String::from("Bonnie and Clyde and the New Hollywood")),
+                description: Some(String::from(
+                    "How do you make a good movie in this country & not get jumped on?"
+                )),
+                authors: vec![
+                    String::from("Pauline Kael"),
+                    String::from("Arthur Penn"),
+                    String::from("The Film Society")
+                ],
+                date_published: Some(Date {
+                    day: 1,
+                    month: Month::October,
+                    year: 1967
+                }),
+                entry_type: Some(EntryType::Paper { pages: 20 }),
+                ..PageMetadata::default()
+            })
+        );
+    }
+
+    #[test]
+    fn crossref_records_of_books_have_subtitles() {
+        let record = r#"{"message": {"title": ["Kiss Kiss Bang Bang"], "subtitle": ["Film Writings"],
+            "type": "book", "issued": {"date-parts": [[1968, 3, 12]]}}}"#;
+        let metadata = read(&doi(), record).unwrap();
+        assert_eq!(
+            metadata.title.as_deref(),
+            Some("Kiss Kiss Bang Bang: Film Writings")
+        );
+        assert_eq!(metadata.entry_type, Some(EntryType::Book { pages: 0 }));
+        assert_eq!(metadata.authors, Vec::<String>::new());
+    }
+
+    #[test]
+    fn arxiv_record_of_a_paper() {
+        let record = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+              <title type="html">ArXiv Query: search_query=&amp;id_list=1706.03762</title>
+              <entry>
+                <id>http://arxiv.org/abs/1706.03762v7</id>
+                <updated>2023-08-02T00:41:18Z</updated>
+                <published>2017-06-12T17:57:34Z</published>
+                <title>Attention Is All You
+          Need</title>
+                <summary>  The dominant sequence transduction models are based on complex recurrent or
+        convolutional neural networks &amp; attention.
+        </summary>
+                <author><name>Ashish Vaswani</name></author>
+                <author><name>Noam Shazeer</name></author>
+                <arxiv:comment xmlns:arxiv="http://arxiv.org/schemas/atom">15 pages, 5 figures</arxiv:comment>
+                <link href="http://arxiv.org/abs/1706.03762v7" rel="alternate" type="text/html"/>
+              </entry>
+            </feed>"#;
+        assert_eq!(
+            read(&arxiv(), record),
+            Some(PageMetadata {
+                title: Some(String::from("Attention Is All You Need")),
+                description: Some(String::from(
+                    "The dominant sequence transduction models are based on complex recurrent or convolutional neural networks & attention."
+                )),
+                authors: vec![String::from("Ashish Vaswani"), String::from("Noam Shazeer")],
+                date_published: Some(Date {
+                    day: 12,
+                    month: Month::June,
+                    year: 2017
+                }),
+                entry_type: Some(EntryType::Paper { pages: 15 }),
+                ..PageMetadata::default()
+            })
+        );
+    }
+
+    #[test]
+    fn arxiv_errors_are_no_record() {
+        let record = r#"<feed><entry>
+            <id>http://arxiv.org/api/errors#incorrect_id_format_for_1706.0376</id>
+            <title>Error</title>
+            <summary>incorrect id format for 1706.0376</summary>
+            </entry></feed>"#;
+        assert_eq!(read(&arxiv(), record), None);
+        assert_eq!(read(&arxiv(), "<feed><title>Empty</title></feed>"), None);
+    }
+
+    #[test]
+    fn openlibrary_record_of_a_book() {
+        let record = r#"{"ISBN:9780141182605": {
+            "url": "https://openlibrary.org/books/OL7353617M/Kiss_Kiss_Bang_Bang",
+            "title": "Kiss Kiss Bang Bang",
+            "authors": [{"url": "https://openlibrary.org/authors/OL31215A", "name": "Kael, Pauline"}],
+            "number_of_pages": 404,
+            "publish_date": "Mar 03, 1968"
+        }}"#;
+        assert_eq!(
+            read(&isbn(), record),
+            Some(PageMetadata {
+                title: Some(String::from("Kiss Kiss Bang Bang")),
+                authors: vec![String::from("Pauline Kael")],
+                date_published: Some(Date {
+                    day: 3,
+                    month: Month::March,
+                    year: 1968
+                }),
+                entry_type: Some(EntryType::Book { pages: 404 }),
+                ..PageMetadata::default()
+            })
+        );
+        assert_eq!(read(&isbn(), "{}"), None);
+    }
+
+    // parse_publish_date
+
+    #[test]
+    fn publish_dates_as_printed() {
+        let date = |day, month, year| Some(Date { day, month, year });
+        assert_eq!(parse_publish_date("1968"), date(1, Month::January, 1968));
+        assert_eq!(
+            parse_publish_date("March 1968"),
+            date(1, Month::March, 1968)
+        );
+        assert_eq!(
+            parse_publish_date("12 March 1968"),
+            date(12, Month::March, 1968)
+        );
+        assert_eq!(
+            parse_publish_date("1968-03-12"),
+            date(12, Month::March, 1968)
+        );
+        assert_eq!(parse_publish_date("Spring"), None);
+    }
+
+    // pages_of_range
+
+    #[test]
+    fn pages_of_ranges_of_pages() {
+        assert_eq!(pages_of_range("112-131"), 20);
+        assert_eq!(pages_of_range("7–7"), 1);
+        assert_eq!(pages_of_range("e1001"), 0);
+        assert_eq!(pages_of_range("131-112"), 0);
+    }
+}
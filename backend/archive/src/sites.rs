@@ -0,0 +1,268 @@
+// The sites entries are published in, by the canonical domain of their links. Every entry has the site of its link,
+// kept with it whenever it is saved. The name and favicon of a site are a separate capture, from the first of its pages
+// an entry is created from, and kept from then on. Sites that are never captured are listed all the same, without them.
+
+use crate::html_meta::parse_html_head;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct Site {
+    // Host of the canonical link of its pages, like example.com.
+    pub domain: String,
+    // As its pages name it in og:site_name.
+    pub name: Option<String>,
+    // Link to its favicon, a 32x32 png.
+    pub favicon: Option<String>,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct SiteWithCount {
+    #[serde(flatten)]
+    pub site: Site,
+    // Number of entries outside the trash.
+    pub entries: usize,
+}
+
+// What a page says about the site it is in.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SiteOfPage {
+    pub name: Option<String>,
+    // As written in the page, so it may be relative to the page's url. /favicon.ico if the page names no icon.
+    pub favicon_link: String,
+}
+
+pub fn site_of_page(html_source: &str) -> SiteOfPage {
+    let head = parse_html_head(html_source);
+    SiteOfPage {
+        name: head
+            .meta_tags
+            .iter()
+            .find(|tag| {
+                tag.property.as_deref() == Some("og:site_name") && !tag.content.trim().is_empty()
+            })
+            .map(|tag| tag.content.trim().to_owned()),
+        favicon_link: head
+            .icon_link
+            .unwrap_or_else(|| String::from("/favicon.ico")),
+    }
+}
+
+pub fn favicon_link(domain: &str) -> String {
+    format!("/api/sites/{}/favicon", domain)
+}
+
+// The site of an entry, read from the site column of the entry and the name and favicon columns of its site.
+pub(crate) fn read_site(
+    domain: Option<String>,
+    name: Option<String>,
+    has_favicon: Option<bool>,
+) -> Option<Site> {
+    domain.map(|domain| site_of_domain(domain, name, has_favicon))
+}
+
+// The name and favicon are None if the site hasn't been captured.
+fn site_of_domain(domain: String, name: Option<String>, has_favicon: Option<bool>) -> Site {
+    Site {
+        favicon: has_favicon.unwrap_or(false).then(|| favicon_link(&domain)),
+        domain,
+        name,
+    }
+}
+
+// Whether the name and favicon of the site have already been captured, so that they aren't fetched again.
+pub fn is_site_captured(database: &rusqlite::Connection, domain: &str) -> rusqlite::Result<bool> {
+    database.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sites WHERE domain = ?)",
+        [domain],
+        |row| row.get(0),
+    )
+}
+
+// Keeps the name and favicon, which must be normalized, of a site that hasn't been captured yet. Either of them can be
+// missing, if the page names none or it can't be fetched, and the site is captured all the same.
+pub fn insert_site(
+    database: &rusqlite::Connection,
+    domain: &str,
+    name: Option<&str>,
+    favicon: Option<&[u8]>,
+) -> rusqlite::Result<usize> {
+    database.execute(
+        "INSERT OR IGNORE INTO sites (domain, site_name, favicon) VALUES (?, ?, ?)",
+        rusqlite::params![domain, name, favicon],
+    )
+}
+
+// Returns None if the site hasn't been captured or it has no favicon.
+pub fn select_favicon(
+    database: &rusqlite::Connection,
+    domain: &str,
+) -> rusqlite::Result<Option<Vec<u8>>> {
+    let mut statement = database.prepare("SELECT favicon FROM sites WHERE domain = ?")?;
+    let mut rows = statement.query([domain])?;
+    match rows.next()? {
+        Some(row) => Ok(row.get_ref(0)?.as_blob_or_null()?.map(Vec::from)),
+        None => Ok(None),
+    }
+}
+
+// Sites of the entries outside the trash, with the most entries first. Sites that haven't been captured are listed
+// without name or favicon.
+pub fn select_sites(database: &rusqlite::Connection) -> rusqlite::Result<Vec<SiteWithCount>> {
+    let mut statement = database.prepare(
+        "SELECT entries.site, sites.site_name, sites.favicon IS NOT NULL, count(*) AS entry_count
+        FROM entries LEFT JOIN sites ON sites.domain = entries.site
+        WHERE entries.date_deleted IS NULL AND entries.site IS NOT NULL
+        GROUP BY entries.site
+        ORDER BY entry_count DESC, entries.site",
+    )?;
+    let sites = statement
+        .query_map([], |row| {
+            Ok(SiteWithCount {
+                site: site_of_domain(row.get(0)?, row.get(1)?, row.get(2)?),
+                entries: row.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(sites)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{create_tables, delete_entry, insert_entry, select_entry};
+    use crate::date::{Date, Month};
+    use crate::entry::NewEntryForm;
+    use crate::entry_type::EntryType;
+
+    fn open_in_memory() -> rusqlite::Connection {
+        let database = rusqlite::Connection::open_in_memory().unwrap();
+        create_tables(&database).unwrap();
+        database
+    }
+
+    fn new_entry_form(link: &str) -> NewEntryForm {
+        NewEntryForm {
+            link: String::from(link),
+            title: String::from("Bonnie and Clyde"),
+            description: String::new(),
+            authors: vec![String::from("Pauline Kael")],
+            category: String::from("Cinema"),
+            themes: Vec::new(),
+            works_mentioned: Vec::new(),
+            tags: Vec::new(),
+            date_published: Date {
+                day: 21,
+                month: Month::October,
+                year: 1967,
+            },
+            date_saved: None,
+            exceptional: false,
+            entry_type: EntryType::Article { words: 1000 },
+            identifier: None,
+        }
+    }
+
+    // site_of_page
+
+    #[test]
+    fn site_of_a_page_with_name_and_icon() {
+        let site = site_of_page(
+            r#"<meta property="og:site_name" content=" The New Yorker ">
+            <link rel="icon" href="/favicon-32x32.png">"#,
+        );
+        assert_eq!(site.name.as_deref(), Some("The New Yorker"));
+        assert_eq!(site.favicon_link, "/favicon-32x32.png");
+    }
+
+    #[test]
+    fn site_of_a_page_without_name_or_icon() {
+        let site = site_of_page(r#"<meta name="og:site_name" content="Not a property">"#);
+        assert_eq!(site.name, None);
+        assert_eq!(site.favicon_link, "/favicon.ico");
+    }
+
+    // insert_site, is_site_captured, select_entry
+
+    #[test]
+    fn entries_have_the_site_of_their_link_once_it_is_captured() {
+        let database = open_in_memory();
+        let id = insert_entry(
+            &database,
+            &new_entry_form("https://www.NewYorker.com/magazine/bonnie-and-clyde"),
+        )
+        .unwrap();
+        assert!(!is_site_captured(&database, "newyorker.com").unwrap());
+        assert_eq!(
+            select_entry(&database, id).unwrap().unwrap().site,
+            Some(Site {
+                domain: String::from("newyorker.com"),
+                name: None,
+                favicon: None,
+            })
+        );
+
+        insert_site(
+            &database,
+            "newyorker.com",
+            Some("The New Yorker"),
+            Some(&[1, 2, 3]),
+        )
+        .unwrap();
+        // Captured once, the site is kept as it is.
+        insert_site(&database, "newyorker.com", Some("Other"), None).unwrap();
+        assert!(is_site_captured(&database, "newyorker.com").unwrap());
+        assert_eq!(
+            select_entry(&database, id).unwrap().unwrap().site,
+            Some(Site {
+                domain: String::from("newyorker.com"),
+                name: Some(String::from("The New Yorker")),
+                favicon: Some(String::from("/api/sites/newyorker.com/favicon")),
+            })
+        );
+        assert_eq!(
+            select_favicon(&database, "newyorker.com").unwrap(),
+            Some(vec![1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn entries_without_a_web_link_have_no_site() {
+        // Only entries saved before links were validated can have one.
+        let database = open_in_memory();
+        let id = insert_entry(&database, &new_entry_form("Kiss Kiss Bang Bang")).unwrap();
+        assert_eq!(select_entry(&database, id).unwrap().unwrap().site, None);
+    }
+
+    // select_sites
+
+    #[test]
+    fn sites_are_listed_with_their_number_of_entries() {
+        let database = open_in_memory();
+        insert_entry(&database, &new_entry_form("https://example.com/a")).unwrap();
+        insert_entry(&database, &new_entry_form("https://newyorker.com/a")).unwrap();
+        insert_entry(&database, &new_entry_form("https://www.newyorker.com/b")).unwrap();
+        let deleted = insert_entry(&database, &new_entry_form("https://elpais.com/a")).unwrap();
+        delete_entry(&database, deleted).unwrap();
+        insert_entry(&database, &new_entry_form("Kiss Kiss Bang Bang")).unwrap();
+        insert_site(&database, "newyorker.com", Some("The New Yorker"), None).unwrap();
+
+        let sites = select_sites(&database)
+            .unwrap()
+            .into_iter()
+            .map(|site| (site.site.domain, site.site.name, site.entries))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sites,
+            [
+                (
+                    String::from("newyorker.com"),
+                    Some(String::from("The New Yorker")),
+                    2
+                ),
+                (String::from("example.com"), None, 1),
+            ]
+        );
+    }
+}
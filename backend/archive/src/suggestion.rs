@@ -0,0 +1,540 @@
+// Entries suggested for pages, merging what each of the vocabularies of a page says about it. Every field of the
+// suggestion is taken from the vocabulary most likely to be right about it, which is recorded along with it.
+
+use crate::charset::decode_text;
+use crate::entry_type::{self, EntryType};
+use crate::html_meta::{self, PageMetadata};
+use crate::json_ld::json_ld_metadata;
+use crate::media;
+use crate::pdf;
+use crate::readability::article_word_count;
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+// Where a field of a suggestion was found.
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    JsonLd,
+    Highwire,
+    OpenGraph,
+    DublinCore,
+    TwitterCard,
+    Html,
+    // The text of the page itself.
+    Content,
+    // The document information and pages of a PDF.
+    Pdf,
+    // The headers and tags of an audio or video file.
+    Media,
+    // The oEmbed endpoint the page links to.
+    #[serde(rename = "oembed")]
+    OEmbed,
+    // The registry of the DOI, arXiv id or ISBN of the text, like CrossRef.
+    Registry,
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, PartialEq)]
+pub struct Provenance {
+    pub source: Source,
+    // How likely the value is to be right, from 0 to 1.
+    pub confidence: f64,
+}
+
+#[derive(Serialize, ToSchema, Debug, Default, PartialEq)]
+pub struct SuggestedEntry {
+    #[serde(flatten)]
+    pub metadata: PageMetadata,
+    // Where each field of the metadata that has a value was found.
+    pub provenance: BTreeMap<String, Provenance>,
+}
+
+// How likely each source is to be right about each field.
+fn confidence(source: Source, field: &str) -> f64 {
+    match (source, field) {
+        (Source::JsonLd, "canonical_link") => 0.8,
+        (Source::JsonLd, _) => 0.9,
+        (Source::Highwire, "description") => 0.8,
+        (Source::Highwire, _) => 0.9,
+        // Sites give og:type article to everything that is not a video or a song.
+        (Source::OpenGraph, "entry_type") => 0.6,
+        (Source::OpenGraph, "image") => 0.9,
+        (Source::OpenGraph, _) => 0.8,
+        (Source::DublinCore, "entry_type") => 0.5,
+        (Source::DublinCore, _) => 0.7,
+        (Source::TwitterCard, "image") => 0.8,
+        (Source::TwitterCard, _) => 0.7,
+        // The <title> usually has the name of the site as well.
+        (Source::Html, "title") => 0.4,
+        (Source::Html, "canonical_link") => 0.95,
+        (Source::Html, _) => 0.6,
+        // Any page with enough text looks like an article.
+        (Source::Content, _) => 0.3,
+        // Whoever made the file, on the day the file was made.
+        (Source::Pdf, "date_published") => 0.3,
+        (Source::Pdf, "entry_type") => 0.7,
+        (Source::Pdf, _) => 0.5,
+        // Whether the file has video and how long it plays are never wrong.
+        (Source::Media, "entry_type") => 0.9,
+        (Source::Media, _) => 0.5,
+        // Written by the site for embedding the page elsewhere, so without the name of the site in the title.
+        (Source::OEmbed, _) => 0.85,
+        // Written by the publisher for citing the text, so the most likely to be right about everything it has.
+        (Source::Registry, _) => 0.95,
+    }
+}
+
+// Value of the field from the source most likely to be right about it. On a tie, the first source wins.
+fn pick<T>(
+    sources: &[(Source, PageMetadata)],
+    field: &str,
+    value_of: impl Fn(&PageMetadata) -> Option<T>,
+    provenance: &mut BTreeMap<String, Provenance>,
+) -> Option<T> {
+    let mut best: Option<(T, Provenance)> = None;
+    for (source, metadata) in sources {
+        let confidence = confidence(*source, field);
+        if best
+            .as_ref()
+            .is_some_and(|(_, best)| best.confidence >= confidence)
+        {
+            continue;
+        }
+        if let Some(value) = value_of(metadata) {
+            best = Some((
+                value,
+                Provenance {
+                    source: *source,
+                    confidence,
+                },
+            ));
+        }
+    }
+
+    let (value, source) = best?;
+    provenance.insert(String::from(field), source);
+    Some(value)
+}
+
+// Fills in the length of the entry type from another source with the same type, for when the one that is most likely
+// right about the type doesn't say how long it is.
+fn with_length(entry_type: EntryType, sources: &[(Source, PageMetadata)]) -> EntryType {
+    if entry_type::metadata(entry_type) != 0 {
+        return entry_type;
+    }
+    sources
+        .iter()
+        .filter_map(|(_, metadata)| metadata.entry_type)
+        .find(|other| {
+            entry_type::index(*other) == entry_type::index(entry_type)
+                && entry_type::metadata(*other) != 0
+        })
+        .unwrap_or(entry_type)
+}
+
+// Merges what every source says into one suggestion.
+pub fn merge(sources: &[(Source, PageMetadata)]) -> SuggestedEntry {
+    let mut provenance = BTreeMap::new();
+    let metadata = PageMetadata {
+        title: pick(
+            sources,
+            "title",
+            |metadata| metadata.title.clone(),
+            &mut provenance,
+        ),
+        description: pick(
+            sources,
+            "description",
+            |metadata| metadata.description.clone(),
+            &mut provenance,
+        ),
+        authors: pick(
+            sources,
+            "authors",
+            |metadata| Some(metadata.authors.clone()).filter(|authors| !authors.is_empty()),
+            &mut provenance,
+        )
+        .unwrap_or_default(),
+        date_published: pick(
+            sources,
+            "date_published",
+            |metadata| metadata.date_published,
+            &mut provenance,
+        ),
+        entry_type: pick(
+            sources,
+            "entry_type",
+            |metadata| metadata.entry_type,
+            &mut provenance,
+        )
+        .map(|entry_type| with_length(entry_type, sources)),
+        image: pick(
+            sources,
+            "image",
+            |metadata| metadata.image.clone(),
+            &mut provenance,
+        ),
+        canonical_link: pick(
+            sources,
+            "canonical_link",
+            |metadata| metadata.canonical_link.clone(),
+            &mut provenance,
+        ),
+        identifier: pick(
+            sources,
+            "identifier",
+            |metadata| metadata.identifier.clone(),
+            &mut provenance,
+        ),
+    };
+    SuggestedEntry {
+        metadata,
+        provenance,
+    }
+}
+
+// What every vocabulary of the html page says about it.
+pub fn sources_of_page(html_source: &str) -> Vec<(Source, PageMetadata)> {
+    let head = html_meta::parse_html_head(html_source);
+    vec![
+        (
+            Source::JsonLd,
+            json_ld_metadata(html_source).unwrap_or_default(),
+        ),
+        (Source::Highwire, html_meta::highwire_metadata(&head)),
+        (Source::OpenGraph, html_meta::open_graph_metadata(&head)),
+        (Source::DublinCore, html_meta::dublin_core_metadata(&head)),
+        (Source::TwitterCard, html_meta::twitter_card_metadata(&head)),
+        (Source::Html, html_meta::html_metadata(&head)),
+        (
+            Source::Content,
+            PageMetadata {
+                entry_type: article_word_count(html_source)
+                    .map(|words| EntryType::Article { words }),
+                ..PageMetadata::default()
+            },
+        ),
+    ]
+}
+
+pub fn suggest_entry(html_source: &str) -> SuggestedEntry {
+    merge(&sources_of_page(html_source))
+}
+
+// What a fetched resource or a backup says about itself, if it is of a type there is anything to read from.
+pub fn sources_of_resource(
+    content_type: &str,
+    content: &[u8],
+) -> Option<Vec<(Source, PageMetadata)>> {
+    if content_type.starts_with("text/html") {
+        Some(sources_of_page(&decode_text(content_type, content)))
+    } else if pdf::is_pdf(content_type, content) {
+        let metadata = pdf::read_pdf_metadata(content).ok()?;
+        Some(vec![(Source::Pdf, pdf::page_metadata_of_pdf(&metadata))])
+    } else if media::is_media(content_type, content) {
+        let metadata = media::read_media_metadata(content_type, content).ok()?;
+        Some(vec![(
+            Source::Media,
+            media::page_metadata_of_media(&metadata),
+        )])
+    } else {
+        None
+    }
+}
+
+// Suggestion for a fetched resource or a backup, if it is of a type there is anything to read from.
+pub fn suggest_entry_for_resource(content_type: &str, content: &[u8]) -> Option<SuggestedEntry> {
+    sources_of_resource(content_type, content).map(|sources| merge(&sources))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::{Date, Month};
+    use crate::oembed::oembed_metadata;
+
+    fn provenance(source: Source, confidence: f64) -> Provenance {
+        Provenance { source, confidence }
+    }
+
+    // suggest_entry
+
+    #[test]
+    fn suggestion_for_an_article() {
+        let html = r#"
+            <html><head>
+            <title>Bonnie and Clyde | The New Yorker</title>
+            <link rel="canonical" href="https://www.newyorker.com/magazine/1967/10/21/bonnie-and-clyde">
+            <meta property="og:type" content="article">
+            <meta property="og:title" content="Bonnie and Clyde">
+            <meta name="twitter:title" content="Bonnie and Clyde | The New Yorker">
+            <meta name="description" content="The frontier of movies">
+            <meta name="author" content="Pauline Kael">
+            <meta property="article:author" content="https://www.newyorker.com/contributors/pauline-kael">
+            <meta property="article:published_time" content="1967-10-21T00:00:00Z">
+            <meta name="twitter:image" content="/cover-small.jpg">
+            <meta property="og:image" content="/cover.jpg">
+            </head></html>
+        "#;
+
+        let suggestion = suggest_entry(html);
+        assert_eq!(
+            suggestion.metadata,
+            PageMetadata {
+                title: Some(String::from("Bonnie and Clyde")),
+                description: Some(String::from("The frontier of movies")),
+                authors: vec![String::from("Pauline Kael")],
+                date_published: Some(Date {
+                    day: 21,
+                    month: Month::October,
+                    year: 1967
+                }),
+                entry_type: Some(EntryType::Article { words: 0 }),
+                image: Some(String::from("/cover.jpg")),
+                canonical_link: Some(String::from(
+                    "https://www.newyorker.com/magazine/1967/10/21/bonnie-and-clyde"
+                )),
+                identifier: None,
+            }
+        );
+        assert_eq!(
+            suggestion.provenance,
+            BTreeMap::from([
+                (String::from("authors"), provenance(Source::Html, 0.6)),
+                (
+                    String::from("canonical_link"),
+                    provenance(Source::Html, 0.95)
+                ),
+                (
+                    String::from("date_published"),
+                    provenance(Source::OpenGraph, 0.8)
+                ),
+                (String::from("description"), provenance(Source::Html, 0.6)),
+                (
+                    String::from("entry_type"),
+                    provenance(Source::OpenGraph, 0.6)
+                ),
+                (String::from("image"), provenance(Source::OpenGraph, 0.9)),
+                (String::from("title"), provenance(Source::OpenGraph, 0.8)),
+            ])
+        );
+    }
+
+    #[test]
+    fn json_ld_and_citations_win_over_meta_tags() {
+        let html = r#"
+            <meta property="og:type" content="article">
+            <meta property="og:title" content="The Frontier of Movies | Film Quarterly">
+            <meta name="citation_title" content="The Frontier of Movies">
+            <meta name="citation_author" content="Kael, Pauline">
+            <meta name="citation_firstpage" content="112">
+            <meta name="citation_lastpage" content="131">
+            <script type="application/ld+json">
+                {"@type": "ScholarlyArticle", "name": "The frontier of movies", "datePublished": "1968-01-15"}
+            </script>
+        "#;
+
+        let suggestion = suggest_entry(html);
+        assert_eq!(
+            suggestion.metadata.title.as_deref(),
+            Some("The frontier of movies")
+        );
+        assert_eq!(suggestion.metadata.authors, ["Pauline Kael"]);
+        assert_eq!(
+            suggestion.metadata.entry_type,
+            Some(EntryType::Paper { pages: 20 })
+        );
+        assert_eq!(
+            suggestion.provenance["title"],
+            provenance(Source::JsonLd, 0.9)
+        );
+        assert_eq!(
+            suggestion.provenance["authors"],
+            provenance(Source::Highwire, 0.9)
+        );
+        assert_eq!(
+            suggestion.provenance["entry_type"],
+            provenance(Source::JsonLd, 0.9)
+        );
+    }
+
+    #[test]
+    fn length_comes_from_a_source_with_the_same_type() {
+        let html = r#"
+            <meta property="og:type" content="video.other">
+            <meta property="video:duration" content="754">
+            <script type="application/ld+json">{"@type": "VideoObject", "name": "Arthur Penn"}</script>
+        "#;
+        assert_eq!(
+            suggest_entry(html).metadata.entry_type,
+            Some(EntryType::Video {
+                length_in_seconds: 754
+            })
+        );
+
+        let html = r#"
+            <meta property="og:type" content="video.other">
+            <meta property="video:duration" content="754">
+            <script type="application/ld+json">{"@type": "NewsArticle"}</script>
+        "#;
+        assert_eq!(
+            suggest_entry(html).metadata.entry_type,
+            Some(EntryType::Article { words: 0 })
+        );
+    }
+
+    #[test]
+    fn articles_are_counted_from_the_text_of_the_page() {
+        let body = "<body><article><p>How do you make a good movie in this country without being jumped on?</p></article></body>";
+
+        let suggestion = suggest_entry(&format!(
+            r#"<meta property="og:type" content="article">{}"#,
+            body
+        ));
+        assert_eq!(
+            suggestion.metadata.entry_type,
+            Some(EntryType::Article { words: 14 })
+        );
+        assert_eq!(
+            suggestion.provenance["entry_type"],
+            provenance(Source::OpenGraph, 0.6)
+        );
+
+        let suggestion = suggest_entry(body);
+        assert_eq!(
+            suggestion.metadata.entry_type,
+            Some(EntryType::Article { words: 14 })
+        );
+        assert_eq!(
+            suggestion.provenance["entry_type"],
+            provenance(Source::Content, 0.3)
+        );
+
+        let suggestion = suggest_entry(&format!(
+            r#"<meta property="og:type" content="video.other">{}"#,
+            body
+        ));
+        assert_eq!(
+            suggestion.metadata.entry_type,
+            Some(EntryType::Video {
+                length_in_seconds: 0
+            })
+        );
+    }
+
+    #[test]
+    fn suggestion_for_a_page_without_metadata_is_empty() {
+        assert_eq!(
+            suggest_entry("<html><body>Hello</body></html>"),
+            SuggestedEntry::default()
+        );
+    }
+
+    #[test]
+    fn suggestion_serializes_provenance_next_to_the_fields() {
+        let suggestion = suggest_entry("<title>Bonnie and Clyde</title>");
+        assert_eq!(
+            serde_json::to_value(&suggestion).unwrap(),
+            serde_json::json!({
+                "title": "Bonnie and Clyde",
+                "description": null,
+                "authors": [],
+                "date_published": null,
+                "entry_type": null,
+                "image": null,
+                "canonical_link": null,
+                "identifier": null,
+                "provenance": {"title": {"source": "html", "confidence": 0.4}},
+            })
+        );
+    }
+
+    // merge
+
+    #[test]
+    fn oembed_fills_what_the_meta_tags_of_a_video_lack() {
+        let html = r#"
+            <title>Arthur Penn on Bonnie and Clyde - YouTube</title>
+            <meta property="og:type" content="video.other">
+            <meta property="og:title" content="Arthur Penn on Bonnie and Clyde">
+            <meta property="og:image" content="https://i.ytimg.com/vi/abc123/maxresdefault.jpg">
+        "#;
+        let json = r#"{"type": "video", "title": "Arthur Penn on Bonnie and Clyde", "author_name": "Archivo de Cine", "duration": 754, "thumbnail_url": "https://i.ytimg.com/vi/abc123/hqdefault.jpg"}"#;
+        let mut sources = sources_of_page(html);
+        sources.push((Source::OEmbed, oembed_metadata(json).unwrap()));
+
+        let suggestion = merge(&sources);
+        assert_eq!(suggestion.metadata.authors, ["Archivo de Cine"]);
+        assert_eq!(
+            suggestion.metadata.entry_type,
+            Some(EntryType::Video {
+                length_in_seconds: 754
+            })
+        );
+        assert_eq!(
+            suggestion.metadata.image.as_deref(),
+            Some("https://i.ytimg.com/vi/abc123/maxresdefault.jpg")
+        );
+        assert_eq!(
+            suggestion.provenance["title"],
+            provenance(Source::OEmbed, 0.85)
+        );
+        assert_eq!(
+            serde_json::to_value(suggestion.provenance["authors"]).unwrap(),
+            serde_json::json!({"source": "oembed", "confidence": 0.85})
+        );
+    }
+
+    // suggest_entry_for_resource
+
+    #[test]
+    fn suggestion_for_a_pdf() {
+        let content = include_bytes!("../fixtures/pdf/the-frontier-of-movies.pdf");
+        let suggestion = suggest_entry_for_resource("application/octet-stream", content).unwrap();
+        assert_eq!(
+            suggestion.metadata.title.as_deref(),
+            Some("The Frontier of Movies: Bonnie and Clyde")
+        );
+        assert_eq!(
+            suggestion.metadata.entry_type,
+            Some(EntryType::Paper { pages: 3 })
+        );
+        assert_eq!(
+            suggestion.provenance["date_published"],
+            provenance(Source::Pdf, 0.3)
+        );
+    }
+
+    #[test]
+    fn suggestion_for_an_audio_file() {
+        let content = include_bytes!("../fixtures/media/foggy-mountain-breakdown.mp3");
+        let suggestion = suggest_entry_for_resource("audio/mpeg", content).unwrap();
+        assert_eq!(suggestion.metadata.authors, ["Flatt & Scruggs"]);
+        assert_eq!(
+            suggestion.metadata.entry_type,
+            Some(EntryType::Audio {
+                length_in_seconds: 165
+            })
+        );
+        assert_eq!(
+            suggestion.provenance["entry_type"],
+            provenance(Source::Media, 0.9)
+        );
+    }
+
+    #[test]
+    fn no_suggestion_for_other_resources() {
+        assert_eq!(suggest_entry_for_resource("text/plain", b"Hello"), None);
+        assert_eq!(
+            suggest_entry_for_resource("application/pdf", b"Hello"),
+            None
+        );
+        assert_eq!(suggest_entry_for_resource("video/mp4", b"Hello"), None);
+        assert!(
+            suggest_entry_for_resource("text/html; charset=utf-8", b"<title>Hello</title>")
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,330 @@
+// The tables of unique categories, authors, themes, works and tags, which list the values used by the entries outside
+// the trash. Every value but categories also has the category of the oldest entry that uses it.
+
+use crate::entry::NewEntryForm;
+
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+// Tables of values that entries have lists of, with the column of the entries where the lists are stored.
+const LIST_TABLES: [(&str, &str); 4] = [
+    ("authors", "author"),
+    ("themes", "themes"),
+    ("works", "works_mentioned"),
+    ("tags", "tags"),
+];
+
+fn lists_of(form: &NewEntryForm) -> [&Vec<String>; 4] {
+    [
+        &form.authors,
+        &form.themes,
+        &form.works_mentioned,
+        &form.tags,
+    ]
+}
+
+fn refresh_category(database: &rusqlite::Connection, category: &str) -> rusqlite::Result<()> {
+    let used: bool = database.query_row(
+        "SELECT EXISTS (SELECT 1 FROM entries WHERE date_deleted IS NULL AND category = ?)",
+        [category],
+        |row| row.get(0),
+    )?;
+    if used {
+        database.execute(
+            "INSERT OR IGNORE INTO categories (value) VALUES (?)",
+            [category],
+        )?;
+    } else {
+        database.execute("DELETE FROM categories WHERE value = ?", [category])?;
+    }
+    Ok(())
+}
+
+// Adds the value to the table with the category of the oldest entry that uses it, or removes it if no entry does.
+fn refresh_list_value(
+    database: &rusqlite::Connection,
+    table: &str,
+    column: &str,
+    value: &str,
+) -> rusqlite::Result<()> {
+    let mut statement = database.prepare(&format!(
+        "SELECT category FROM entries
+        WHERE date_deleted IS NULL AND instr({}, '|' || ? || '|') > 0
+        ORDER BY entry_id LIMIT 1",
+        column
+    ))?;
+    let mut rows = statement.query([value])?;
+    match rows.next()? {
+        Some(row) => {
+            let category: String = row.get(0)?;
+            database.execute(
+                &format!(
+                    "INSERT INTO {} (value, category) VALUES (?1, ?2)
+                    ON CONFLICT (value) DO UPDATE SET category = ?2",
+                    table
+                ),
+                [value, &category],
+            )?;
+        }
+        None => {
+            database.execute(&format!("DELETE FROM {} WHERE value = ?", table), [value])?;
+        }
+    }
+    Ok(())
+}
+
+// Brings the tables up to date after an entry changed from the previous values to the current ones. None means the
+// entry didn't exist or was in the trash.
+pub fn update(
+    database: &rusqlite::Connection,
+    previous: Option<&NewEntryForm>,
+    current: Option<&NewEntryForm>,
+) -> rusqlite::Result<()> {
+    let forms = previous.into_iter().chain(current).collect::<Vec<_>>();
+
+    let categories = forms
+        .iter()
+        .map(|form| form.category.as_str())
+        .collect::<BTreeSet<_>>();
+    for category in categories {
+        refresh_category(database, category)?;
+    }
+
+    for (index, (table, column)) in LIST_TABLES.iter().enumerate() {
+        let values = forms
+            .iter()
+            .flat_map(|form| lists_of(form)[index])
+            .map(String::as_str)
+            .collect::<BTreeSet<_>>();
+        for value in values {
+            refresh_list_value(database, table, column, value)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct RebuiltTable {
+    pub table: String,
+    // Values that were missing from the table.
+    pub added: Vec<String>,
+    // Values in the table that no entry used.
+    pub removed: Vec<String>,
+}
+
+fn values_of(database: &rusqlite::Connection, table: &str) -> rusqlite::Result<BTreeSet<String>> {
+    let mut statement = database.prepare(&format!("SELECT value FROM {}", table))?;
+    let values = statement
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<BTreeSet<String>>>()?;
+    Ok(values)
+}
+
+// Empties the tables and fills them again from the entries, for databases where they got out of sync. Returns what
+// changed in each table.
+pub fn rebuild(database: &rusqlite::Connection) -> rusqlite::Result<Vec<RebuiltTable>> {
+    let tables = std::iter::once("categories")
+        .chain(LIST_TABLES.iter().map(|(table, _)| *table))
+        .collect::<Vec<_>>();
+
+    let transaction = database.unchecked_transaction()?;
+
+    let mut before = Vec::new();
+    for table in &tables {
+        before.push(values_of(&transaction, table)?);
+        transaction.execute(&format!("DELETE FROM {}", table), [])?;
+    }
+
+    transaction.execute(
+        "INSERT OR IGNORE INTO categories (value)
+        SELECT category FROM entries WHERE date_deleted IS NULL ORDER BY entry_id",
+        [],
+    )?;
+    let mut statement = transaction.prepare(
+        "SELECT author, themes, works_mentioned, tags, category FROM entries
+        WHERE date_deleted IS NULL ORDER BY entry_id",
+    )?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let category: String = row.get(4)?;
+        for (index, (table, _)) in LIST_TABLES.iter().enumerate() {
+            let list: String = row.get(index)?;
+            for value in crate::sql_array::read_from_sql_array(&list) {
+                transaction.execute(
+                    &format!(
+                        "INSERT OR IGNORE INTO {} (value, category) VALUES (?, ?)",
+                        table
+                    ),
+                    [&value, &category],
+                )?;
+            }
+        }
+    }
+    drop(rows);
+    drop(statement);
+
+    let mut rebuilt = Vec::new();
+    for (table, before) in tables.iter().zip(before) {
+        let after = values_of(&transaction, table)?;
+        rebuilt.push(RebuiltTable {
+            table: table.to_string(),
+            added: after.difference(&before).cloned().collect(),
+            removed: before.difference(&after).cloned().collect(),
+        });
+    }
+
+    transaction.commit()?;
+    Ok(rebuilt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database;
+    use crate::date::{Date, Month};
+    use crate::entry_type::EntryType;
+
+    fn open_in_memory() -> rusqlite::Connection {
+        let database = rusqlite::Connection::open_in_memory().unwrap();
+        database::create_tables(&database).unwrap();
+        database
+    }
+
+    fn form(title: &str, category: &str, authors: &[&str]) -> NewEntryForm {
+        NewEntryForm {
+            link: format!("https://example.com/{}", title),
+            title: String::from(title),
+            description: String::new(),
+            authors: authors.iter().map(|author| author.to_string()).collect(),
+            category: String::from(category),
+            themes: Vec::new(),
+            works_mentioned: Vec::new(),
+            tags: vec![String::from("Classic")],
+            date_published: Date {
+                day: 3,
+                month: Month::May,
+                year: 1967,
+            },
+            date_saved: None,
+            exceptional: false,
+            entry_type: EntryType::Article { words: 1000 },
+            identifier: None,
+        }
+    }
+
+    fn authors(database: &rusqlite::Connection) -> Vec<(String, String)> {
+        database::get_all_strings_by_category_of(database, "authors")
+            .unwrap()
+            .into_iter()
+            .map(|author| (author.value, author.category))
+            .collect()
+    }
+
+    // update
+
+    #[test]
+    fn values_no_entry_uses_are_removed() {
+        let database = open_in_memory();
+        let id =
+            database::insert_entry(&database, &form("a", "Cinema", &["Pauline Kael"])).unwrap();
+        database::insert_entry(&database, &form("b", "Cinema", &["Roger Ebert"])).unwrap();
+
+        database::update_entry(&database, id, &form("a", "Music", &["Lester Bangs"])).unwrap();
+        assert_eq!(
+            database::get_all_strings_of(&database, "categories").unwrap(),
+            ["Cinema", "Music"]
+        );
+        assert_eq!(
+            values_of(&database, "authors").unwrap(),
+            BTreeSet::from([String::from("Lester Bangs"), String::from("Roger Ebert")])
+        );
+
+        database::delete_entry(&database, id).unwrap();
+        assert_eq!(
+            database::get_all_strings_of(&database, "categories").unwrap(),
+            ["Cinema"]
+        );
+        assert_eq!(
+            authors(&database),
+            [("Roger Ebert".into(), "Cinema".into())]
+        );
+        assert_eq!(
+            database::get_all_strings_of(&database, "tags").unwrap(),
+            ["Classic"]
+        );
+    }
+
+    #[test]
+    fn values_take_the_category_of_the_oldest_entry_that_uses_them() {
+        let database = open_in_memory();
+        let first =
+            database::insert_entry(&database, &form("a", "Cinema", &["Pauline Kael"])).unwrap();
+        database::insert_entry(&database, &form("b", "Books", &["Pauline Kael"])).unwrap();
+        assert_eq!(
+            authors(&database),
+            [("Pauline Kael".into(), "Cinema".into())]
+        );
+
+        database::delete_entry(&database, first).unwrap();
+        assert_eq!(
+            authors(&database),
+            [("Pauline Kael".into(), "Books".into())]
+        );
+
+        database::restore_from_trash(&database, first).unwrap();
+        assert_eq!(
+            authors(&database),
+            [("Pauline Kael".into(), "Cinema".into())]
+        );
+    }
+
+    // rebuild
+
+    #[test]
+    fn rebuild_fills_the_tables_from_the_entries() {
+        let database = open_in_memory();
+        database::insert_entry(&database, &form("a", "Cinema", &["Pauline Kael"])).unwrap();
+        database
+            .execute_batch(
+                "DELETE FROM authors;
+                INSERT INTO themes (value, category) VALUES ('Unused', 'Cinema');",
+            )
+            .unwrap();
+
+        let rebuilt = rebuild(&database).unwrap();
+        assert_eq!(
+            rebuilt,
+            [
+                RebuiltTable {
+                    table: "categories".into(),
+                    added: vec![],
+                    removed: vec![]
+                },
+                RebuiltTable {
+                    table: "authors".into(),
+                    added: vec!["Pauline Kael".into()],
+                    removed: vec![]
+                },
+                RebuiltTable {
+                    table: "themes".into(),
+                    added: vec![],
+                    removed: vec!["Unused".into()]
+                },
+                RebuiltTable {
+                    table: "works".into(),
+                    added: vec![],
+                    removed: vec![]
+                },
+                RebuiltTable {
+                    table: "tags".into(),
+                    added: vec![],
+                    removed: vec![]
+                },
+            ]
+        );
+        assert_eq!(
+            authors(&database),
+            [("Pauline Kael".into(), "Cinema".into())]
+        );
+    }
+}
@@ -1,4 +1,6 @@
 use crate::date;
+use crate::identifiers::identifier_of;
+use crate::links;
 use percent_encoding::percent_decode_str;
 use std::fmt::Write;
 
@@ -55,6 +57,23 @@ pub fn url_to_sql_query(query_text: &str) -> Option<SqlQuery> {
                         result += "category = ?";
                         params.push(String::from(key_value[1]));
                     }
+                    "identifier" => match identifier_of(key_value[1]) {
+                        // However it is written, it is found as stored.
+                        Some(identifier) => {
+                            result += "identifier = ?";
+                            params.push(identifier.to_string());
+                        }
+                        None => {
+                            result += "identifier LIKE ?";
+                            params.push(sql_arg_string_contains(key_value[1]));
+                        }
+                    },
+                    "site" => {
+                        // The domain of the site, written in any way or as a link to any of its pages, or its name.
+                        result += "(site = ? OR site IN (SELECT domain FROM sites WHERE site_name = ? COLLATE NOCASE))";
+                        params.push(site_domain(key_value[1]));
+                        params.push(String::from(key_value[1].trim()));
+                    }
                     "type" => {
                         _ = write!(
                             &mut result,
@@ -108,6 +127,14 @@ pub fn url_to_sql_query(query_text: &str) -> Option<SqlQuery> {
                         result += "date_saved <= DATE(?)";
                         params.push(date::format_as_sql_date(date::read_sql_date(key_value[1])?));
                     }
+                    "modified_between_from" => {
+                        result += "DATE(date_modified, 'localtime') >= DATE(?)";
+                        params.push(date::format_as_sql_date(date::read_sql_date(key_value[1])?));
+                    }
+                    "modified_between_until" => {
+                        result += "DATE(date_modified, 'localtime') <= DATE(?)";
+                        params.push(date::format_as_sql_date(date::read_sql_date(key_value[1])?));
+                    }
                     "exceptional" => {
                         result += "exceptional = ";
                         result += if key_value[1] == "true" {
@@ -161,6 +188,12 @@ pub fn url_to_sql_query(query_text: &str) -> Option<SqlQuery> {
     })
 }
 
+// Domain of a site as stored in entries, from the domain or a link to any of its pages.
+fn site_domain(text: &str) -> String {
+    let link = links::canonical_link(text);
+    String::from(link.split(['/', '?']).next().unwrap_or_default())
+}
+
 fn sql_arg_string_contains(string: &str) -> String {
     String::from("%") + string + "%"
 }
@@ -253,6 +286,53 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_url_to_sql_query_site_is_found_by_domain_or_name() {
+        for url_params in [
+            "site=www.NewYorker.com",
+            "site=https%3A%2F%2Fnewyorker.com%2Fmagazine",
+        ] {
+            match url_to_sql_query(url_params) {
+                Some(query) => {
+                    assert_eq!(
+                        query.where_query,
+                        "(site = ? OR site IN (SELECT domain FROM sites WHERE site_name = ? COLLATE NOCASE))"
+                    );
+                    assert_eq!(query.params[0], "newyorker.com");
+                }
+                None => unreachable!(),
+            }
+        }
+        match url_to_sql_query("site=The%20New%20Yorker") {
+            Some(query) => assert_eq!(query.params[1], "The New Yorker"),
+            None => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_url_to_sql_query_identifier_is_found_as_stored() {
+        let url_params = "identifier=DOI%3A%2010.1145%2F3297858.3304013";
+        match url_to_sql_query(url_params) {
+            Some(query) => {
+                assert_eq!(query.where_query, "identifier = ?");
+                assert_eq!(query.params, ["doi:10.1145/3297858.3304013"]);
+            }
+            None => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_url_to_sql_query_partial_identifier_checks_for_containment() {
+        let url_params = "identifier=10.1145";
+        match url_to_sql_query(url_params) {
+            Some(query) => {
+                assert_eq!(query.where_query, "identifier LIKE ?");
+                assert_eq!(query.params, ["%10.1145%"]);
+            }
+            None => unreachable!(),
+        }
+    }
+
     #[test]
     fn test_url_to_sql_query_themes_checks_for_containment_of_each() {
         let url_params = "themes=Rust%7CTesting";
@@ -318,6 +398,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_url_to_sql_query_modified_between_checks_date_modified() {
+        let url_params = "modified_between_from=2024-1-1&modified_between_until=2024-12-31";
+        match url_to_sql_query(url_params) {
+            Some(query) => {
+                assert_eq!(
+                    query.where_query,
+                    "DATE(date_modified, 'localtime') >= DATE(?) AND DATE(date_modified, 'localtime') <= DATE(?)"
+                );
+                assert_eq!(query.params, ["2024-01-01", "2024-12-31"]);
+                assert_eq!(query.offset, 0);
+            }
+            None => unreachable!(),
+        }
+    }
+
     #[test]
     fn test_url_to_sql_query_published_between_until_checks_for_less_equal() {
         let url_params = "published_between_until=1967-11-24";
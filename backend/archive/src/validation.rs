@@ -0,0 +1,318 @@
+// Normalization and checks of the values of an entry form before it is stored.
+
+use crate::date::{self, Date};
+use crate::entry::NewEntryForm;
+use crate::entry_type::EntryType;
+use crate::identifiers::identifier_of;
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema, Debug, Default, PartialEq, Eq)]
+pub struct ValidationErrors {
+    pub error_message: String,
+    // Every problem found, by the name of the field that has it.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, Vec<String>>,
+}
+
+impl ValidationErrors {
+    pub fn add(&mut self, field: &str, problem: impl Into<String>) {
+        self.fields
+            .entry(field.to_owned())
+            .or_default()
+            .push(problem.into());
+    }
+
+    // Ok if no problem was added, otherwise the errors with a message that lists all of them.
+    pub fn into_result(mut self) -> Result<(), ValidationErrors> {
+        if self.fields.is_empty() {
+            return Ok(());
+        }
+        self.error_message = self
+            .fields
+            .iter()
+            .flat_map(|(field, problems)| {
+                problems
+                    .iter()
+                    .map(move |problem| format!("{}: {}", field, problem))
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        Err(self)
+    }
+}
+
+// Trims the values of the list and removes repeated ones, keeping the first.
+pub fn normalize_list(list: &mut Vec<String>) {
+    let mut normalized: Vec<String> = Vec::with_capacity(list.len());
+    for value in list.drain(..) {
+        let value = value.trim().to_owned();
+        if !normalized.contains(&value) {
+            normalized.push(value);
+        }
+    }
+    *list = normalized;
+}
+
+// Trims every text of the form and removes repeated values from its lists.
+pub fn normalize(form: &mut NewEntryForm) {
+    form.link = form.link.trim().to_owned();
+    form.title = form.title.trim().to_owned();
+    form.description = form.description.trim().to_owned();
+    form.category = form.category.trim().to_owned();
+    normalize_list(&mut form.authors);
+    normalize_list(&mut form.themes);
+    normalize_list(&mut form.works_mentioned);
+    normalize_list(&mut form.tags);
+    if let Some(identifier) = &form.identifier {
+        let identifier = identifier.trim();
+        form.identifier = Some(
+            identifier_of(identifier)
+                .map(|identifier| identifier.to_string())
+                .unwrap_or_else(|| identifier.to_owned()),
+        );
+    }
+}
+
+// Whether the text is an absolute http or https URL with a host.
+pub fn is_url(text: &str) -> bool {
+    let rest = match text.split_once("://") {
+        Some((scheme, rest))
+            if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") =>
+        {
+            rest
+        }
+        _ => return false,
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    !host.is_empty() && !text.chars().any(char::is_whitespace)
+}
+
+// Lists are stored separated by '|', so values can't contain it.
+pub fn check_list(errors: &mut ValidationErrors, field: &str, list: &[String]) {
+    if list.iter().any(String::is_empty) {
+        errors.add(field, "Values must not be empty");
+    }
+    for value in list.iter().filter(|value| value.contains('|')) {
+        errors.add(field, format!("'{}' must not contain '|'", value));
+    }
+}
+
+pub fn check_category(errors: &mut ValidationErrors, category: &str) {
+    if category.is_empty() {
+        errors.add("category", "Must not be empty");
+    }
+}
+
+fn check_date(errors: &mut ValidationErrors, field: &str, date: Date) {
+    if !date::is_valid(date) {
+        errors.add(
+            field,
+            format!(
+                "{:?} {}, {} is not a date in the calendar",
+                date.month, date.day, date.year
+            ),
+        );
+    }
+}
+
+fn check_entry_type(errors: &mut ValidationErrors, entry_type: EntryType) {
+    let (name, value) = match entry_type {
+        EntryType::Article { words } => ("words", words),
+        EntryType::Paper { pages } | EntryType::Book { pages } => ("pages", pages),
+        EntryType::Video { length_in_seconds } | EntryType::Audio { length_in_seconds } => {
+            ("length_in_seconds", length_in_seconds)
+        }
+    };
+    if value < 0 {
+        errors.add(
+            "entry_type",
+            format!("{} must not be negative, but is {}", name, value),
+        );
+    }
+}
+
+// Checks every field of a normalized form, returning all the problems found.
+pub fn validate(form: &NewEntryForm) -> Result<(), ValidationErrors> {
+    validate_edit(form, None)
+}
+
+// Like validate, for a form that replaces the values of the entry with the current link, or of a new entry if there is
+// none. Entries saved before links were checked can have one that isn't an http or https URL, like a file: link. It
+// is only rejected if the form changes it, so that the rest of the entry can still be edited.
+pub fn validate_edit(
+    form: &NewEntryForm,
+    current_link: Option<&str>,
+) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::default();
+
+    if current_link != Some(form.link.as_str()) && !is_url(&form.link) {
+        errors.add(
+            "link",
+            format!("'{}' is not an http or https URL", form.link),
+        );
+    }
+    if form.title.is_empty() {
+        errors.add("title", "Must not be empty");
+    }
+    check_category(&mut errors, &form.category);
+    check_list(&mut errors, "authors", &form.authors);
+    check_list(&mut errors, "themes", &form.themes);
+    check_list(&mut errors, "works_mentioned", &form.works_mentioned);
+    check_list(&mut errors, "tags", &form.tags);
+    check_date(&mut errors, "date_published", form.date_published);
+    if let Some(date_saved) = form.date_saved {
+        check_date(&mut errors, "date_saved", date_saved);
+    }
+    check_entry_type(&mut errors, form.entry_type);
+    if let Some(identifier) = form.identifier.as_deref() {
+        if !identifier.is_empty() && identifier_of(identifier).is_none() {
+            errors.add(
+                "identifier",
+                format!("'{}' is not a DOI, arXiv id or ISBN", identifier),
+            );
+        }
+    }
+
+    errors.into_result()
+}
+
+pub fn normalize_and_validate(form: NewEntryForm) -> Result<NewEntryForm, ValidationErrors> {
+    normalize_and_validate_edit(form, None)
+}
+
+pub fn normalize_and_validate_edit(
+    mut form: NewEntryForm,
+    current_link: Option<&str>,
+) -> Result<NewEntryForm, ValidationErrors> {
+    normalize(&mut form);
+    validate_edit(&form, current_link)?;
+    Ok(form)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::Month;
+
+    fn form() -> NewEntryForm {
+        NewEntryForm {
+            link: String::from("https://example.com/bonnie-and-clyde"),
+            title: String::from("Bonnie and Clyde"),
+            description: String::new(),
+            authors: vec![String::from("Pauline Kael")],
+            category: String::from("Cinema"),
+            themes: vec![String::from("Criticism")],
+            works_mentioned: vec![],
+            tags: vec![],
+            date_published: Date {
+                day: 21,
+                month: Month::October,
+                year: 1967,
+            },
+            date_saved: None,
+            exceptional: false,
+            entry_type: EntryType::Article { words: 7000 },
+            identifier: None,
+        }
+    }
+
+    // normalize
+
+    #[test]
+    fn normalize_trims_texts_and_removes_repeated_values() {
+        let mut form = form();
+        form.title = String::from("  Bonnie and Clyde\n");
+        form.authors = vec![
+            String::from(" Pauline Kael"),
+            String::from("Pauline Kael "),
+            String::from("Roger Ebert"),
+        ];
+
+        normalize(&mut form);
+        assert_eq!(form.title, "Bonnie and Clyde");
+        assert_eq!(form.authors, ["Pauline Kael", "Roger Ebert"]);
+    }
+
+    // validate
+
+    #[test]
+    fn valid_form_is_accepted() {
+        assert_eq!(validate(&form()), Ok(()));
+    }
+
+    #[test]
+    fn every_problem_is_reported_by_field() {
+        let mut form = form();
+        form.link = String::from("example.com/bonnie-and-clyde");
+        form.title = String::new();
+        form.tags = vec![String::new(), String::from("a|b")];
+        form.date_published.month = Month::February;
+        form.date_published.day = 31;
+        form.entry_type = EntryType::Article { words: -1 };
+
+        let errors = validate(&form).unwrap_err();
+        assert_eq!(
+            errors.fields.keys().collect::<Vec<_>>(),
+            ["date_published", "entry_type", "link", "tags", "title"]
+        );
+        assert_eq!(errors.fields["tags"].len(), 2);
+        assert!(errors.error_message.contains("title: Must not be empty"));
+    }
+
+    #[test]
+    fn dates_are_checked_against_the_calendar() {
+        let mut form = form();
+        form.date_published = Date {
+            day: 29,
+            month: Month::February,
+            year: 2024,
+        };
+        assert_eq!(validate(&form), Ok(()));
+
+        form.date_saved = Some(Date {
+            day: 29,
+            month: Month::February,
+            year: 2023,
+        });
+        let errors = validate(&form).unwrap_err();
+        assert_eq!(errors.fields.keys().collect::<Vec<_>>(), ["date_saved"]);
+    }
+
+    #[test]
+    fn links_must_be_http_urls() {
+        for link in [
+            "http://example.com",
+            "HTTPS://example.com/a?b=c#d",
+            "https://localhost:8080/",
+        ] {
+            assert!(is_url(link), "{}", link);
+        }
+        for link in [
+            "",
+            "example.com",
+            "ftp://example.com",
+            "https://",
+            "https:///path",
+            "https://example.com/a b",
+        ] {
+            assert!(!is_url(link), "{}", link);
+        }
+    }
+
+    // validate_edit
+
+    #[test]
+    fn links_of_older_versions_are_only_checked_when_they_change() {
+        let mut form = form();
+        form.link = String::from("file:///home/pauline/bonnie-and-clyde.pdf");
+        assert_eq!(validate_edit(&form, Some(&form.link)), Ok(()));
+
+        let errors =
+            validate_edit(&form, Some("https://example.com/bonnie-and-clyde")).unwrap_err();
+        assert_eq!(errors.fields.keys().collect::<Vec<_>>(), ["link"]);
+        assert!(validate_edit(&form, None).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+// Word counts of the articles that were saved without one, counted from the html pages of their backups.
+
+use crate::charset::decode_text;
+use crate::database;
+use crate::entry::form_from_entry;
+use crate::entry_type::EntryType;
+use crate::readability::article_word_count;
+
+use serde::Serialize;
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct CountedArticle {
+    pub id: i64,
+    pub words: i32,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct UncountedArticle {
+    pub id: i64,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct WordCountReport {
+    pub counted: Vec<CountedArticle>,
+    pub uncounted: Vec<UncountedArticle>,
+}
+
+// Words of the article in the backup of the entry, or why they could not be counted.
+fn count_backup_words(
+    database: &rusqlite::Connection,
+    id: i64,
+) -> rusqlite::Result<Result<i32, String>> {
+    let backup = match database::select_backup_info(database, id)? {
+        Some(backup) => backup,
+        None => return Ok(Err(String::from("The entry has no backup"))),
+    };
+    if !backup.content_type.starts_with("text/html") {
+        return Ok(Err(format!(
+            "The backup is {}, not html",
+            backup.content_type
+        )));
+    }
+
+    let content =
+        database::read_backup_bytes(database, id, backup.content_offset, backup.content_length)?;
+    Ok(
+        article_word_count(&decode_text(&backup.content_type, &content))
+            .ok_or_else(|| String::from("No article was found in the backup")),
+    )
+}
+
+// Counts the words of every article outside the trash whose word count is 0 from its html backup, and saves them as
+// a change of each entry, so that they are in its history.
+pub fn fill_missing_word_counts(
+    database: &rusqlite::Connection,
+) -> rusqlite::Result<WordCountReport> {
+    let mut statement = database.prepare(
+        "SELECT entry_id FROM entries
+        WHERE date_deleted IS NULL AND entry_type = 0 AND entry_type_metadata = 0
+        ORDER BY entry_id",
+    )?;
+    let ids = statement
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<i64>>>()?;
+
+    let mut report = WordCountReport::default();
+    for id in ids {
+        match count_backup_words(database, id)? {
+            Ok(words) => {
+                if let Some(entry) = database::select_entry(database, id)? {
+                    let mut form = form_from_entry(&entry);
+                    form.entry_type = EntryType::Article { words };
+                    database::update_entry(database, id, &form)?;
+                    report.counted.push(CountedArticle { id, words });
+                }
+            }
+            Err(reason) => report.uncounted.push(UncountedArticle { id, reason }),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::{Date, Month};
+    use crate::entry::NewEntryForm;
+    use crate::history;
+
+    fn open_in_memory() -> rusqlite::Connection {
+        let database = rusqlite::Connection::open_in_memory().unwrap();
+        database::create_tables(&database).unwrap();
+        database
+    }
+
+    fn insert(database: &rusqlite::Connection, entry_type: EntryType) -> i64 {
+        let form = NewEntryForm {
+            link: String::from("https://example.com/bonnie-and-clyde"),
+            title: String::from("Bonnie and Clyde"),
+            description: String::new(),
+            authors: vec![String::from("Pauline Kael")],
+            category: String::from("Cinema"),
+            themes: Vec::new(),
+            works_mentioned: Vec::new(),
+            tags: Vec::new(),
+            date_published: Date {
+                day: 21,
+                month: Month::October,
+                year: 1967,
+            },
+            date_saved: None,
+            exceptional: false,
+            entry_type,
+            identifier: None,
+        };
+        database::insert_entry(database, &form).unwrap()
+    }
+
+    // fill_missing_word_counts
+
+    #[test]
+    fn articles_without_word_count_are_counted_from_their_backups() {
+        let database = open_in_memory();
+        let latin1 = insert(&database, EntryType::Article { words: 0 });
+        database::update_backup(
+            &database,
+            latin1,
+            "text/html; charset=iso-8859-1",
+            b"<article><p>Cr\xEDtica de una pel\xEDcula que cambi\xF3 el cine americano.</p></article>",
+        )
+        .unwrap();
+        let counted = insert(&database, EntryType::Article { words: 7000 });
+        database::update_backup(
+            &database,
+            counted,
+            "text/html",
+            b"<p>Not counted again, ever.</p>",
+        )
+        .unwrap();
+        let video = insert(
+            &database,
+            EntryType::Video {
+                length_in_seconds: 0,
+            },
+        );
+        let without_backup = insert(&database, EntryType::Article { words: 0 });
+        let pdf = insert(&database, EntryType::Article { words: 0 });
+        database::update_backup(&database, pdf, "application/pdf", b"%PDF-1.4").unwrap();
+        let short = insert(&database, EntryType::Article { words: 0 });
+        database::update_backup(&database, short, "text/html", b"<p>Hello</p>").unwrap();
+
+        let report = fill_missing_word_counts(&database).unwrap();
+        assert_eq!(
+            report.counted,
+            [CountedArticle {
+                id: latin1,
+                words: 9
+            }]
+        );
+        assert_eq!(
+            report
+                .uncounted
+                .iter()
+                .map(|article| article.id)
+                .collect::<Vec<_>>(),
+            [without_backup, pdf, short]
+        );
+
+        let entry = database::select_entry(&database, latin1).unwrap().unwrap();
+        assert_eq!(entry.entry_type, EntryType::Article { words: 9 });
+        assert_eq!(
+            history::select_revisions(&database, latin1).unwrap().len(),
+            2
+        );
+        assert_eq!(
+            database::select_entry(&database, video)
+                .unwrap()
+                .unwrap()
+                .entry_type,
+            EntryType::Video {
+                length_in_seconds: 0
+            }
+        );
+    }
+}
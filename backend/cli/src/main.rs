@@ -0,0 +1,72 @@
+// Command line access to the archive, working directly on the database named in the same config file as the server.
+//
+// Usage:
+//   cli search [query]   Search entries with the same query syntax as /api/texts, e.g. author=Kael&category=Cinema
+//   cli show <id>        Show a single entry
+//   cli reconcile        Rebuild the tables of categories, authors, themes, works and tags from the entries
+//   cli count-words      Fill in the word count of articles that have none from their html backups
+
+use archive::{config, database, taxonomy, url_to_sql_query, word_counts};
+use std::env;
+use std::process::ExitCode;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+fn open_database() -> Result<rusqlite::Connection, Error> {
+    let config_path =
+        config::config_file_path().ok_or("Could not find user directory in the system.")?;
+    let config = config::load_config_file(config_path)?;
+    Ok(database::open(config.database_path)?)
+}
+
+fn search(query_text: &str) -> Result<(), Error> {
+    let query = url_to_sql_query::url_to_sql_query(query_text).ok_or("Invalid search query.")?;
+    let response = database::select_texts(&open_database()?, &query)?;
+    println!("{}", serde_json::to_string_pretty(&response)?);
+    Ok(())
+}
+
+fn show(id: &str) -> Result<(), Error> {
+    let id: i64 = id.parse()?;
+    match database::select_entry(&open_database()?, id)? {
+        Some(entry) => println!("{}", serde_json::to_string_pretty(&entry)?),
+        None => return Err(format!("There is no entry with id {}.", id).into()),
+    }
+    Ok(())
+}
+
+fn reconcile() -> Result<(), Error> {
+    let rebuilt = taxonomy::rebuild(&open_database()?)?;
+    println!("{}", serde_json::to_string_pretty(&rebuilt)?);
+    Ok(())
+}
+
+fn count_words() -> Result<(), Error> {
+    let report = word_counts::fill_missing_word_counts(&open_database()?)?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+fn run(args: &[String]) -> Result<(), Error> {
+    match args {
+        [command] if command == "search" => search(""),
+        [command, query] if command == "search" => search(query),
+        [command, id] if command == "show" => show(id),
+        [command] if command == "reconcile" => reconcile(),
+        [command] if command == "count-words" => count_words(),
+        _ => Err(
+            "Usage: cli search [query] | cli show <id> | cli reconcile | cli count-words".into(),
+        ),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,203 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    // Both ends are inclusive, as in the Content-Range header.
+    pub first: usize,
+    pub last: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeRequest {
+    // No range was requested, or the range can't be honored in a single part, so the whole resource is served.
+    Full,
+    Partial(ByteRange),
+    Unsatisfiable,
+}
+
+// Parses the value of a Range header for a resource of `total_length` bytes. Only single ranges
+// in bytes are honored. Anything else is ignored, which RFC 7233 allows by serving the full resource.
+pub fn parse_range_header(header: &str, total_length: usize) -> RangeRequest {
+    let specifier = match header.trim().strip_prefix("bytes=") {
+        Some(s) => s.trim(),
+        None => return RangeRequest::Full,
+    };
+
+    if specifier.contains(',') {
+        return RangeRequest::Full;
+    }
+
+    let (first, last) = match specifier.split_once('-') {
+        Some((first, last)) => (first.trim(), last.trim()),
+        None => return RangeRequest::Full,
+    };
+
+    if first.is_empty() {
+        // Suffix range: the last N bytes.
+        let suffix_length = match last.parse::<usize>() {
+            Ok(n) => n,
+            Err(_) => return RangeRequest::Full,
+        };
+        if suffix_length == 0 || total_length == 0 {
+            return RangeRequest::Unsatisfiable;
+        }
+        return RangeRequest::Partial(ByteRange {
+            first: total_length.saturating_sub(suffix_length),
+            last: total_length - 1,
+        });
+    }
+
+    let first = match first.parse::<usize>() {
+        Ok(n) => n,
+        Err(_) => return RangeRequest::Full,
+    };
+
+    let last = if last.is_empty() {
+        None
+    } else {
+        match last.parse::<usize>() {
+            Ok(n) => Some(n),
+            Err(_) => return RangeRequest::Full,
+        }
+    };
+
+    if let Some(last) = last {
+        if last < first {
+            return RangeRequest::Full;
+        }
+    }
+
+    if first >= total_length {
+        return RangeRequest::Unsatisfiable;
+    }
+
+    RangeRequest::Partial(ByteRange {
+        first,
+        last: std::cmp::min(last.unwrap_or(total_length - 1), total_length - 1),
+    })
+}
+
+// If-Range uses strong comparison, so weak entity tags never match. Dates never match either,
+// because we don't keep track of modification times of backups.
+pub fn if_range_matches(if_range: &str, etag: Option<&str>) -> bool {
+    let if_range = if_range.trim();
+    match etag {
+        Some(etag) => !if_range.starts_with("W/") && if_range == etag,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // parse_range_header
+
+    #[test]
+    fn parse_range_header_first_and_last() {
+        assert_eq!(
+            parse_range_header("bytes=0-499", 1000),
+            RangeRequest::Partial(ByteRange {
+                first: 0,
+                last: 499
+            })
+        );
+        assert_eq!(
+            parse_range_header("bytes=500-999", 1000),
+            RangeRequest::Partial(ByteRange {
+                first: 500,
+                last: 999
+            })
+        );
+    }
+
+    #[test]
+    fn parse_range_header_open_ended() {
+        assert_eq!(
+            parse_range_header("bytes=900-", 1000),
+            RangeRequest::Partial(ByteRange {
+                first: 900,
+                last: 999
+            })
+        );
+    }
+
+    #[test]
+    fn parse_range_header_suffix() {
+        assert_eq!(
+            parse_range_header("bytes=-100", 1000),
+            RangeRequest::Partial(ByteRange {
+                first: 900,
+                last: 999
+            })
+        );
+        assert_eq!(
+            parse_range_header("bytes=-5000", 1000),
+            RangeRequest::Partial(ByteRange {
+                first: 0,
+                last: 999
+            })
+        );
+    }
+
+    #[test]
+    fn parse_range_header_last_is_clamped_to_length() {
+        assert_eq!(
+            parse_range_header("bytes=100-5000", 1000),
+            RangeRequest::Partial(ByteRange {
+                first: 100,
+                last: 999
+            })
+        );
+    }
+
+    #[test]
+    fn parse_range_header_past_the_end_is_unsatisfiable() {
+        assert_eq!(
+            parse_range_header("bytes=1000-", 1000),
+            RangeRequest::Unsatisfiable
+        );
+        assert_eq!(
+            parse_range_header("bytes=-0", 1000),
+            RangeRequest::Unsatisfiable
+        );
+        assert_eq!(
+            parse_range_header("bytes=0-10", 0),
+            RangeRequest::Unsatisfiable
+        );
+    }
+
+    #[test]
+    fn parse_range_header_unsupported_or_malformed_serves_everything() {
+        assert_eq!(parse_range_header("items=0-10", 1000), RangeRequest::Full);
+        assert_eq!(
+            parse_range_header("bytes=0-10,20-30", 1000),
+            RangeRequest::Full
+        );
+        assert_eq!(
+            parse_range_header("bytes=ten-twenty", 1000),
+            RangeRequest::Full
+        );
+        assert_eq!(
+            parse_range_header("bytes=500-100", 1000),
+            RangeRequest::Full
+        );
+        assert_eq!(parse_range_header("bytes=100", 1000), RangeRequest::Full);
+    }
+
+    // if_range_matches
+
+    #[test]
+    fn if_range_matches_equal_strong_etags() {
+        assert!(if_range_matches(r#""abc""#, Some(r#""abc""#)));
+        assert!(!if_range_matches(r#""abc""#, Some(r#""def""#)));
+    }
+
+    #[test]
+    fn if_range_matches_never_weak_etags_or_dates() {
+        assert!(!if_range_matches(r#"W/"abc""#, Some(r#""abc""#)));
+        assert!(!if_range_matches(
+            "Wed, 21 Oct 2015 07:28:00 GMT",
+            Some(r#""abc""#)
+        ));
+        assert!(!if_range_matches(r#""abc""#, None));
+    }
+}
@@ -0,0 +1,275 @@
+// Downloads of the resources entries link to, for their images and backups, and of the favicons of their sites.
+
+use crate::http;
+use crate::state;
+
+use archive::charset::decode_text;
+use archive::html_meta::PageMetadata;
+use archive::identifiers::{identifier_in_link, parse_identifier, Identifier};
+use archive::images::{normalize_favicon, normalize_image};
+use archive::links::canonical_domain;
+use archive::oembed::{oembed_link, oembed_metadata};
+use archive::sites::{self, site_of_page};
+use archive::suggestion::{
+    merge, sources_of_page, sources_of_resource, suggest_entry, Source, SuggestedEntry,
+};
+use hyper::body::Bytes;
+use hyper::StatusCode;
+
+const IMAGE_CONTENT_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/bmp"];
+
+pub struct Resource {
+    pub content_type: String,
+    pub content: Bytes,
+}
+
+impl Resource {
+    // The content as text, transcoded to UTF-8 from the charset it is in.
+    pub fn text(&self) -> String {
+        decode_text(&self.content_type, &self.content)
+    }
+}
+
+// Gets the resource at the url. Fails if it can't be reached, the answer is not 200 OK or it has no content type.
+pub async fn fetch(url: &str) -> Result<Resource, String> {
+    let response = http::get(url)
+        .await
+        .map_err(|err| format!("Request to url {} failed: {}", url, err))?;
+
+    if response.status() != StatusCode::OK {
+        return Err(format!("Could not get resource at url {}", url));
+    }
+
+    let content_type = match http::get_header_case_insensitive(response.headers(), "Content-Type") {
+        None => {
+            return Err(format!(
+                "Missing content type header in resource at url {}",
+                url
+            ))
+        }
+        Some(value) => String::from(value.to_str().unwrap_or_default()),
+    };
+
+    let content = hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(|err| format!("Reading resource at url {} failed: {}", url, err))?;
+
+    Ok(Resource {
+        content_type,
+        content,
+    })
+}
+
+// Gets the image at the url, which must be of one of the supported types.
+pub async fn fetch_image(url: &str) -> Result<Bytes, String> {
+    let resource = fetch(url).await?;
+    if !IMAGE_CONTENT_TYPES.contains(&resource.content_type.as_str()) {
+        return Err(format!(
+            "Unsuported content type {} at link {}",
+            resource.content_type, url
+        ));
+    }
+    Ok(resource.content)
+}
+
+// Gets the image that represents the page at the url, as named by its metadata (og:image, twitter:image or JSON-LD),
+// and normalizes it. If the url is an image itself, that image is used.
+pub async fn fetch_image_of_page(url: &str) -> Result<Vec<u8>, String> {
+    let page = fetch(url).await?;
+    image_of_page(url, &page).await
+}
+
+// Like fetch_image_of_page, for a page that has already been fetched from the url.
+pub async fn image_of_page(url: &str, page: &Resource) -> Result<Vec<u8>, String> {
+    let image = if IMAGE_CONTENT_TYPES.contains(&page.content_type.as_str()) {
+        page.content.clone()
+    } else if page.content_type.starts_with("text/html") {
+        let image_url = suggest_entry(&page.text())
+            .metadata
+            .image
+            .map(|image| resolve_url(url, &image))
+            .ok_or_else(|| format!("The page at {} names no image", url))?;
+        fetch_image(&image_url).await?
+    } else {
+        return Err(format!(
+            "Unsuported content type {} at link {}",
+            page.content_type, url
+        ));
+    };
+
+    normalize_image(&image).map_err(|err| format!("Resource is not a valid image: {}", err))
+}
+
+// Name and normalized favicon of the site of an entry, to be kept the first time an entry of the site is created. The
+// entry has its site without them, so nothing else depends on capturing them.
+pub struct SiteToCapture {
+    pub domain: String,
+    pub name: Option<String>,
+    pub favicon: Option<Vec<u8>>,
+}
+
+// The site of the page fetched from the url, unless the url is not a web page or its site has already been captured.
+// A site whose favicon can't be fetched is captured without one, so that it isn't fetched again with every entry.
+pub async fn site_to_capture(url: &str, page: &Resource) -> Option<SiteToCapture> {
+    let domain = canonical_domain(url)?;
+    match state::with_database(|database| sites::is_site_captured(database, &domain)) {
+        Ok(false) => {}
+        Ok(true) => return None,
+        Err(err) => {
+            println!("Site query failed: {}", err);
+            return None;
+        }
+    }
+
+    let html = if page.content_type.starts_with("text/html") {
+        page.text()
+    } else {
+        String::new()
+    };
+    let site = site_of_page(&html);
+
+    let favicon_url = resolve_url(url, &site.favicon_link);
+    let favicon = fetch(&favicon_url).await.and_then(|favicon| {
+        normalize_favicon(&favicon.content)
+            .map_err(|err| format!("Resource is not a valid image: {}", err))
+    });
+    let favicon = match favicon {
+        Ok(favicon) => Some(favicon),
+        Err(err) => {
+            println!("Favicon of {} could not be fetched: {}", domain, err);
+            None
+        }
+    };
+
+    Some(SiteToCapture {
+        domain,
+        name: site.name,
+        favicon,
+    })
+}
+
+// What the registry of the identifier says about the text, like its title, authors and number of pages.
+async fn resolve_identifier(identifier: &Identifier) -> Result<PageMetadata, String> {
+    let resolver = state::resolver_urls().resolver_for(identifier);
+    let url = resolver.record_url(identifier);
+    let record = fetch(&url).await?;
+    let mut metadata = resolver
+        .read_record(&record.text())
+        .ok_or_else(|| format!("There is no record of {} at {}", identifier, url))?;
+    metadata.identifier = Some(identifier.to_string());
+    Ok(metadata)
+}
+
+// Suggestion for the text with the identifier, from its registry alone. Fails if the registry has no record of it.
+pub async fn suggest_entry_for_identifier(
+    identifier: &Identifier,
+) -> Result<SuggestedEntry, String> {
+    let metadata = resolve_identifier(identifier).await?;
+    Ok(merge(&[(Source::Registry, metadata)]))
+}
+
+// Suggestion for the resource fetched from the url, if it is of a type there is anything to read from or the url or
+// the page say what its DOI, arXiv id or ISBN is. What the oEmbed endpoint an html page links to and the registry of
+// the identifier say about it are merged in too, unless they can't be reached.
+pub async fn suggest_entry_for_page(url: &str, page: &Resource) -> Option<SuggestedEntry> {
+    let mut sources = if page.content_type.starts_with("text/html") {
+        let html = page.text();
+        let mut sources = sources_of_page(&html);
+        if let Some(link) = oembed_link(&html) {
+            match fetch(&resolve_url(url, &link)).await {
+                Ok(response) => {
+                    if let Some(metadata) = oembed_metadata(&response.text()) {
+                        sources.push((Source::OEmbed, metadata));
+                    }
+                }
+                Err(err) => println!("oEmbed of {} could not be fetched: {}", url, err),
+            }
+        }
+        sources
+    } else {
+        sources_of_resource(&page.content_type, &page.content).unwrap_or_default()
+    };
+
+    let identifier = identifier_in_link(url).or_else(|| {
+        let identifier = merge(&sources).metadata.identifier?;
+        parse_identifier(&identifier)
+    });
+    if let Some(identifier) = identifier {
+        match resolve_identifier(&identifier).await {
+            Ok(metadata) => sources.push((Source::Registry, metadata)),
+            Err(err) => {
+                println!("{} could not be resolved: {}", identifier, err);
+                sources.push((
+                    Source::Registry,
+                    PageMetadata {
+                        identifier: Some(identifier.to_string()),
+                        ..PageMetadata::default()
+                    },
+                ));
+            }
+        }
+    }
+
+    (!sources.is_empty()).then(|| merge(&sources))
+}
+
+// Makes a link found in the page at base absolute.
+fn resolve_url(base: &str, link: &str) -> String {
+    if link.contains("://") {
+        return String::from(link);
+    }
+
+    let base_uri = match base.parse::<hyper::Uri>() {
+        Ok(uri) => uri,
+        Err(_) => return String::from(link),
+    };
+    let scheme = base_uri.scheme_str().unwrap_or("http");
+    let authority = base_uri.authority().map(|a| a.as_str()).unwrap_or_default();
+
+    if let Some(path) = link.strip_prefix("//") {
+        format!("{}://{}", scheme, path)
+    } else if link.starts_with('/') {
+        format!("{}://{}{}", scheme, authority, link)
+    } else {
+        let directory = match base_uri.path().rfind('/') {
+            Some(end) => &base_uri.path()[..end + 1],
+            None => "/",
+        };
+        format!("{}://{}{}{}", scheme, authority, directory, link)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // resolve_url
+
+    #[test]
+    fn resolve_url_keeps_absolute_links() {
+        assert_eq!(
+            resolve_url(
+                "https://example.com/a/page",
+                "https://cdn.example.com/cover.png"
+            ),
+            "https://cdn.example.com/cover.png"
+        );
+    }
+
+    #[test]
+    fn resolve_url_of_relative_links() {
+        let base = "https://example.com/articles/page.html?id=5";
+        assert_eq!(
+            resolve_url(base, "//cdn.example.com/cover.png"),
+            "https://cdn.example.com/cover.png"
+        );
+        assert_eq!(
+            resolve_url(base, "/images/cover.png"),
+            "https://example.com/images/cover.png"
+        );
+        assert_eq!(
+            resolve_url(base, "cover.png"),
+            "https://example.com/articles/cover.png"
+        );
+    }
+}
@@ -1,48 +1,79 @@
-use crate::date::Date;
-use crate::entry_type::EntryType;
-
+use archive::media::MediaMetadata;
+use archive::pdf::PdfMetadata;
+use archive::suggestion::SuggestedEntry;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Deserialize, Debug)]
-pub struct NewEntryForm {
-    pub link: String,
-    pub title: String,
-    pub description: String,
-    pub authors: Vec<String>,
-    pub category: String,
-    pub themes: Vec<String>,
-    pub works_mentioned: Vec<String>,
-    pub tags: Vec<String>,
-    pub date_published: Date,
-    pub exceptional: bool,
-    pub entry_type: EntryType,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
-pub struct Entry {
-    pub id: i64,
-    pub link: String,
-    pub title: String,
-    pub description: String,
-    pub authors: Vec<String>,
-    pub category: String,
-    pub themes: Vec<String>,
-    pub works_mentioned: Vec<String>,
-    pub tags: Vec<String>,
-    pub date_published: Date,
-    pub date_saved: Date,
-    pub exceptional: bool,
-    pub entry_type: EntryType,
-    pub image: Option<String>,
-    pub backup: Option<String>,
-}
-
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ImageLinkForm {
     pub image_url: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct BackupLinkForm {
     pub backup_url: String,
 }
+
+#[derive(Serialize, ToSchema)]
+pub struct CreatedEntry {
+    pub id: i64,
+    pub link: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ResourceLink {
+    pub link: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StoredBackup {
+    pub link: String,
+    // Fields of the entry that were empty and were filled from the backup.
+    pub auto_filled: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BackupMetadata {
+    pub content_type: String,
+    // What the backup says about the entry, if it is of a type that can be read.
+    pub suggestion: Option<SuggestedEntry>,
+    pub pdf: Option<PdfMetadata>,
+    pub media: Option<MediaMetadata>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error_message: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DuplicateEntryResponse {
+    pub error_message: String,
+    pub existing_id: i64,
+    pub existing_link: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EntryFromUrlForm {
+    // Url of the page, or a DOI, arXiv id or ISBN.
+    pub url: String,
+    // Values of the entry form that take precedence over the ones found in the page.
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub overrides: serde_json::Map<String, serde_json::Value>,
+    // Whether to store the page as the backup of the entry.
+    #[serde(default)]
+    pub backup: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreatedEntryFromUrl {
+    pub id: i64,
+    pub link: String,
+    // Fields of the entry whose values were found in the page, including the image.
+    pub auto_filled: Vec<String>,
+    pub image: Option<String>,
+    pub backup: Option<String>,
+    // Problems that didn't stop the entry from being created, like an image that couldn't be downloaded.
+    pub warnings: Vec<String>,
+}
@@ -5,10 +5,10 @@ pub fn get_header_case_insensitive<'a>(
     target_header: &str,
 ) -> Option<&'a hyper::header::HeaderValue> {
     let target_lowercase = target_header.to_lowercase();
-    return headers
+    headers
         .iter()
         .find(|(name, _)| name.as_str().to_lowercase() == target_lowercase)
-        .map(|(_, value)| value);
+        .map(|(_, value)| value)
 }
 
 pub async fn request(
@@ -20,13 +20,29 @@ pub async fn request(
     client.request(request).await
 }
 
-pub async fn get(url: &str) -> hyper::Result<hyper::Response<hyper::Body>> {
+// Why a GET request failed: either the url is not a valid URI, so it was never sent, or the request itself failed.
+#[derive(Debug)]
+pub enum GetError {
+    InvalidUrl(hyper::http::Error),
+    Request(hyper::Error),
+}
+
+impl std::fmt::Display for GetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GetError::InvalidUrl(err) => write!(f, "invalid url: {}", err),
+            GetError::Request(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+pub async fn get(url: &str) -> Result<hyper::Response<hyper::Body>, GetError> {
     let req = hyper::Request::builder()
         .method(hyper::Method::GET)
         .uri(url)
         .header("user-agent", "localhost/0.1.0")
         .body(hyper::Body::from(""))
-        .unwrap();
+        .map_err(GetError::InvalidUrl)?;
 
-    request(req).await
+    request(req).await.map_err(GetError::Request)
 }
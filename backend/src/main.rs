@@ -1,29 +1,23 @@
-mod date;
-mod entry_type;
+mod byte_range;
+mod fetch;
 mod forms;
-mod html_meta;
 mod http;
-mod images;
+mod openapi;
 mod paths;
 mod requests;
-mod sql_array;
 mod state;
-mod url_to_sql_query;
 
+#[cfg(test)]
+mod tests;
+
+use archive::{config, database};
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Method, Request, Response, Server};
-use serde::Deserialize;
 use state::global_state;
 use std::convert::Infallible;
 use std::env;
-use std::fs;
-
-#[derive(Deserialize)]
-struct ConfigFile {
-    database_path: String,
-}
 
-async fn process_request(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+pub async fn process_request(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
     match req.uri().query() {
         Some(query_text) => {
             println!(
@@ -38,6 +32,18 @@ async fn process_request(req: Request<Body>) -> Result<Response<Body>, hyper::Er
         }
     };
 
+    // HEAD is answered by every route that answers GET, with the same headers and no body.
+    if req.method() == Method::HEAD {
+        let (mut parts, body) = req.into_parts();
+        parts.method = Method::GET;
+        let response = route_request(Request::from_parts(parts, body)).await?;
+        return Ok(requests::without_body(response));
+    }
+
+    route_request(req).await
+}
+
+async fn route_request(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
     match (req.method(), req.uri().path()) {
         (&Method::OPTIONS, _) => requests::options(),
 
@@ -57,14 +63,33 @@ async fn process_request(req: Request<Body>) -> Result<Response<Body>, hyper::Er
             requests::serve_page("pages/index.html")
         }
 
+        (&Method::GET, "/api/openapi.json") => requests::get_openapi(),
+
         (&Method::GET, "/api/texts") => requests::get_texts(req),
         (&Method::GET, path) if paths::is_single_entry_path(path) => requests::get_single_text(req),
         (&Method::PUT, path) if paths::is_single_entry_path(path) => {
             requests::put_single_text(req).await
         }
+        (&Method::PATCH, path) if paths::is_single_entry_path(path) => {
+            requests::patch_single_text(req).await
+        }
         (&Method::DELETE, path) if paths::is_single_entry_path(path) => {
             requests::delete_single_text(req)
         }
+        (&Method::GET, path) if paths::is_entry_history_path(path) => {
+            requests::get_entry_history(req)
+        }
+        (&Method::POST, path) if paths::is_revision_restore_path(path) => {
+            requests::restore_entry_revision(req)
+        }
+        (&Method::GET, "/api/trash") => requests::get_trash(),
+        (&Method::GET, "/api/duplicates") => requests::get_duplicates(req),
+        (&Method::POST, path) if paths::is_trash_restore_path(path) => {
+            requests::restore_from_trash(req)
+        }
+        (&Method::DELETE, path) if paths::is_trash_entry_path(path) => {
+            requests::purge_from_trash(req)
+        }
         (&Method::GET, path) if paths::is_entry_image_path(path) => requests::get_entry_image(req),
         (&Method::PUT, path) if paths::is_entry_image_path(path) => {
             requests::put_entry_image(req).await
@@ -81,13 +106,20 @@ async fn process_request(req: Request<Body>) -> Result<Response<Body>, hyper::Er
         (&Method::DELETE, path) if paths::is_entry_backup_path(path) => {
             requests::delete_entry_backup(req)
         }
+        (&Method::GET, path) if paths::is_entry_backup_metadata_path(path) => {
+            requests::get_entry_backup_metadata(req)
+        }
         (&Method::POST, "/api/texts") => requests::post_texts(req).await,
+        (&Method::POST, "/api/texts/bulk") => requests::post_bulk(req).await,
+        (&Method::POST, "/api/texts/from_url") => requests::post_text_from_url(req).await,
 
         (&Method::GET, "/api/categories") => requests::get_categories(),
         (&Method::GET, "/api/authors") => requests::get_authors(),
         (&Method::GET, "/api/themes") => requests::get_themes(),
         (&Method::GET, "/api/works") => requests::get_works(),
         (&Method::GET, "/api/tags") => requests::get_tags(),
+        (&Method::GET, "/api/sites") => requests::get_sites(),
+        (&Method::GET, path) if paths::is_site_favicon_path(path) => requests::get_site_favicon(req),
 
         (&Method::GET, path) if path.starts_with("/api/forward/") => {
             requests::forward_get_request(path.strip_prefix("/api/forward/").unwrap()).await
@@ -95,96 +127,56 @@ async fn process_request(req: Request<Body>) -> Result<Response<Body>, hyper::Er
         (&Method::GET, path) if path.starts_with("/api/meta_headers/") => {
             requests::get_meta_headers_at_url(req.uri()).await
         }
+        (&Method::GET, path) if path.starts_with("/api/page_metadata/") => {
+            requests::get_page_metadata_at_url(req.uri()).await
+        }
 
         // Return the 404 Not Found for other routes.
         _ => requests::not_found_404_response(),
     }
 }
 
-fn load_config_file<P: AsRef<std::path::Path>>(
-    path: P,
-) -> Result<ConfigFile, Box<dyn std::error::Error + Send + Sync>> {
-    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+// Purges the entries that have been in the trash for longer than the retention period, at startup and then every hour
+// while the server runs.
+async fn purge_expired_trash_periodically(retention_days: u32) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+    loop {
+        interval.tick().await;
+        match state::with_database(|database| {
+            database::purge_expired_trash(database, retention_days)
+        }) {
+            Ok(0) => {}
+            Ok(purged) => println!("Purged {} entries from the trash", purged),
+            Err(err) => println!("Purging the trash failed: {}", err),
+        }
+    }
 }
 
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let config_path = match dirs::home_dir() {
-        Some(dir) => {
-            let mut osstr = dir.into_os_string();
-            osstr.push("/.localhost.json");
-            osstr
-        }
+    let config_path = match config::config_file_path() {
+        Some(path) => path,
         None => {
             println!("Could not find user directory in the system. The program will now close.");
             return Ok(());
         }
     };
     println!("Config path: {}", config_path.to_str().unwrap());
-    let config = load_config_file(config_path)?;
+    let config = config::load_config_file(config_path)?;
 
     println!("Loading database at: {}", config.database_path);
 
-    let connection = rusqlite::Connection::open(config.database_path)?;
-
-    connection.execute(
-        "CREATE TABLE IF NOT EXISTS entries (
-            entry_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
-            link TEXT NOT NULL COLLATE NOCASE,
-            title TEXT NOT NULL COLLATE NOCASE,
-            description TEXT NOT NULL COLLATE NOCASE,
-            author TEXT NOT NULL,
-            category TEXT NOT NULL,
-            themes TEXT NOT NULL,
-            works_mentioned TEXT NOT NULL,
-            tags TEXT NOT NULL,
-            date_published DATE NOT NULL,
-            date_saved DATE NOT NULL,
-            exceptional BOOL NOT NULL,
-            entry_type INT NOT NULL,
-            entry_type_metadata INT NOT NULL,
-            image BLOB,
-            backup BLOB
-        );",
-        [],
-    )?;
-
-    connection.execute("CREATE TABLE IF NOT EXISTS categories (id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL, value TEXT UNIQUE NOT NULL);", [])?;
-
-    connection.execute(
-        "CREATE TABLE IF NOT EXISTS authors (
-            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
-            value TEXT UNIQUE NOT NULL,
-            category TEXT NOT NULL
-        );",
-        [],
-    )?;
-    connection.execute(
-        "CREATE TABLE IF NOT EXISTS themes (
-            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
-            value TEXT UNIQUE NOT NULL,
-            category TEXT NOT NULL
-        );",
-        [],
-    )?;
-    connection.execute(
-        "CREATE TABLE IF NOT EXISTS works (
-            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
-            value TEXT UNIQUE NOT NULL,
-            category TEXT NOT NULL
-        );",
-        [],
-    )?;
-    connection.execute(
-        "CREATE TABLE IF NOT EXISTS tags (
-            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
-            value TEXT UNIQUE NOT NULL,
-            category TEXT NOT NULL
-        );",
-        [],
-    )?;
-
-    global_state().lock().unwrap().database = Some(connection);
+    let connection = database::open(config.database_path)?;
+
+    {
+        let mut state = global_state().lock().unwrap();
+        state.database = Some(connection);
+        state.resolvers = Some(config.resolvers);
+    }
+
+    tokio::spawn(purge_expired_trash_periodically(
+        config.trash_retention_days,
+    ));
 
     // For every connection, we must make a `Service` to handle all
     // incoming HTTP requests on said connection.
@@ -0,0 +1,63 @@
+use crate::requests;
+
+use utoipa::openapi::schema::{KnownFormat, ObjectBuilder, Schema, SchemaFormat, Type};
+use utoipa::openapi::RefOr;
+use utoipa::{OpenApi, PartialSchema, ToSchema};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "localhost",
+        description = "Archive of everything interesting read or watched, with its metadata, images and backups.",
+        license(name = "MIT")
+    ),
+    paths(
+        requests::get_texts,
+        requests::post_texts,
+        requests::post_bulk,
+        requests::post_text_from_url,
+        requests::get_single_text,
+        requests::put_single_text,
+        requests::patch_single_text,
+        requests::delete_single_text,
+        requests::get_entry_history,
+        requests::restore_entry_revision,
+        requests::get_trash,
+        requests::restore_from_trash,
+        requests::purge_from_trash,
+        requests::get_duplicates,
+        requests::get_entry_image,
+        requests::put_entry_image,
+        requests::delete_entry_image,
+        requests::get_entry_backup,
+        requests::put_entry_backup,
+        requests::delete_entry_backup,
+        requests::get_entry_backup_metadata,
+        requests::get_categories,
+        requests::get_authors,
+        requests::get_themes,
+        requests::get_works,
+        requests::get_tags,
+        requests::get_sites,
+        requests::get_site_favicon,
+        requests::forward_get_request,
+        requests::get_meta_headers_at_url,
+        requests::get_page_metadata_at_url,
+        requests::get_openapi,
+    )
+)]
+pub struct ApiDoc;
+
+// Schema of request and response bodies that are raw bytes, like images and backups.
+pub struct Binary;
+
+impl PartialSchema for Binary {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::String)
+            .format(Some(SchemaFormat::KnownFormat(KnownFormat::Binary)))
+            .into()
+    }
+}
+
+impl ToSchema for Binary {}
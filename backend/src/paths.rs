@@ -25,6 +25,46 @@ pub fn is_entry_backup_path(path: &str) -> bool {
     is_entry_subpath(path, "backup")
 }
 
+pub fn is_entry_history_path(path: &str) -> bool {
+    is_entry_subpath(path, "history")
+}
+
+// /api/texts/{id}/history/{revision}/restore
+pub fn is_revision_restore_path(path: &str) -> bool {
+    if let Some(p) = path.strip_prefix("/api/texts/") {
+        let parts = p.split('/').collect::<Vec<_>>();
+        return parts.len() == 4
+            && parts[0].parse::<i64>().is_ok()
+            && parts[1] == "history"
+            && parts[2].parse::<i64>().is_ok()
+            && parts[3] == "restore";
+    }
+
+    false
+}
+
+// /api/texts/{id}/backup/metadata
+pub fn is_entry_backup_metadata_path(path: &str) -> bool {
+    if let Some(p) = path.strip_prefix("/api/texts/") {
+        let parts = p.split('/').collect::<Vec<_>>();
+        return parts.len() == 3
+            && parts[0].parse::<i64>().is_ok()
+            && parts[1] == "backup"
+            && parts[2] == "metadata";
+    }
+
+    false
+}
+
+pub fn get_revision_id_from_path(path: &str) -> i64 {
+    if let Some(p) = path.strip_prefix("/api/texts/") {
+        let parts = p.split('/').collect::<Vec<_>>();
+        return parts[2].parse::<i64>().unwrap();
+    }
+
+    unreachable!();
+}
+
 pub fn get_entry_id_from_path(path: &str) -> i64 {
     if let Some(p) = path.strip_prefix("/api/texts/") {
         let parts = p.split('/').collect::<Vec<_>>();
@@ -34,6 +74,51 @@ pub fn get_entry_id_from_path(path: &str) -> i64 {
     unreachable!();
 }
 
+// /api/trash/{id}
+pub fn is_trash_entry_path(path: &str) -> bool {
+    if let Some(p) = path.strip_prefix("/api/trash/") {
+        return p.parse::<i64>().is_ok();
+    }
+    false
+}
+
+// /api/trash/{id}/restore
+pub fn is_trash_restore_path(path: &str) -> bool {
+    if let Some(p) = path.strip_prefix("/api/trash/") {
+        if let Some(id) = p.strip_suffix("/restore") {
+            return id.parse::<i64>().is_ok();
+        }
+    }
+    false
+}
+
+pub fn get_trash_entry_id_from_path(path: &str) -> i64 {
+    if let Some(p) = path.strip_prefix("/api/trash/") {
+        let parts = p.split('/').collect::<Vec<_>>();
+        return parts[0].parse::<i64>().unwrap();
+    }
+
+    unreachable!();
+}
+
+// /api/sites/{domain}/favicon
+pub fn is_site_favicon_path(path: &str) -> bool {
+    if let Some(p) = path.strip_prefix("/api/sites/") {
+        if let Some(domain) = p.strip_suffix("/favicon") {
+            return !domain.is_empty() && !domain.contains('/');
+        }
+    }
+    false
+}
+
+pub fn get_site_domain_from_path(path: &str) -> &str {
+    if let Some(p) = path.strip_prefix("/api/sites/") {
+        return p.split('/').next().unwrap();
+    }
+
+    unreachable!();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -67,4 +152,90 @@ mod tests {
         assert!(!is_single_entry_path("/api/texts/215/image"));
         assert!(!is_single_entry_path("/api/texts/1845348/backup"));
     }
+
+    // is_revision_restore_path
+
+    #[test]
+    fn is_revision_restore_path_correct_paths() {
+        assert!(is_revision_restore_path("/api/texts/1/history/3/restore"));
+        assert!(is_revision_restore_path(
+            "/api/texts/215/history/1845348/restore"
+        ));
+    }
+
+    #[test]
+    fn is_revision_restore_path_incorrect_paths() {
+        assert!(!is_revision_restore_path("/api/texts/1/history"));
+        assert!(!is_revision_restore_path("/api/texts/1/history/3"));
+        assert!(!is_revision_restore_path(
+            "/api/texts/1/history/three/restore"
+        ));
+        assert!(!is_revision_restore_path("/api/texts/1/backup/3/restore"));
+        assert!(!is_revision_restore_path(
+            "/api/texts/1/history/3/restore/now"
+        ));
+    }
+
+    // is_entry_backup_metadata_path
+
+    #[test]
+    fn is_entry_backup_metadata_path_correct_paths() {
+        assert!(is_entry_backup_metadata_path(
+            "/api/texts/1/backup/metadata"
+        ));
+        assert!(!is_entry_backup_metadata_path("/api/texts/1/backup"));
+        assert!(!is_entry_backup_metadata_path(
+            "/api/texts/one/backup/metadata"
+        ));
+        assert!(!is_entry_backup_metadata_path(
+            "/api/texts/1/image/metadata"
+        ));
+        assert!(!is_entry_backup_metadata_path(
+            "/api/texts/1/backup/metadata/title"
+        ));
+    }
+
+    #[test]
+    fn get_revision_id_from_path_reads_the_revision() {
+        assert_eq!(
+            get_revision_id_from_path("/api/texts/1/history/3/restore"),
+            3
+        );
+        assert_eq!(get_entry_id_from_path("/api/texts/1/history/3/restore"), 1);
+    }
+
+    // is_trash_entry_path, is_trash_restore_path
+
+    #[test]
+    fn trash_paths_correct_paths() {
+        assert!(is_trash_entry_path("/api/trash/1"));
+        assert!(is_trash_restore_path("/api/trash/215/restore"));
+        assert_eq!(get_trash_entry_id_from_path("/api/trash/215/restore"), 215);
+    }
+
+    #[test]
+    fn trash_paths_incorrect_paths() {
+        assert!(!is_trash_entry_path("/api/trash"));
+        assert!(!is_trash_entry_path("/api/trash/one"));
+        assert!(!is_trash_entry_path("/api/trash/1/restore"));
+        assert!(!is_trash_restore_path("/api/trash/1"));
+        assert!(!is_trash_restore_path("/api/trash/one/restore"));
+        assert!(!is_trash_restore_path("/api/texts/1/restore"));
+    }
+
+    // is_site_favicon_path
+
+    #[test]
+    fn site_favicon_paths() {
+        assert!(is_site_favicon_path("/api/sites/example.com/favicon"));
+        assert!(is_site_favicon_path("/api/sites/127.0.0.1:8080/favicon"));
+        assert_eq!(
+            get_site_domain_from_path("/api/sites/example.com/favicon"),
+            "example.com"
+        );
+        assert!(!is_site_favicon_path("/api/sites"));
+        assert!(!is_site_favicon_path("/api/sites//favicon"));
+        assert!(!is_site_favicon_path("/api/sites/example.com/a/favicon"));
+        assert!(!is_site_favicon_path("/api/sites/example.com/image"));
+    }
 }
@@ -1,30 +1,43 @@
 use crate::http;
 use crate::paths;
-use crate::date;
-use crate::state::*;
-use crate::url_to_sql_query::{url_to_sql_query, SqlQuery};
-use crate::sql_array::*;
-use crate::entry_type;
+use crate::byte_range::{self, RangeRequest};
+use crate::state::with_database;
+use crate::fetch;
 use crate::forms::*;
-use crate::html_meta::html_meta_headers;
-use crate::images::normalize_image;
+use crate::openapi::{ApiDoc, Binary};
+
+use archive::bulk::{self, BulkEntryResult, BulkOperation, BulkRequest, BulkResponse, BulkStatus};
+use archive::charset::decode_text;
+use archive::database::{self, GetTextsResponse, PatchResult, RestoreResult, StringWithCategory};
+use archive::duplicates::{self, DuplicateGroup};
+use archive::entry::{form_from_entry, Entry, NewEntryForm};
+use archive::history::{self, RevisionChanges};
+use archive::html_meta::html_meta_headers;
+use archive::identifiers::parse_identifier;
+use archive::page_entry::{fill_empty_fields, fill_entry_form, FilledForm};
+use archive::media::{self, read_media_metadata};
+use archive::pdf::{self, read_pdf_metadata};
+use archive::sites::{self, SiteWithCount};
+use archive::suggestion::{suggest_entry_for_resource, SuggestedEntry};
+use archive::images::normalize_image;
+use archive::url_to_sql_query::{url_to_sql_query, SqlQuery};
+use archive::validation::{self, ValidationErrors};
 
 use hyper::{Body, Request, Response, StatusCode};
-use rand::rngs::SmallRng;
-use rand::seq::SliceRandom;
-use rand::SeedableRng;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::fs;
+use utoipa::OpenApi;
 
 pub fn options() -> Result<Response<Body>, hyper::Error>
 {
     println!("Options!");
     Response::builder()
         .status(StatusCode::NO_CONTENT)
-        .header("Allow", "OPTIONS, GET, PUT, POST, DELETE")
+        .header("Allow", "OPTIONS, GET, HEAD, PUT, PATCH, POST, DELETE")
         .header("Access-Control-Allow-Origin", "*")
         .header("Access-Control-Allow-Headers", "*")
-        .header("Access-Control-Allow-Methods", "OPTIONS, GET, PUT, POST, DELETE")
+        .header("Access-Control-Allow-Methods", "OPTIONS, GET, HEAD, PUT, PATCH, POST, DELETE")
         .body(Body::from(""))
         .or_else(|_| internal_server_error_response())
 }
@@ -60,10 +73,88 @@ fn bad_request_response(error_message : &str) -> Result<Response<Body>, hyper::E
         .header("Access-Control-Allow-Origin", "*")
         .header("Access-Control-Allow-Headers", "*")
         .header("Content-Type", "application/json")
-        .body(Body::from(format!(r#"{{"error_message":"{}""#, error_message)))
+        .body(Body::from(serde_json::to_string(&ErrorResponse{ error_message : String::from(error_message) }).unwrap()))
         .or_else(|_| internal_server_error_response())
 }
 
+fn validation_error_response(errors : &ValidationErrors) -> Result<Response<Body>, hyper::Error>
+{
+    println!("Rejecting invalid entry: {}", errors.error_message);
+
+    Response::builder()
+        .status(StatusCode::BAD_REQUEST)
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Access-Control-Allow-Headers", "*")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(errors).unwrap()))
+        .or_else(|_| internal_server_error_response())
+}
+
+fn no_content_response() -> Result<Response<Body>, hyper::Error>
+{
+    Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Access-Control-Allow-Headers", "*")
+        .body(Body::from(""))
+        .or_else(|_| internal_server_error_response())
+}
+
+// Turns the response to a GET request into the response to the equivalent HEAD request: same status and
+// headers, no body. The length of the body that would have been sent is kept in Content-Length.
+pub fn without_body(response : Response<Body>) -> Response<Body>
+{
+    let (mut parts, body) = response.into_parts();
+    if !parts.headers.contains_key(hyper::header::CONTENT_LENGTH) {
+        if let Some(length) = hyper::body::HttpBody::size_hint(&body).exact() {
+            parts.headers.insert(hyper::header::CONTENT_LENGTH, hyper::header::HeaderValue::from(length));
+        }
+    }
+    Response::from_parts(parts, Body::empty())
+}
+
+// Maps the result of a statement that modifies a single entry to a response. Modifying no rows means there is no such entry.
+fn single_entry_modification_response(result : rusqlite::Result<usize>, action : &str) -> Result<Response<Body>, hyper::Error>
+{
+    match result {
+        Ok(0) => not_found_404_response(),
+        Ok(_) => no_content_response(),
+        Err(err) => {
+            println!("{} failed: {}", action, err);
+            internal_server_error_response()
+        }
+    }
+}
+
+fn entry_etag(entry : &Entry) -> String
+{
+    format!("\"{}\"", entry.version)
+}
+
+fn entry_http_response(entry : &Entry) -> Result<Response<Body>, hyper::Error>
+{
+    let mut response = to_json_http_response(entry)?;
+    if let Ok(etag) = hyper::header::HeaderValue::from_str(&entry_etag(entry)) {
+        response.headers_mut().insert("ETag", etag);
+        response.headers_mut().insert("Access-Control-Expose-Headers", hyper::header::HeaderValue::from_static("ETag"));
+    }
+    Ok(response)
+}
+
+// Versions of the entry listed in an If-Match header, as entity tags or plain numbers. None if there is no header or
+// it is "*", which every existing entry matches. If-Match compares entity tags strongly, so weak ones match nothing.
+fn if_match_versions(req : &Request<Body>) -> Option<Vec<i64>>
+{
+    let header = req.headers().get("If-Match")?.to_str().unwrap_or("");
+    if header.trim() == "*" { return None; }
+
+    Some(header.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.starts_with("W/"))
+        .filter_map(|tag| tag.trim_matches('"').parse::<i64>().ok())
+        .collect())
+}
+
 fn to_json_http_response<T : Serialize>(entries : &T) -> Result<Response<Body>, hyper::Error>
 {
     if let Ok(json) = serde_json::to_string(entries) {
@@ -79,54 +170,95 @@ fn to_json_http_response<T : Serialize>(entries : &T) -> Result<Response<Body>,
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/texts",
+    description = "Search entries, in pages of 10. Without search parameters, all entries are returned in a random order given by the seed.",
+    params(
+        ("link" = Option<String>, Query, description = "Link contains"),
+        ("title" = Option<String>, Query, description = "Title contains"),
+        ("author" = Option<String>, Query, description = "One of the authors is"),
+        ("description" = Option<String>, Query, description = "Description contains"),
+        ("category" = Option<String>, Query, description = "Category is"),
+        ("type" = Option<String>, Query, description = "Entry type is one of article, paper, book, video or audio"),
+        ("identifier" = Option<String>, Query, description = "Has the DOI, arXiv id or ISBN, written in any way, or an identifier that contains the text"),
+        ("site" = Option<String>, Query, description = "Links to the site with the domain, written in any way or as a link to any of its pages, or with the name"),
+        ("works_mentioned" = Option<String>, Query, description = "Mentions every work in a list separated by |"),
+        ("themes" = Option<String>, Query, description = "Has every theme in a list separated by |"),
+        ("tags" = Option<String>, Query, description = "Has every tag in a list separated by |"),
+        ("published_between_from" = Option<String>, Query, description = "Published on or after a date formatted as yyyy-mm-dd"),
+        ("published_between_until" = Option<String>, Query, description = "Published on or before a date formatted as yyyy-mm-dd"),
+        ("saved_between_from" = Option<String>, Query, description = "Saved on or after a date formatted as yyyy-mm-dd"),
+        ("saved_between_until" = Option<String>, Query, description = "Saved on or before a date formatted as yyyy-mm-dd"),
+        ("modified_between_from" = Option<String>, Query, description = "Last modified on or after a date formatted as yyyy-mm-dd"),
+        ("modified_between_until" = Option<String>, Query, description = "Last modified on or before a date formatted as yyyy-mm-dd"),
+        ("exceptional" = Option<bool>, Query, description = "Is marked as exceptional"),
+        ("offset" = Option<usize>, Query, description = "Number of results to skip"),
+        ("seed" = Option<u64>, Query, description = "Seed of the random order of results when there are no search parameters"),
+    ),
+    responses(
+        (status = 200, body = GetTextsResponse),
+        (status = 400, description = "The search query could not be parsed", body = ErrorResponse),
+        (status = 500),
+    )
+)]
 pub fn get_texts(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
 {
     let sql_query = match req.uri().query() {
         Some(query_text) => match url_to_sql_query(query_text) {
             Some(result) => result,
-            None => { return internal_server_error_response(); }
+            None => { return bad_request_response(&format!("Could not parse search query '{}'", query_text)); }
         },
         None => SqlQuery::default()
     };
 
-    let served_entries = {
-        let state = global_state().lock().unwrap();
-        match select_texts(state.database.as_ref().unwrap(), &sql_query) {
-            Ok(entries) => entries,
-            Err(err) => {
-                println!("SQL query error: {}", err);
-                return internal_server_error_response();
-            }
-        }
-    };
+    if !sql_query.where_query.is_empty() { println!("SQL query: {}", sql_query.where_query); }
 
-    to_json_http_response(&served_entries)
+    match with_database(|database| database::select_texts(database, &sql_query)) {
+        Ok(served_entries) => to_json_http_response(&served_entries),
+        Err(err) => {
+            println!("SQL query error: {}", err);
+            internal_server_error_response()
+        }
+    }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/texts/{id}",
+    params(("id" = i64, Path, description = "Id of the entry")),
+    responses(
+        (status = 200, body = Entry, headers(("ETag" = String, description = "Version of the entry"))),
+        (status = 404, description = "There is no such entry"),
+        (status = 500),
+    )
+)]
 pub fn get_single_text(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
 {
-    let path = req.uri().path().strip_prefix("/api/texts/").unwrap();
-    if let Ok(entry_id) = path.parse::<i64>() {
-        let served_entries = {
-            let state = global_state().lock().unwrap();
-            match select_texts(state.database.as_ref().unwrap(),  &SqlQuery::with_query_str("entry_id = ?", [ format!("{}", entry_id) ])) {
-                Ok(entries) => entries,
-                Err(err) => {
-                    println!("{}", err);
-                    return internal_server_error_response();
-                }
-            }
-        };
-        if served_entries.entries.is_empty() {
-            not_found_404_response()
-        } else {
-            to_json_http_response(&served_entries.entries[0])
+    let entry_id = paths::get_entry_id_from_path(req.uri().path());
+
+    match with_database(|database| database::select_entry(database, entry_id)) {
+        Ok(Some(entry)) => entry_http_response(&entry),
+        Ok(None) => not_found_404_response(),
+        Err(err) => {
+            println!("{}", err);
+            internal_server_error_response()
         }
-    } else {
-        bad_request_response(&format!("Could not convert '{}' to an entry id", path))
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/texts/{id}",
+    params(("id" = i64, Path, description = "Id of the entry")),
+    request_body = NewEntryForm,
+    responses(
+        (status = 204, description = "The entry was replaced"),
+        (status = 400, description = "The body is malformed or some fields are invalid", body = ValidationErrors),
+        (status = 404, description = "There is no such entry"),
+        (status = 500),
+    )
+)]
 pub async fn put_single_text(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
 {
     let entry_id = paths::get_entry_id_from_path(req.uri().path());
@@ -134,150 +266,268 @@ pub async fn put_single_text(req : Request<Body>) -> Result<Response<Body>, hype
     let whole_body = hyper::body::to_bytes(req.into_body()).await?;
     match serde_json::from_slice(&whole_body) as Result<NewEntryForm, serde_json::Error> {
         Ok(form) => {
-            let state = global_state().lock().unwrap();
-
-            if let Some(database) = &state.database {
-                let result = database.execute(
-                    "
-                    UPDATE entries
-                    SET link = ?1,
-                        title = ?2,
-                        description = ?3,
-                        author = ?4,
-                        category = ?5,
-                        themes = ?6,
-                        works_mentioned = ?7,
-                        tags = ?8,
-                        date_published = ?9,
-                        date_saved = ?10,
-                        exceptional = ?11,
-                        entry_type = ?12,
-                        entry_type_metadata = ?13
-                    WHERE entry_id = ?14;
-                    ",
-                    rusqlite::params!
-                        [ &form.link
-                        , &form.title
-                        , &form.description
-                        , &format_as_sql_array(&form.authors)
-                        , &form.category 
-                        , &format_as_sql_array(&form.themes) 
-                        , &format_as_sql_array(&form.works_mentioned) 
-                        , &format_as_sql_array(&form.tags)
-                        , &date::format_as_sql_date(form.date_published)
-                        , &date::format_as_sql_date(date::today())
-                        , form.exceptional
-                        , entry_type::index(form.entry_type)
-                        , entry_type::metadata(form.entry_type)
-                        , entry_id
-                        ]
-                );
-
-                if let Err(err) = result {
-                    println!("Entry update failed: {}", err);
-                    return internal_server_error_response();
+            // The link is checked against the one the entry has, so it is done while holding the database.
+            let result = with_database(|database| -> rusqlite::Result<Result<usize, ValidationErrors>> {
+                let current_link = database::select_entry(database, entry_id)?.map(|entry| entry.link);
+                match validation::normalize_and_validate_edit(form, current_link.as_deref()) {
+                    Ok(form) => database::update_entry(database, entry_id, &form).map(Ok),
+                    Err(errors) => Ok(Err(errors)),
                 }
-
-                Response::builder()
-                    .status(StatusCode::NO_CONTENT)
-                    .header("Access-Control-Allow-Origin", "*")
-                    .header("Access-Control-Allow-Headers", "*")
-                    .body(Body::from(""))
-                    .or_else(|_| internal_server_error_response())
-            } else {
-                internal_server_error_response()
+            });
+            match result {
+                Ok(Err(errors)) => validation_error_response(&errors),
+                Ok(Ok(modified)) => single_entry_modification_response(Ok(modified), "Entry update"),
+                Err(err) => single_entry_modification_response(Err(err), "Entry update"),
             }
         },
         Err(err) => bad_request_response(&format!("{}", err))
     }
 }
 
-pub fn delete_single_text(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
+#[utoipa::path(
+    patch,
+    path = "/api/texts/{id}",
+    description = "Changes only the fields in the body, which is a JSON Merge Patch (RFC 7396) of the entry form. Lists and the entry type are replaced as a whole.",
+    params(
+        ("id" = i64, Path, description = "Id of the entry"),
+        ("If-Match" = Option<String>, Header, description = "Only patch the entry if its ETag or version is one of these"),
+    ),
+    request_body(content = Object, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, body = Entry, headers(("ETag" = String, description = "Version of the patched entry"))),
+        (status = 400, description = "The patch is malformed or some fields of the patched entry are invalid", body = ValidationErrors),
+        (status = 404, description = "There is no such entry"),
+        (status = 412, description = "The entry changed since the version in If-Match"),
+        (status = 500),
+    )
+)]
+pub async fn patch_single_text(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
 {
     let entry_id = paths::get_entry_id_from_path(req.uri().path());
+    let expected_versions = if_match_versions(&req);
 
-    let state = global_state().lock().unwrap();
-    let database = state.database.as_ref().unwrap();
+    let whole_body = hyper::body::to_bytes(req.into_body()).await?;
+    let patch : serde_json::Value = match serde_json::from_slice(&whole_body) {
+        Ok(patch) => patch,
+        Err(err) => { return bad_request_response(&format!("{}", err)); }
+    };
 
-    let result = database.execute(
-        "
-        DELETE FROM entries
-        WHERE entry_id = ?
-        ",
-        rusqlite::params![entry_id]
-    );
+    match with_database(|database| database::patch_entry(database, entry_id, &patch, expected_versions.as_deref())) {
+        Ok(PatchResult::Patched(entry)) => entry_http_response(&entry),
+        Ok(PatchResult::NoSuchEntry) => not_found_404_response(),
+        Ok(PatchResult::VersionMismatch) => {
+            Response::builder()
+                .status(StatusCode::PRECONDITION_FAILED)
+                .header("Access-Control-Allow-Origin", "*")
+                .header("Access-Control-Allow-Headers", "*")
+                .body(Body::from(""))
+                .or_else(|_| internal_server_error_response())
+        }
+        Ok(PatchResult::Invalid(error_message)) => bad_request_response(&error_message),
+        Ok(PatchResult::Rejected(errors)) => validation_error_response(&errors),
+        Err(err) => {
+            println!("Entry patch failed: {}", err);
+            internal_server_error_response()
+        }
+    }
+}
 
-    if let Err(err) = result {
-        println!("Entry delete failed: {}", err);
-        return not_found_404_response();
+#[utoipa::path(
+    delete,
+    path = "/api/texts/{id}",
+    params(("id" = i64, Path, description = "Id of the entry")),
+    responses(
+        (status = 204, description = "The entry was moved to the trash"),
+        (status = 404, description = "There is no such entry"),
+        (status = 500),
+    )
+)]
+pub fn delete_single_text(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
+{
+    let entry_id = paths::get_entry_id_from_path(req.uri().path());
+
+    let result = with_database(|database| database::delete_entry(database, entry_id));
+    single_entry_modification_response(result, "Entry delete")
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/trash",
+    description = "Deleted entries, most recently deleted first. They are purged automatically after the retention period set in the config file.",
+    responses((status = 200, body = Vec<Entry>), (status = 500))
+)]
+pub fn get_trash() -> Result<Response<Body>, hyper::Error>
+{
+    match with_database(database::select_trash) {
+        Ok(entries) => to_json_http_response(&entries),
+        Err(err) => {
+            println!("Trash query failed: {}", err);
+            internal_server_error_response()
+        }
     }
+}
 
-    Response::builder()
-        .status(StatusCode::NO_CONTENT)
-        .header("Access-Control-Allow-Origin", "*")
-        .header("Access-Control-Allow-Headers", "*")
-        .body(Body::from(""))
-        .or_else(|_| internal_server_error_response())
+#[utoipa::path(
+    post,
+    path = "/api/trash/{id}/restore",
+    params(("id" = i64, Path, description = "Id of the entry")),
+    responses(
+        (status = 204, description = "The entry was taken out of the trash"),
+        (status = 404, description = "There is no such entry in the trash"),
+        (status = 500),
+    )
+)]
+pub fn restore_from_trash(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
+{
+    let entry_id = paths::get_trash_entry_id_from_path(req.uri().path());
+
+    let result = with_database(|database| database::restore_from_trash(database, entry_id));
+    single_entry_modification_response(result, "Trash restore")
 }
 
-pub fn get_entry_image(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
+#[utoipa::path(
+    delete,
+    path = "/api/trash/{id}",
+    description = "Deletes the entry permanently, including its image and backup. Its history is kept.",
+    params(("id" = i64, Path, description = "Id of the entry")),
+    responses(
+        (status = 204, description = "The entry was purged"),
+        (status = 404, description = "There is no such entry in the trash"),
+        (status = 500),
+    )
+)]
+pub fn purge_from_trash(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
+{
+    let entry_id = paths::get_trash_entry_id_from_path(req.uri().path());
+
+    let result = with_database(|database| database::purge_entry(database, entry_id));
+    single_entry_modification_response(result, "Trash purge")
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/texts/{id}/history",
+    description = "Revisions of the entry, oldest first, with the fields each one changed.",
+    params(("id" = i64, Path, description = "Id of the entry")),
+    responses(
+        (status = 200, body = Vec<RevisionChanges>),
+        (status = 404, description = "There is no such entry and never was"),
+        (status = 500),
+    )
+)]
+pub fn get_entry_history(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
 {
     let entry_id = paths::get_entry_id_from_path(req.uri().path());
 
-    let state = global_state().lock().unwrap();
-    let database = state.database.as_ref().unwrap();
- 
-    let mut statement = match database.prepare("SELECT image FROM entries WHERE entry_id = ?") {
-        Ok(s) => s,
-        Err(_) => { return internal_server_error_response(); }
-    };
-    let mut rows = match statement.query([entry_id]) {
-        Ok(r) => r,
-        Err(_) => { return internal_server_error_response(); }
-    };
-        
-    let first = match rows.next() {
-        Ok(r) => r,
-        Err(_) => { return internal_server_error_response(); }
-    };
+    let result = with_database(|database| -> rusqlite::Result<Option<Vec<RevisionChanges>>> {
+        let revisions = history::select_history(database, entry_id)?;
+        // Entries saved before history was recorded exist without revisions.
+        if revisions.is_empty() && database::select_entry(database, entry_id)?.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(revisions))
+    });
 
-    if let Some(found_row) = first {
-        let blob_ref = match found_row.get_ref(0) {
-            Ok(r) => r,
-            Err(_) => { return internal_server_error_response(); }
-        };
+    match result {
+        Ok(Some(revisions)) => to_json_http_response(&revisions),
+        Ok(None) => not_found_404_response(),
+        Err(err) => {
+            println!("History query failed: {}", err);
+            internal_server_error_response()
+        }
+    }
+}
 
-        let maybe_blob = match blob_ref.as_blob_or_null() {
-            Ok(r) => r,
-            Err(_) => { return internal_server_error_response(); }
-        };
+#[utoipa::path(
+    post,
+    path = "/api/texts/{id}/history/{revision}/restore",
+    description = "Sets the entry back to the values it had right after the revision. A deleted entry is created again, without its image and backup.",
+    params(
+        ("id" = i64, Path, description = "Id of the entry"),
+        ("revision" = i64, Path, description = "Id of the revision"),
+    ),
+    responses(
+        (status = 204, description = "The entry was restored"),
+        (status = 400, description = "The revision deleted the entry, so there is nothing to restore", body = ErrorResponse),
+        (status = 404, description = "The entry has no such revision"),
+        (status = 500),
+    )
+)]
+pub fn restore_entry_revision(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
+{
+    let entry_id = paths::get_entry_id_from_path(req.uri().path());
+    let revision_id = paths::get_revision_id_from_path(req.uri().path());
+
+    match with_database(|database| database::restore_revision(database, entry_id, revision_id)) {
+        Ok(RestoreResult::Restored) => no_content_response(),
+        Ok(RestoreResult::NoSuchRevision) => not_found_404_response(),
+        Ok(RestoreResult::NothingToRestore) => bad_request_response(&format!("Revision {} deleted the entry. Restore an earlier revision instead.", revision_id)),
+        Err(err) => {
+            println!("Restore failed: {}", err);
+            internal_server_error_response()
+        }
+    }
+}
 
-        if let Some(blob) = maybe_blob {
-            let copy = Vec::from(blob);
+#[utoipa::path(
+    get,
+    path = "/api/texts/{id}/image",
+    params(("id" = i64, Path, description = "Id of the entry")),
+    responses(
+        (status = 200, description = "300x169 png image", content_type = "image/png", body = Binary),
+        (status = 404, description = "There is no such entry or it has no image"),
+        (status = 500),
+    )
+)]
+pub fn get_entry_image(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
+{
+    let entry_id = paths::get_entry_id_from_path(req.uri().path());
 
+    match with_database(|database| database::select_image(database, entry_id)) {
+        Ok(Some(image)) => {
             Response::builder()
                 .status(StatusCode::OK)
                 .header("Access-Control-Allow-Origin", "*")
                 .header("Access-Control-Allow-Headers", "*")
                 .header("Content-Type", "image/png")
                 .header("Cache-Control", "public, max-age=31919000, immutable")
-                .body(Body::from(copy))
+                .body(Body::from(image))
                 .or_else(|_| internal_server_error_response())
-        } else {
-            not_found_404_response()
         }
-    } else {
-        not_found_404_response()
+        Ok(None) => not_found_404_response(),
+        Err(err) => {
+            println!("Image query failed: {}", err);
+            internal_server_error_response()
+        }
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/texts/{id}/image",
+    description = "Set the image of an entry, either from the image in the body or from a link to download it from. The image is normalized to a 300x169 png.",
+    params(("id" = i64, Path, description = "Id of the entry")),
+    request_body(content(
+        (ImageLinkForm = "application/json"),
+        (Binary = "image/png"),
+        (Binary = "image/jpeg"),
+        (Binary = "image/gif"),
+        (Binary = "image/bmp"),
+    )),
+    responses(
+        (status = 200, body = ResourceLink),
+        (status = 400, body = ErrorResponse),
+        (status = 404, description = "There is no such entry"),
+        (status = 500),
+    )
+)]
 pub async fn put_entry_image(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
 {
     let entry_id = paths::get_entry_id_from_path(req.uri().path());
 
     let is_content_in_body : bool = match http::get_header_case_insensitive(req.headers(), "Content-Type") {
         None => { return bad_request_response("Missing content type hader"); }
-        Some(value) => match value.to_str().unwrap() {
+        Some(value) => match value.to_str().unwrap_or_default() {
             // TO DO: Save image type.
             "image/png" => true,
             "image/jpeg" => true,
@@ -294,273 +544,300 @@ pub async fn put_entry_image(req : Request<Body>) -> Result<Response<Body>, hype
         whole_body
     } else {
         match serde_json::from_slice(&whole_body) as Result<ImageLinkForm, serde_json::Error> {
-            Ok(form) => { 
-                let response = match http::get(&form.image_url).await {
-                    Ok(r) => r,
-                    Err(err) => {
-                        println!("Request failed: {}", err);
-                        Err(err)?
-                    }
-                };
-
-                match http::get_header_case_insensitive(response.headers(), "Content-Type") {
-                    None => { return bad_request_response(&format!("Missing content type header at link {}.", &form.image_url)); }
-                    Some(value) => match value.to_str().unwrap() {
-                        // TO DO: Save image type.
-                        "image/png" => (),
-                        "image/jpeg" => (),
-                        "image/gif" => (),
-                        "image/bmp" => (),
-                        other => { return bad_request_response(&format!("Unsuported content type {} at link {}", other, &form.image_url)); }
-                    }
-                }
-
-                hyper::body::to_bytes(response.into_body()).await?
-            }
+            Ok(form) => match fetch::fetch_image(&form.image_url).await {
+                Ok(image) => image,
+                Err(err) => { return bad_request_response(&err); }
+            },
             Err(err) => { return bad_request_response(&format!("{}", err));  }
         }
     };
 
-    let normalized_image_bytes = match normalize_image(image_bytes) {
+    let normalized_image_bytes = match normalize_image(&image_bytes) {
         Ok(x) => x,
         Err(err) => return bad_request_response(&format!("Body is not a valid image: {}", err))
     };
 
-    let state = global_state().lock().unwrap();
-    let database = state.database.as_ref().unwrap();
-    match database.execute(
-        "
-        UPDATE entries
-        SET image = ?
-        WHERE entry_id = ?
-        ",
-        rusqlite::params![rusqlite::blob::ZeroBlob(normalized_image_bytes.len() as i32), entry_id]
-    ) {
+    match with_database(|database| database::update_image(database, entry_id, &normalized_image_bytes)) {
         // No values where modified.
-        Ok(0) => { return not_found_404_response(); }
-        // Something went wrong within the database.
-        Err(_) => { return internal_server_error_response(); }
+        Ok(0) => not_found_404_response(),
         // Everything went fine.
-        Ok(_) => ()
-    };
-
-    let mut blob = match database.blob_open(rusqlite::MAIN_DB, "entries", "image", entry_id, false) {
-        Ok(b) => b,
-        Err(_) => { return internal_server_error_response(); }
-    };
-
-    if blob.write_at(&normalized_image_bytes, 0).is_err() {
-        return internal_server_error_response();
-    };
-
-    Response::builder()
-        .status(StatusCode::OK)
-        .header("Access-Control-Allow-Origin", "*")
-        .header("Access-Control-Allow-Headers", "*")
-        .header("Content-Type", "application/json")
-        .body(Body::from(format!(r#"{{"link":"/api/texts/{}/image"}}"#, entry_id)))
-        .or_else(|_| internal_server_error_response())
+        Ok(_) => to_json_http_response(&ResourceLink{ link : format!("/api/texts/{}/image", entry_id) }),
+        // Something went wrong within the database.
+        Err(err) => {
+            println!("Image update failed: {}", err);
+            internal_server_error_response()
+        }
+    }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/texts/{id}/image",
+    params(("id" = i64, Path, description = "Id of the entry")),
+    responses(
+        (status = 204, description = "The image was deleted"),
+        (status = 404, description = "There is no such entry"),
+        (status = 500),
+    )
+)]
 pub fn delete_entry_image(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
 {
     let entry_id = paths::get_entry_id_from_path(req.uri().path());
 
-    let state = global_state().lock().unwrap();
-    let database = state.database.as_ref().unwrap();
+    let result = with_database(|database| database::delete_image(database, entry_id));
+    single_entry_modification_response(result, "Image delete")
+}
 
-    let result = database.execute(
-        "
-        UPDATE entries
-        SET image = NULL
-        WHERE entry_id = ?
-        ",
-        rusqlite::params![entry_id]
-    );
+// Size of the chunks in which backups are streamed from the database to the client.
+const BACKUP_CHUNK_SIZE : usize = 64 * 1024;
 
-    if let Err(err) = result {
-        println!("Image delete failed: {}", err);
-        return not_found_404_response();
+// Sends the bytes in [first, first + length) of the backup of an entry through the body sender, reading them from
+// the database one chunk at a time. The database is only locked while reading each chunk, so a long download
+// doesn't block the rest of the server.
+async fn stream_backup(mut sender : hyper::body::Sender, entry_id : i64, first : usize, length : usize)
+{
+    let mut sent = 0;
+    while sent < length {
+        let chunk = with_database(|database| database::read_backup_bytes(database, entry_id, first + sent, std::cmp::min(BACKUP_CHUNK_SIZE, length - sent)));
+
+        match chunk {
+            Ok(chunk) if !chunk.is_empty() => {
+                sent += chunk.len();
+                // The client went away. Nothing left to do.
+                if sender.send_data(hyper::body::Bytes::from(chunk)).await.is_err() {
+                    return;
+                }
+            }
+            // The backup was deleted or shrank while streaming it. Abort so that the client doesn't think it got all the content.
+            Ok(_) => { sender.abort(); return; }
+            Err(err) => {
+                println!("Reading backup of entry {} failed: {}", entry_id, err);
+                sender.abort();
+                return;
+            }
+        }
     }
-
-    Response::builder()
-        .status(StatusCode::NO_CONTENT)
-        .header("Access-Control-Allow-Origin", "*")
-        .header("Access-Control-Allow-Headers", "*")
-        .body(Body::from(""))
-        .or_else(|_| internal_server_error_response())
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/texts/{id}/backup",
+    description = "Get the backup of an entry with the content type it was saved with. Single byte ranges are supported through Range and If-Range.",
+    params(
+        ("id" = i64, Path, description = "Id of the entry"),
+        ("Range" = Option<String>, Header, description = "Single range of bytes to get"),
+        ("If-Range" = Option<String>, Header, description = "Entity tag the range is valid for"),
+    ),
+    responses(
+        (status = 200, content_type = "application/octet-stream", body = Binary,
+            headers(("ETag" = String), ("Accept-Ranges" = String))),
+        (status = 206, content_type = "application/octet-stream", body = Binary,
+            headers(("ETag" = String), ("Content-Range" = String))),
+        (status = 404, description = "There is no such entry or it has no backup"),
+        (status = 416, description = "The range is past the end of the backup", headers(("Content-Range" = String))),
+        (status = 500),
+    )
+)]
 pub fn get_entry_backup(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
 {
     let entry_id = paths::get_entry_id_from_path(req.uri().path());
 
-    let state = global_state().lock().unwrap();
-    let database = state.database.as_ref().unwrap();
-
-    let mut statement = match database.prepare("SELECT backup FROM entries WHERE entry_id = ?") {
-        Ok(s) => s,
-        Err(_) => { return internal_server_error_response(); }
-    };
-    let mut rows = match statement.query([entry_id]) {
-        Ok(r) => r,
-        Err(_) => { return internal_server_error_response(); }
-    };
-        
-    let first = match rows.next() {
-        Ok(r) => r,
-        Err(_) => { return internal_server_error_response(); }
+    let backup = match with_database(|database| database::select_backup_info(database, entry_id)) {
+        Ok(Some(backup)) => backup,
+        Ok(None) => { return not_found_404_response(); }
+        Err(err) => {
+            println!("Backup query failed: {}", err);
+            return internal_server_error_response();
+        }
     };
+    let total_length = backup.content_length;
+    let etag = backup.etag;
 
-    if let Some(found_row) = first {
-        let blob_ref = match found_row.get_ref(0) {
-            Ok(r) => r,
-            Err(_) => { return internal_server_error_response(); }
-        };
-
-        let maybe_blob = match blob_ref.as_blob_or_null() {
-            Ok(r) => r,
-            Err(_) => { return internal_server_error_response(); }
-        };
+    let range_header = http::get_header_case_insensitive(req.headers(), "Range").and_then(|h| h.to_str().ok());
+    let if_range_header = http::get_header_case_insensitive(req.headers(), "If-Range").and_then(|h| h.to_str().ok());
 
-        if let Some(blob) = maybe_blob {
-            let content_type_length = blob[0] as usize;
-            let content_type = &blob[1..content_type_length + 1];
-            let content_data = Vec::from(&blob[content_type_length + 1..]);
+    // If the representation changed since the client got the part it has, ignore the range and send everything again.
+    let range = match (range_header, if_range_header) {
+        (Some(range), None) => byte_range::parse_range_header(range, total_length),
+        (Some(range), Some(if_range)) if byte_range::if_range_matches(if_range, etag.as_deref()) => byte_range::parse_range_header(range, total_length),
+        _ => RangeRequest::Full,
+    };
 
-            Response::builder()
-                .status(StatusCode::OK)
-                .header("Access-Control-Allow-Origin", "*")
-                .header("Access-Control-Allow-Headers", "*")
-                .header("Cache-Control", "public, max-age=31919000, immutable")
-                .header("Content-Type", content_type)
-                .body(Body::from(content_data))
-                .or_else(|_| internal_server_error_response())
-        } else {
-            not_found_404_response()
-        }
-    } else {
-        not_found_404_response()
+    let mut response = Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Access-Control-Allow-Headers", "*")
+        .header("Cache-Control", "public, max-age=31919000, immutable")
+        .header("Accept-Ranges", "bytes");
+    if let Some(etag) = &etag {
+        response = response.header("ETag", etag);
     }
-}
 
-fn write_entry_backup_to_database(entry_id : i64, body : hyper::body::Bytes, content_type : &str) -> Result<Response<Body>, hyper::Error>
-{
-    let blob_header_length = content_type.len() + 1; // + 1 for storing the size.
-    let blob_length = blob_header_length + body.len();
-
-    let state = global_state().lock().unwrap();
-    let database = state.database.as_ref().unwrap();
-    match database.execute(
-        "
-        UPDATE entries
-        SET backup = ?
-        WHERE entry_id = ?
-        ",
-        rusqlite::params![rusqlite::blob::ZeroBlob(blob_length as i32), entry_id]
-    ) {
-        // No values where modified.
-        Ok(0) => { return not_found_404_response(); }
-        // Something went wrong within the database.
-        Err(_) => { return internal_server_error_response(); }
-        // Everything went fine.
-        Ok(_) => ()
+    let (first, length) = match range {
+        RangeRequest::Full => {
+            response = response.status(StatusCode::OK);
+            (0, total_length)
+        }
+        RangeRequest::Partial(range) => {
+            response = response
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header("Content-Range", format!("bytes {}-{}/{}", range.first, range.last, total_length));
+            (range.first, range.last - range.first + 1)
+        }
+        RangeRequest::Unsatisfiable => {
+            return response
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header("Content-Range", format!("bytes */{}", total_length))
+                .body(Body::from(""))
+                .or_else(|_| internal_server_error_response());
+        }
     };
 
-    let mut blob = match database.blob_open(rusqlite::MAIN_DB, "entries", "backup", entry_id, false) {
-        Ok(b) => b,
-        Err(_) => { return internal_server_error_response(); }
-    };
+    let (sender, body) = Body::channel();
+    tokio::spawn(stream_backup(sender, entry_id, backup.content_offset + first, length));
 
-    // Write the length of the content type string in 1 byte. Content type strings are very short
-    // so 1 byte should always be enough.
-    if blob.write_at(&[content_type.len() as u8], 0).is_err() {
-        return internal_server_error_response();
-    };
+    response
+        .header("Content-Type", backup.content_type)
+        .header("Content-Length", length)
+        .body(body)
+        .or_else(|_| internal_server_error_response())
+}
 
-    // Write the content type string. This way, when a client requests the backup, we can return it with
-    // the correct content type.
-    if blob.write_at(content_type.as_bytes(), 1).is_err() {
-        return internal_server_error_response();
-    };
+// Stores the backup and fills the fields of the entry that were left empty with what the backup says about it, like
+// the number of pages of a PDF.
+fn write_entry_backup_to_database(entry_id : i64, body : hyper::body::Bytes, content_type : &str) -> Result<Response<Body>, hyper::Error>
+{
+    // The backup is read before taking the database, so that it isn't locked while parsing a big PDF.
+    let suggestion = suggest_entry_for_resource(content_type, &body);
 
-    // Write the actual backup data.
-    if blob.write_at(&body, blob_header_length).is_err() {
-        return internal_server_error_response();
-    };
+    let result = with_database(|database| database::atomically(database, || {
+        if database::update_backup(database, entry_id, content_type, &body)? == 0 {
+            return Ok(None);
+        }
+        let mut auto_filled = Vec::new();
+        if let (Some(suggestion), Some(entry)) = (&suggestion, database::select_entry(database, entry_id)?) {
+            let mut form = form_from_entry(&entry);
+            auto_filled = fill_empty_fields(&mut form, &suggestion.metadata);
+            if !auto_filled.is_empty() {
+                database::update_entry(database, entry_id, &form)?;
+            }
+        }
+        Ok(Some(auto_filled))
+    }));
 
-    Response::builder()
-        .status(StatusCode::OK)
-        .header("Access-Control-Allow-Origin", "*")
-        .header("Access-Control-Allow-Headers", "*")
-        .header("Content-Type", "application/json")
-        .body(Body::from(format!(r#"{{"link":"/api/texts/{}/backup"}}"#, entry_id)))
-        .or_else(|_| internal_server_error_response())
+    match result {
+        // No values where modified.
+        Ok(None) => not_found_404_response(),
+        // Everything went fine.
+        Ok(Some(auto_filled)) => to_json_http_response(&StoredBackup{ link : format!("/api/texts/{}/backup", entry_id), auto_filled }),
+        // Something went wrong within the database.
+        Err(err) => {
+            println!("Backup update failed: {}", err);
+            internal_server_error_response()
+        }
+    }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/texts/{id}/backup",
+    description = "Set the backup of an entry, either from the body or, if the body is a BackupLinkForm, from a link to download it from. The authors, description and length of the entry are filled from the backup if they are empty, like the number of pages of a PDF or the length of an audio or video file.",
+    params(("id" = i64, Path, description = "Id of the entry")),
+    request_body(content(
+        (BackupLinkForm = "application/json"),
+        (Binary = "application/octet-stream"),
+    )),
+    responses(
+        (status = 200, body = StoredBackup),
+        (status = 400, body = ErrorResponse),
+        (status = 404, description = "There is no such entry"),
+        (status = 500),
+    )
+)]
 pub async fn put_entry_backup(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
 {
     let entry_id = paths::get_entry_id_from_path(req.uri().path());
 
     let content_type = match http::get_header_case_insensitive(req.headers(), "Content-Type") {
         None => { return bad_request_response("Missing content type header"); }
-        Some(value) => String::from(value.to_str().unwrap())
+        Some(value) => match value.to_str() {
+            Ok(value) => String::from(value),
+            Err(_) => { return bad_request_response("Content type header is not valid text"); }
+        }
     };
 
     let whole_body = hyper::body::to_bytes(req.into_body()).await?;
 
     if content_type == "application/json" {
         if let Ok(form) = serde_json::from_slice(&whole_body) as Result<BackupLinkForm, serde_json::Error> {
-            let response = match http::get(&form.backup_url).await {
-                Ok(x) => x,
-                Err(err) => return bad_request_response(&format!("Request to url {} failed: {}", form.backup_url, err))
-            };
-                
-            if response.status() != StatusCode::OK {
-                return bad_request_response(&format!("Could not get resource at url {}", form.backup_url));
-            }
-                
-            let response_content_type = match http::get_header_case_insensitive(response.headers(), "Content-Type") {
-                None => { return bad_request_response(&format!("Missing content type header in resource at url {}", form.backup_url)); }
-                Some(value) => String::from(value.to_str().unwrap())
+            return match fetch::fetch(&form.backup_url).await {
+                Ok(resource) => write_entry_backup_to_database(entry_id, resource.content, &resource.content_type),
+                Err(err) => bad_request_response(&err)
             };
-
-            let response_body = hyper::body::to_bytes(response.into_body()).await?;
-
-            return write_entry_backup_to_database(entry_id, response_body, &response_content_type)
         }
     }
 
     write_entry_backup_to_database(entry_id, whole_body, &content_type)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/texts/{id}/backup",
+    params(("id" = i64, Path, description = "Id of the entry")),
+    responses(
+        (status = 204, description = "The backup was deleted"),
+        (status = 404, description = "There is no such entry"),
+        (status = 500),
+    )
+)]
 pub fn delete_entry_backup(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
 {
     let entry_id = paths::get_entry_id_from_path(req.uri().path());
 
-    let state = global_state().lock().unwrap();
-    let database = state.database.as_ref().unwrap();
+    let result = with_database(|database| database::delete_backup(database, entry_id));
+    single_entry_modification_response(result, "Backup delete")
+}
 
-    let result = database.execute(
-        "
-        UPDATE entries
-        SET backup = NULL
-        WHERE entry_id = ?
-        ",
-        rusqlite::params![entry_id]
-    );
+#[utoipa::path(
+    get,
+    path = "/api/texts/{id}/backup/metadata",
+    description = "What the backup of an entry says about it: a suggestion for the entry like the one for pages, the page count, document information and first page text of PDFs, and the length and tags of MP3, MP4, Ogg and WebM files. Backups of other types have no suggestion.",
+    params(("id" = i64, Path, description = "Id of the entry")),
+    responses(
+        (status = 200, body = BackupMetadata),
+        (status = 404, description = "There is no such entry or it has no backup"),
+        (status = 500),
+    )
+)]
+pub fn get_entry_backup_metadata(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
+{
+    let entry_id = paths::get_entry_id_from_path(req.uri().path());
 
-    if let Err(err) = result {
-        println!("Backup delete failed: {}", err);
-        return not_found_404_response();
-    }
+    let backup = with_database(|database| -> rusqlite::Result<Option<(String, Vec<u8>)>> {
+        match database::select_backup_info(database, entry_id)? {
+            Some(info) => {
+                let content = database::read_backup_bytes(database, entry_id, info.content_offset, info.content_length)?;
+                Ok(Some((info.content_type, content)))
+            }
+            None => Ok(None),
+        }
+    });
+    let (content_type, content) = match backup {
+        Ok(Some(backup)) => backup,
+        Ok(None) => { return not_found_404_response(); }
+        Err(err) => {
+            println!("Backup query failed: {}", err);
+            return internal_server_error_response();
+        }
+    };
 
-    Response::builder()
-        .status(StatusCode::NO_CONTENT)
-        .header("Access-Control-Allow-Origin", "*")
-        .header("Access-Control-Allow-Headers", "*")
-        .body(Body::from(""))
-        .or_else(|_| internal_server_error_response())
+    let pdf = pdf::is_pdf(&content_type, &content).then(|| read_pdf_metadata(&content).ok()).flatten();
+    let media = media::is_media(&content_type, &content).then(|| read_media_metadata(&content_type, &content).ok()).flatten();
+    to_json_http_response(&BackupMetadata{
+        suggestion : suggest_entry_for_resource(&content_type, &content),
+        content_type,
+        pdf,
+        media,
+    })
 }
 
 fn strings_as_http_response(strings : &Vec<String>) -> Result<Response<Body>, hyper::Error>
@@ -593,33 +870,53 @@ fn strings_with_categories_as_http_response(strings : &Vec<StringWithCategory>)
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/categories",
+    responses((status = 200, body = Vec<String>), (status = 500))
+)]
 pub fn get_categories() -> Result<Response<Body>, hyper::Error>
 {
-    match &get_all_strings_of("categories") {
+    match &with_database(|database| database::get_all_strings_of(database, "categories")) {
         Ok(strings) => strings_as_http_response(strings),
         Err(_) => internal_server_error_response()
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/authors",
+    responses((status = 200, body = Vec<StringWithCategory>), (status = 500))
+)]
 pub fn get_authors() -> Result<Response<Body>, hyper::Error>
 {
-    match &get_all_strings_by_category_of("authors") {
+    match &with_database(|database| database::get_all_strings_by_category_of(database, "authors")) {
         Ok(strings) => strings_with_categories_as_http_response(strings),
         Err(_) => internal_server_error_response()
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/themes",
+    responses((status = 200, body = Vec<StringWithCategory>), (status = 500))
+)]
 pub fn get_themes() -> Result<Response<Body>, hyper::Error>
 {
-    match &get_all_strings_by_category_of("themes") {
+    match &with_database(|database| database::get_all_strings_by_category_of(database, "themes")) {
         Ok(strings) => strings_with_categories_as_http_response(strings),
         Err(_) => internal_server_error_response()
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/works",
+    responses((status = 200, body = Vec<StringWithCategory>), (status = 500))
+)]
 pub fn get_works() -> Result<Response<Body>, hyper::Error>
 {
-    match &get_all_strings_by_category_of("works") {
+    match &with_database(|database| database::get_all_strings_by_category_of(database, "works")) {
         Ok(strings) => strings_with_categories_as_http_response(strings),
         Err(err) => { 
             println!("{}", err);
@@ -628,9 +925,14 @@ pub fn get_works() -> Result<Response<Body>, hyper::Error>
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/tags",
+    responses((status = 200, body = Vec<StringWithCategory>), (status = 500))
+)]
 pub fn get_tags() -> Result<Response<Body>, hyper::Error>
 {
-    match &get_all_strings_by_category_of("tags") {
+    match &with_database(|database| database::get_all_strings_by_category_of(database, "tags")) {
         Ok(strings) => strings_with_categories_as_http_response(strings),
         Err(err) => {
             println!("{}", err);
@@ -639,76 +941,406 @@ pub fn get_tags() -> Result<Response<Body>, hyper::Error>
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/sites",
+    description = "Sites of the entries outside the trash, by the domain of their links, with the most entries first. Sites no entry has been created from a url of are listed without name or favicon.",
+    responses((status = 200, body = Vec<SiteWithCount>), (status = 500))
+)]
+pub fn get_sites() -> Result<Response<Body>, hyper::Error>
+{
+    match with_database(sites::select_sites) {
+        Ok(sites) => to_json_http_response(&sites),
+        Err(err) => {
+            println!("Sites query failed: {}", err);
+            internal_server_error_response()
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sites/{domain}/favicon",
+    params(("domain" = String, Path, description = "Domain of the site")),
+    responses(
+        (status = 200, description = "32x32 png image", content_type = "image/png", body = Binary),
+        (status = 404, description = "The site has no favicon"),
+        (status = 500),
+    )
+)]
+pub fn get_site_favicon(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
+{
+    let domain = paths::get_site_domain_from_path(req.uri().path());
+
+    match with_database(|database| sites::select_favicon(database, domain)) {
+        Ok(Some(favicon)) => {
+            Response::builder()
+                .status(StatusCode::OK)
+                .header("Access-Control-Allow-Origin", "*")
+                .header("Access-Control-Allow-Headers", "*")
+                .header("Content-Type", "image/png")
+                .header("Cache-Control", "public, max-age=31919000, immutable")
+                .body(Body::from(favicon))
+                .or_else(|_| internal_server_error_response())
+        }
+        Ok(None) => not_found_404_response(),
+        Err(err) => {
+            println!("Favicon query failed: {}", err);
+            internal_server_error_response()
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/texts",
+    description = "Creates an entry, unless there already is one with the same link once both are canonicalized.",
+    params(("force" = Option<bool>, Query, description = "Create the entry even if there already is one with the same link")),
+    request_body = NewEntryForm,
+    responses(
+        (status = 200, body = CreatedEntry),
+        (status = 400, description = "The body is malformed or some fields are invalid", body = ValidationErrors),
+        (status = 409, description = "There already is an entry with the same link", body = DuplicateEntryResponse),
+        (status = 500),
+    )
+)]
 pub async fn post_texts(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
 {
+    let force = is_forced(&req);
+
     let whole_body = hyper::body::to_bytes(req.into_body()).await?;
     match serde_json::from_slice(&whole_body) as Result<NewEntryForm, serde_json::Error> {
         Ok(form) => {
-            let state = global_state().lock().unwrap();
-
-            if let Some(database) = &state.database {
-                let result = database.execute(
-                    "
-                    INSERT INTO entries (link, title, description, author, category, themes, works_mentioned, tags, date_published, date_saved, exceptional, entry_type, entry_type_metadata)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13);
-                    ",
-                    rusqlite::params!
-                        [ &form.link
-                        , &form.title
-                        , &form.description
-                        , &format_as_sql_array(&form.authors)
-                        , &form.category 
-                        , &format_as_sql_array(&form.themes) 
-                        , &format_as_sql_array(&form.works_mentioned) 
-                        , &format_as_sql_array(&form.tags)
-                        , &date::format_as_sql_date(form.date_published)
-                        , &date::format_as_sql_date(date::today())
-                        , form.exceptional
-                        , entry_type::index(form.entry_type)
-                        , entry_type::metadata(form.entry_type)
-                        ]
-                );
-
-                if let Err(err) = result {
+            let form = match validation::normalize_and_validate(form) {
+                Ok(form) => form,
+                Err(errors) => { return validation_error_response(&errors); }
+            };
+
+            // Checked while holding the database, so that two requests for the same link can't both create it.
+            let result = with_database(|database| -> rusqlite::Result<Result<i64, i64>> {
+                if !force {
+                    if let Some(existing_id) = database::select_entry_with_same_link(database, &form.link)? {
+                        return Ok(Err(existing_id));
+                    }
+                }
+                database::insert_entry(database, &form).map(Ok)
+            });
+
+            match result {
+                Ok(Err(existing_id)) => duplicate_entry_response(existing_id, &form.link),
+                Ok(Ok(id)) => to_json_http_response(&CreatedEntry{ id, link : format!("/api/texts/{}", id) }),
+                Err(err) => {
                     println!("Insert to database failed: {}", err);
-                    return internal_server_error_response();
+                    internal_server_error_response()
                 }
+            }
+        }
+        Err(err) => {
+            bad_request_response(&format!("{}", err))
+        }
+    }
+}
+
+// Whether the request asks to create the entry even if there already is one with the same link.
+fn is_forced(req : &Request<Body>) -> bool
+{
+    req.uri().query().is_some_and(|query| query.split('&').any(|argument| argument == "force=true"))
+}
+
+fn duplicate_entry_response(existing_id : i64, link : &str) -> Result<Response<Body>, hyper::Error>
+{
+    let body = DuplicateEntryResponse{
+        error_message : format!("Entry {} already has the link {}", existing_id, link),
+        existing_id,
+        existing_link : format!("/api/texts/{}", existing_id),
+    };
+    Response::builder()
+        .status(StatusCode::CONFLICT)
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Access-Control-Allow-Headers", "*")
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&body).unwrap()))
+        .or_else(|_| internal_server_error_response())
+}
 
-                let last_insert_row_id = database.last_insert_rowid();
+#[utoipa::path(
+    post,
+    path = "/api/texts/from_url",
+    description = "Creates an entry for the page at a url, with the title, description, author, date of publication, type and image found in the page, its oEmbed endpoint and the registry of the DOI, arXiv id or ISBN in the url or the page. A DOI, arXiv id or ISBN can be given instead of the url, to create the entry from its registry alone. The overrides fill in or replace any of them. The first time an entry of a site is created this way, the name and favicon of the site are kept too.",
+    params(("force" = Option<bool>, Query, description = "Create the entry even if there already is one with the same link")),
+    request_body = EntryFromUrlForm,
+    responses(
+        (status = 200, body = CreatedEntryFromUrl),
+        (status = 400, description = "The body is malformed, the url is neither an http or https URL nor an identifier, the page could not be fetched, the identifier could not be resolved, or some fields are invalid or neither found nor given", body = ValidationErrors),
+        (status = 409, description = "There already is an entry with the same link", body = DuplicateEntryResponse),
+        (status = 500),
+    )
+)]
+pub async fn post_text_from_url(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
+{
+    let force = is_forced(&req);
 
-                // Ignore errors when inserting an element that is already present in tables of unique values.
-                _ = run_sql(database, "INSERT INTO categories (value) VALUES (?)", [&form.category]);
+    let whole_body = hyper::body::to_bytes(req.into_body()).await?;
+    let request : EntryFromUrlForm = match serde_json::from_slice(&whole_body) {
+        Ok(request) => request,
+        Err(err) => { return bad_request_response(&format!("{}", err)); }
+    };
 
-                for author in &form.authors {
-                    _ = run_sql(database, "INSERT INTO authors (value, category) VALUES (?, ?)", [author, &form.category]);
-                }
-                for theme in &form.themes {
-                    _ = run_sql(database, "INSERT INTO themes (value, category) VALUES (?, ?)", [theme, &form.category]);
-                }
-                for work in &form.works_mentioned {
-                    _ = run_sql(database, "INSERT INTO works (value, category) VALUES (?, ?)", [work, &form.category]);
-                }
-                for tag in &form.tags {
-                    _ = run_sql(database, "INSERT INTO tags (value, category) VALUES (?, ?)", [tag, &form.category]);
-                }
+    // A DOI, arXiv id or ISBN given instead of a url is looked up in its registry, and the entry links to the page of
+    // the text there. There is no page to take the image from or back up then.
+    let (link, page, metadata) = match parse_identifier(&request.url) {
+        Some(identifier) => match fetch::suggest_entry_for_identifier(&identifier).await {
+            Ok(suggestion) => (identifier.link(), None, suggestion.metadata),
+            Err(err) => { return bad_request_response(&err); }
+        },
+        None if !validation::is_url(&request.url) => {
+            return bad_request_response(&format!("'{}' is not an http or https URL, DOI, arXiv id or ISBN", request.url));
+        }
+        None => {
+            let page = match fetch::fetch(&request.url).await {
+                Ok(page) => page,
+                Err(err) => { return bad_request_response(&err); }
+            };
+            let metadata = fetch::suggest_entry_for_page(&request.url, &page).await
+                .map(|suggestion| suggestion.metadata)
+                .unwrap_or_default();
+            (request.url.clone(), Some(page), metadata)
+        }
+    };
+
+    let FilledForm{ form, mut auto_filled } = match fill_entry_form(&link, &metadata, &request.overrides) {
+        Ok(filled) => filled,
+        Err(errors) => { return validation_error_response(&errors); }
+    };
+
+    // The image and the favicon of the site are downloaded before taking the database, so that it isn't locked while
+    // waiting for other servers.
+    let mut warnings = Vec::new();
+    let image = match &page {
+        Some(page) => match fetch::image_of_page(&link, page).await {
+            Ok(image) => {
+                auto_filled.push(String::from("image"));
+                Some(image)
+            }
+            Err(err) => {
+                warnings.push(err);
+                None
+            }
+        },
+        None => None,
+    };
+    let backup = match &page {
+        Some(page) if request.backup => Some(page),
+        None if request.backup => {
+            warnings.push(format!("{} is not a page, so there is nothing to back up", request.url));
+            None
+        }
+        _ => None,
+    };
+
+    let site = match &page {
+        Some(page) => fetch::site_to_capture(&link, page).await,
+        None => None,
+    };
 
-                Response::builder()
-                    .status(StatusCode::OK)
-                    .header("Access-Control-Allow-Origin", "*")
-                    .header("Access-Control-Allow-Headers", "*")
-                    .header("Content-Type", "application/json")
-                    .body(Body::from(format!(r#"{{"id":{},"link":"/api/texts/{}"}}"#, last_insert_row_id, last_insert_row_id)))
-                    .or_else(|_| internal_server_error_response())
-            } else {
-                internal_server_error_response()
+    let result = with_database(|database| -> rusqlite::Result<Result<i64, i64>> {
+        if !force {
+            if let Some(existing_id) = database::select_entry_with_same_link(database, &form.link)? {
+                return Ok(Err(existing_id));
             }
         }
+        database::atomically(database, || {
+            let id = database::insert_entry(database, &form)?;
+            if let Some(site) = &site {
+                sites::insert_site(database, &site.domain, site.name.as_deref(), site.favicon.as_deref())?;
+            }
+            if let Some(image) = &image {
+                database::update_image(database, id, image)?;
+            }
+            if let Some(page) = backup {
+                database::update_backup(database, id, &page.content_type, &page.content)?;
+            }
+            Ok(Ok(id))
+        })
+    });
+
+    match result {
+        Ok(Err(existing_id)) => duplicate_entry_response(existing_id, &form.link),
+        Ok(Ok(id)) => to_json_http_response(&CreatedEntryFromUrl{
+            id,
+            link : format!("/api/texts/{}", id),
+            auto_filled,
+            image : image.map(|_| format!("/api/texts/{}/image", id)),
+            backup : backup.map(|_| format!("/api/texts/{}/backup", id)),
+            warnings,
+        }),
         Err(err) => {
-            bad_request_response(&format!("{}", err))
+            println!("Insert to database failed: {}", err);
+            internal_server_error_response()
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/duplicates",
+    description = "Entries that are probably the same text archived more than once: groups of entries with the same canonical link, then pairs of entries with similar titles, most similar first.",
+    params(("min_similarity" = Option<f64>, Query, description = "Minimum similarity of titles from 0 to 1. 0.85 by default")),
+    responses(
+        (status = 200, body = Vec<DuplicateGroup>),
+        (status = 400, body = ErrorResponse),
+        (status = 500),
+    )
+)]
+pub fn get_duplicates(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
+{
+    let mut min_similarity = 0.85;
+    for argument in req.uri().query().unwrap_or_default().split('&').filter(|argument| !argument.is_empty()) {
+        match argument.split_once('=') {
+            Some(("min_similarity", value)) => match value.parse::<f64>() {
+                Ok(value) if (0.0..=1.0).contains(&value) => min_similarity = value,
+                _ => { return bad_request_response(&format!("min_similarity must be a number from 0 to 1, not '{}'", value)); }
+            },
+            _ => { return bad_request_response(&format!("Unknown query argument '{}'", argument)); }
+        }
+    }
+
+    // The titles are compared without holding the database, which other requests would be waiting for.
+    match with_database(duplicates::select_candidates) {
+        Ok(candidates) => to_json_http_response(&duplicates::find_duplicates(&candidates, min_similarity)),
+        Err(err) => {
+            println!("Duplicates query failed: {}", err);
+            internal_server_error_response()
+        }
+    }
+}
+
+// A resource downloaded for a refetch operation of a bulk request.
+enum Refetched
+{
+    Image(Vec<u8>),
+    Backup(fetch::Resource),
+}
+
+// Downloads the resources a refetch operation needs for each entry. Entries that don't exist are left out.
+async fn refetch_for_bulk(operation : &BulkOperation, ids : &[i64]) -> rusqlite::Result<HashMap<i64, Result<Refetched, String>>>
+{
+    let mut refetched = HashMap::new();
+    if !matches!(operation, BulkOperation::RefetchImage | BulkOperation::RefetchBackup) { return Ok(refetched); }
+
+    for &id in ids {
+        let link = match with_database(|database| database::select_entry(database, id))? {
+            Some(entry) => entry.link,
+            None => continue,
+        };
+
+        let resource = if let BulkOperation::RefetchImage = operation {
+            fetch::fetch_image_of_page(&link).await.map(Refetched::Image)
+        } else {
+            fetch::fetch(&link).await.map(Refetched::Backup)
+        };
+        refetched.insert(id, resource);
+    }
+
+    Ok(refetched)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/texts/bulk",
+    description = "Applies an operation to many entries at once, in a single transaction. If anything fails in the database, no entry is changed.",
+    request_body = BulkRequest,
+    responses(
+        (status = 200, description = "What happened to each entry", body = BulkResponse),
+        (status = 400, description = "The request is malformed or the values of the operation are invalid", body = ValidationErrors),
+        (status = 500),
+    )
+)]
+pub async fn post_bulk(req : Request<Body>) -> Result<Response<Body>, hyper::Error>
+{
+    let whole_body = hyper::body::to_bytes(req.into_body()).await?;
+    let mut request : BulkRequest = match serde_json::from_slice(&whole_body) {
+        Ok(request) => request,
+        Err(err) => { return bad_request_response(&format!("{}", err)); }
+    };
+    if let Err(errors) = bulk::normalize_operation(&mut request.operation) {
+        return validation_error_response(&errors);
+    }
+
+    let ids = match (request.ids, &request.query) {
+        (Some(ids), None) => ids,
+        (None, Some(query_text)) => match url_to_sql_query(query_text) {
+            Some(query) => match with_database(|database| bulk::select_ids(database, &query)) {
+                Ok(ids) => ids,
+                Err(err) => {
+                    println!("SQL query error: {}", err);
+                    return internal_server_error_response();
+                }
+            },
+            None => { return bad_request_response(&format!("Could not parse search query '{}'", query_text)); }
+        },
+        _ => { return bad_request_response("Either ids or query must be given, but not both"); }
+    };
+
+    // Resources are downloaded before the transaction starts, so that the database isn't locked while waiting for
+    // other servers.
+    let mut refetched = match refetch_for_bulk(&request.operation, &ids).await {
+        Ok(refetched) => refetched,
+        Err(err) => {
+            println!("Bulk refetch failed: {}", err);
+            return internal_server_error_response();
+        }
+    };
+
+    let result = with_database(|database| -> rusqlite::Result<Vec<BulkEntryResult>> {
+        let transaction = database.unchecked_transaction()?;
+
+        let mut results = Vec::new();
+        for &id in &ids {
+            let (status, error) = match &request.operation {
+                BulkOperation::RefetchImage | BulkOperation::RefetchBackup => match refetched.remove(&id) {
+                    None => (BulkStatus::NotFound, None),
+                    Some(Err(err)) => (BulkStatus::Failed, Some(err)),
+                    Some(Ok(resource)) => {
+                        let modified = match resource {
+                            Refetched::Image(image) => database::update_image(&transaction, id, &image)?,
+                            Refetched::Backup(backup) => database::update_backup(&transaction, id, &backup.content_type, &backup.content)?,
+                        };
+                        (if modified == 0 { BulkStatus::NotFound } else { BulkStatus::Updated }, None)
+                    }
+                },
+                operation => match bulk::apply_operation(&transaction, id, operation)? {
+                    Ok(status) => (status, None),
+                    Err(errors) => (BulkStatus::Failed, Some(errors.error_message)),
+                },
+            };
+            results.push(BulkEntryResult{ id, status, error });
+        }
+
+        transaction.commit()?;
+        Ok(results)
+    });
+
+    match result {
+        Ok(results) => to_json_http_response(&BulkResponse{ results }),
+        Err(err) => {
+            println!("Bulk operation failed: {}", err);
+            internal_server_error_response()
         }
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/openapi.json",
+    description = "This document.",
+    responses((status = 200, content_type = "application/json"))
+)]
+pub fn get_openapi() -> Result<Response<Body>, hyper::Error>
+{
+    to_json_http_response(&ApiDoc::openapi())
+}
+
 pub fn serve_page(path : &str) -> Result<Response<Body>, hyper::Error>
 {
     serve_file(path, "text/html; charset=utf-8", false)
@@ -731,9 +1363,23 @@ pub fn serve_file(path : &str, content_type : &str, cache : bool) -> Result<Resp
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/forward/{url}",
+    description = "Get the resource at a url on behalf of the client, which may not be able to because of CORS.",
+    params(("url" = String, Path, description = "Url of the resource")),
+    responses(
+        (status = 200, description = "The response of the remote server"),
+        (status = 400, description = "The url is not valid", body = ErrorResponse),
+    )
+)]
 pub async fn forward_get_request(url : &str) -> Result<Response<Body>, hyper::Error>
 {
-    let mut response = http::get(url).await?;
+    let mut response = match http::get(url).await {
+        Ok(response) => response,
+        Err(http::GetError::InvalidUrl(err)) => { return bad_request_response(&format!("Invalid url {}: {}", url, err)); }
+        Err(http::GetError::Request(err)) => { return Err(err); }
+    };
 
     response.headers_mut().insert("Access-Control-Allow-Origin", hyper::header::HeaderValue::from_static("*"));
     response.headers_mut().insert("Access-Control-Allow-Headers", hyper::header::HeaderValue::from_static("*"));
@@ -741,16 +1387,31 @@ pub async fn forward_get_request(url : &str) -> Result<Response<Body>, hyper::Er
     Ok(response)
 }
 
+// The url that follows the prefix in the path of the request, with the query of the request, which belongs to it.
+fn url_in_path(request_uri : &hyper::Uri, prefix : &str) -> String
+{
+    let mut url = String::from(request_uri.path().strip_prefix(prefix).unwrap());
+    if let Some(query) = request_uri.query() {
+        url += "?";
+        url += query;
+    }
+    url
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/meta_headers/{url}",
+    description = "Get the name and content of every meta tag of the html page at a url.",
+    params(("url" = String, Path, description = "Url of the page")),
+    responses(
+        (status = 200, body = Vec<(String, String)>),
+        (status = 404, description = "The page could not be fetched or is neither html nor a PDF"),
+        (status = 500),
+    )
+)]
 pub async fn get_meta_headers_at_url(request_uri : &hyper::Uri) -> Result<Response<Body>, hyper::Error>
 {
-    let url = {
-        let mut url = String::from(request_uri.path().strip_prefix("/api/meta_headers/").unwrap());
-        if let Some(query) = request_uri.query() {
-            url += "?";
-            url += query;
-        }
-        url
-    };
+    let url = url_in_path(request_uri, "/api/meta_headers/");
 
     let response = match http::get(&url).await {
         Ok(r) => r,
@@ -768,15 +1429,14 @@ pub async fn get_meta_headers_at_url(request_uri : &hyper::Uri) -> Result<Respon
 
     println!("{}", content_type_header.map(|x| x.to_str().unwrap()).unwrap_or("No content type"));
 
-    if !content_type_header.map_or(false, |h| h.to_str().unwrap().starts_with("text/html")) {
-        return not_found_404_response();
-    }
+    let content_type = match content_type_header.and_then(|h| h.to_str().ok()) {
+        Some(content_type) if content_type.starts_with("text/html") => String::from(content_type),
+        _ => { return not_found_404_response(); }
+    };
 
+    // Pages in other charsets than UTF-8 are transcoded, so that their meta tags can be read.
     let whole_body = hyper::body::to_bytes(response.into_body()).await?;
-    let whole_text = match String::from_utf8(whole_body.to_vec()) {
-        Ok(x) => x,
-        Err(_) => return internal_server_error_response()
-    };
+    let whole_text = decode_text(&content_type, &whole_body);
 
     let headers = html_meta_headers(&whole_text);
 
@@ -793,129 +1453,34 @@ pub async fn get_meta_headers_at_url(request_uri : &hyper::Uri) -> Result<Respon
     }
 }
 
-#[derive(Serialize)]
-struct GetTextsResponse
-{
-    entries : Vec<Entry>,
-    current_offset : usize,
-    next_offset : usize,
-    total_size : usize,
-    seed : u64
-}
-
-fn read_entry_from_database_row(row : &rusqlite::Row<'_>) -> rusqlite::Result<Entry>
+#[utoipa::path(
+    get,
+    path = "/api/page_metadata/{url}",
+    description = "Get an entry for the html page or PDF at a url, merged from its JSON-LD, Highwire, OpenGraph, Dublin Core, Twitter card, plain html and oEmbed metadata, or the document information and pages of the PDF, and the registry of its DOI, arXiv id or ISBN, with the source of each field and how likely it is to be right.",
+    params(("url" = String, Path, description = "Url of the page, or a DOI, arXiv id or ISBN to look up in its registry")),
+    responses(
+        (status = 200, body = SuggestedEntry),
+        (status = 404, description = "The page could not be fetched or is not html, or the identifier could not be resolved"),
+        (status = 500),
+    )
+)]
+pub async fn get_page_metadata_at_url(request_uri : &hyper::Uri) -> Result<Response<Body>, hyper::Error>
 {
-    let id : i64 = row.get(0)?;
-    let entry_type_index : i32 = row.get(12)?;
-    let entry_type_metadata : i32 = row.get(13)?;
-
-    Ok(Entry{
-        id,
-        link : row.get(1)?,
-        title : row.get(2)?,
-        description : row.get(3)?,
-        authors : read_from_sql_array(&row.get::<_, String>(4)?),
-        category : row.get(5)?,
-        themes : read_from_sql_array(&row.get::<_, String>(6)?),
-        works_mentioned : read_from_sql_array(&row.get::<_, String>(7)?),
-        tags : read_from_sql_array(&row.get::<_, String>(8)?),
-        date_published : date::read_sql_date(&row.get::<_, String>(9)?).unwrap(),
-        date_saved : date::read_sql_date(&row.get::<_, String>(10)?).unwrap(),
-        exceptional : row.get(11)?,
-        entry_type : entry_type::from_index_and_metadata(entry_type_index, entry_type_metadata),
-        image : if row.get_ref(14)?.as_blob_or_null()?.is_some() { Some(format!("/api/texts/{}/image", id)) } else { None },
-        backup : if row.get_ref(15)?.as_blob_or_null()?.is_some() { Some(format!("/api/texts/{}/backup", id)) } else { None },
-    })
-}
-
-fn get_number_of_texts_in_database(database : &rusqlite::Connection) -> rusqlite::Result<usize> {
-    let sql_query ="SELECT count(*) FROM entries";
-    let mut statement = database.prepare(sql_query)?;
-    let mut rows = statement.query([])?;
-
-    if let Some(row) = rows.next()? {
-        row.get(0)
-    } else {
-        Ok(0)
-    }
-}
-
-fn select_random_texts(database : &rusqlite::Connection, offset : usize, seed : u64) -> rusqlite::Result<GetTextsResponse> {
-    let total_size : usize = get_number_of_texts_in_database(database)?;
-
-    let mut rng = SmallRng::seed_from_u64(seed);
-
-    let mut indices : Vec<usize> = (0..total_size).collect();
-    indices.shuffle(&mut rng);
-
-    let indices_to_include = &indices[offset..std::cmp::min(offset + 10, indices.len())];
-
-    let sql_query = "SELECT * FROM entries";
-    let mut statement = database.prepare(sql_query)?;
-    let mut rows = statement.query([])?;
-
-    let mut found_entries : Vec<Entry> = Vec::new();
-
-    let mut i : usize = 0;
-    while let Some(row) = rows.next()? {
-        if indices_to_include.contains(&i) {
-            found_entries.push(read_entry_from_database_row(row)?);
-        }
-
-        i += 1;
-    }
-
-    Ok(GetTextsResponse{
-        next_offset : offset + found_entries.len(),
-        entries : found_entries,
-        current_offset : offset,
-        total_size,
-        seed
-    })
-}
+    let url = url_in_path(request_uri, "/api/page_metadata/");
 
-fn select_texts(database : &rusqlite::Connection, query : &SqlQuery) -> rusqlite::Result<GetTextsResponse> {
-    let seed = query.seed.unwrap_or_else(|| rand::random::<i32>().unsigned_abs() as u64);
-
-    if query.where_query.is_empty() {
-        return select_random_texts(database, query.offset, seed);
-    }
-    
-    let mut found_entries : Vec<Entry> = Vec::new();
-
-    let sql_query = format!("SELECT *, count(*) OVER() AS full_count FROM entries WHERE {} LIMIT 10 OFFSET {}", query.where_query, query.offset);
-    let params = query.params.iter().map(|x| x as &dyn rusqlite::ToSql).collect::<Vec<&dyn rusqlite::ToSql>>();
-
-    println!("SQL query: {}", sql_query);
-
-    let mut statement = database.prepare(&sql_query)?;
-    let mut rows = statement.query(params.as_slice())?;
-    let mut total_size = 0;
-
-    // Read the size from the first row. We don't need to read it from subsequent rows because it is always the same.
-    if let Some(row) = rows.next()? {
-        found_entries.push(read_entry_from_database_row(row)?);
-        total_size = row.get(16)?;
-    }
-
-    while let Some(row) = rows.next()? {
-        found_entries.push(read_entry_from_database_row(row)?);
+    if let Some(identifier) = parse_identifier(&url) {
+        return match fetch::suggest_entry_for_identifier(&identifier).await {
+            Ok(suggestion) => to_json_http_response(&suggestion),
+            Err(_) => not_found_404_response(),
+        };
     }
 
-    Ok(GetTextsResponse{
-        next_offset : query.offset + found_entries.len(),
-        entries : found_entries,
-        current_offset : query.offset,
-        total_size,
-        seed
-    })
-}
-
-fn run_sql<Params : rusqlite::Params>(database : &rusqlite::Connection, command : &str, params : Params) -> rusqlite::Result<usize>
-{
-    let result = database.execute(command, params);
-    if let Err(err) = &result {
-        println!("SQL error: {}", err);
+    let page = match fetch::fetch(&url).await {
+        Ok(page) => page,
+        Err(_) => { return not_found_404_response(); }
+    };
+    match fetch::suggest_entry_for_page(&url, &page).await {
+        Some(suggestion) => to_json_http_response(&suggestion),
+        None => not_found_404_response(),
     }
-    result
 }
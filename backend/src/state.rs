@@ -1,13 +1,19 @@
-use serde::Serialize;
+use archive::resolvers::ResolverUrls;
+
 use std::sync::Mutex;
 
 pub struct State {
     pub database: Option<rusqlite::Connection>,
+    // Those of the config file. The public registries if there is none.
+    pub resolvers: Option<ResolverUrls>,
 }
 
 impl State {
     const fn new() -> State {
-        State { database: None }
+        State {
+            database: None,
+            resolvers: None,
+        }
     }
 }
 
@@ -17,49 +23,13 @@ pub fn global_state() -> &'static Mutex<State> {
     &STATE
 }
 
-pub fn get_all_strings_of(table: &str) -> rusqlite::Result<Vec<String>> {
-    let mut entries: Vec<String> = Vec::new();
-
-    {
-        let state = STATE.lock().unwrap();
-        let mut statement = state
-            .database
-            .as_ref()
-            .unwrap()
-            .prepare(&format!("SELECT * from {}", table))?;
-        let mut rows = statement.query([])?;
-        while let Some(row) = rows.next()? {
-            entries.push(row.get(1)?)
-        }
-    }
-
-    Ok(entries)
-}
-
-#[derive(Serialize, Debug, Clone)]
-pub struct StringWithCategory {
-    value: String,
-    category: String,
+// Runs the function with the database of the global state, which stays locked until the function returns.
+pub fn with_database<T>(f: impl FnOnce(&rusqlite::Connection) -> T) -> T {
+    let state = STATE.lock().unwrap();
+    f(state.database.as_ref().unwrap())
 }
 
-pub fn get_all_strings_by_category_of(table: &str) -> rusqlite::Result<Vec<StringWithCategory>> {
-    let mut entries: Vec<StringWithCategory> = Vec::new();
-
-    {
-        let state = STATE.lock().unwrap();
-        let mut statement = state
-            .database
-            .as_ref()
-            .unwrap()
-            .prepare(&format!("SELECT * from {}", table))?;
-        let mut rows = statement.query([])?;
-        while let Some(row) = rows.next()? {
-            entries.push(StringWithCategory {
-                value: row.get(1)?,
-                category: row.get(2)?,
-            })
-        }
-    }
-
-    Ok(entries)
+// Where the identifiers of papers and books are looked up.
+pub fn resolver_urls() -> ResolverUrls {
+    STATE.lock().unwrap().resolvers.clone().unwrap_or_default()
 }
@@ -0,0 +1,338 @@
+use super::stub_server::{StubResponse, StubServer};
+use super::*;
+
+// Bytes that are easy to tell apart at any offset.
+fn backup_content(length: usize) -> Vec<u8> {
+    (0..length).map(|i| (i % 251) as u8).collect()
+}
+
+async fn create_entry_with_backup(content: &[u8]) -> (i64, String) {
+    let id = create_entry("Bonnie and Clyde").await;
+    let response = put_backup(id, "application/pdf", content.to_vec()).await;
+    assert_eq!(response.status, StatusCode::OK);
+    (id, format!("/api/texts/{}/backup", id))
+}
+
+async fn get_range(path: &str, headers: &[(&str, &str)]) -> TestResponse {
+    request_with_headers(Method::GET, path, headers, "").await
+}
+
+#[tokio::test]
+async fn put_entry_backup_in_body_is_returned_whole_with_its_content_type() {
+    let _database = TestDatabase::new().await;
+    // Bigger than one streaming chunk.
+    let content = backup_content(200_000);
+    let (id, path) = create_entry_with_backup(&content).await;
+
+    let response = get(&path).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("Content-Type"), Some("application/pdf"));
+    assert_eq!(response.header("Content-Length"), Some("200000"));
+    assert_eq!(response.header("Accept-Ranges"), Some("bytes"));
+    assert!(response.header("ETag").is_some());
+    assert_eq!(response.body, content);
+
+    let entry = get(&format!("/api/texts/{}", id)).await.json();
+    assert_eq!(entry["backup"], path);
+}
+
+#[tokio::test]
+async fn put_entry_backup_from_link() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route(
+        "/paper.pdf",
+        StubResponse::ok("application/pdf", backup_content(1000)),
+    );
+
+    let id = create_entry("Bonnie and Clyde").await;
+    let response = put_json(
+        &format!("/api/texts/{}/backup", id),
+        serde_json::json!({ "backup_url": stub.url("/paper.pdf") }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let backup = get(&format!("/api/texts/{}/backup", id)).await;
+    assert_eq!(backup.header("Content-Type"), Some("application/pdf"));
+    assert_eq!(backup.body, backup_content(1000));
+}
+
+#[tokio::test]
+async fn put_entry_backup_from_broken_link_is_bad_request() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+
+    let id = create_entry("Bonnie and Clyde").await;
+    let response = put_json(
+        &format!("/api/texts/{}/backup", id),
+        serde_json::json!({ "backup_url": stub.url("/missing.pdf") }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn put_entry_backup_from_malformed_link_is_bad_request() {
+    let _database = TestDatabase::new().await;
+
+    let id = create_entry("Bonnie and Clyde").await;
+    for link in ["example.com/paper.pdf", "not a url", "https://example.com/a b"] {
+        let response = put_json(
+            &format!("/api/texts/{}/backup", id),
+            serde_json::json!({ "backup_url": link }),
+        )
+        .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", link);
+    }
+}
+
+#[tokio::test]
+async fn range_with_first_and_last_is_partial_content() {
+    let _database = TestDatabase::new().await;
+    let content = backup_content(200_000);
+    let (_, path) = create_entry_with_backup(&content).await;
+
+    let response = get_range(&path, &[("Range", "bytes=100-199")]).await;
+    assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        response.header("Content-Range"),
+        Some("bytes 100-199/200000")
+    );
+    assert_eq!(response.header("Content-Length"), Some("100"));
+    assert_eq!(response.header("Content-Type"), Some("application/pdf"));
+    assert_eq!(response.body, content[100..200]);
+}
+
+#[tokio::test]
+async fn range_open_ended_and_suffix() {
+    let _database = TestDatabase::new().await;
+    let content = backup_content(200_000);
+    let (_, path) = create_entry_with_backup(&content).await;
+
+    let response = get_range(&path, &[("Range", "bytes=70000-")]).await;
+    assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        response.header("Content-Range"),
+        Some("bytes 70000-199999/200000")
+    );
+    assert_eq!(response.body, content[70000..]);
+
+    let response = get_range(&path, &[("Range", "bytes=-10")]).await;
+    assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        response.header("Content-Range"),
+        Some("bytes 199990-199999/200000")
+    );
+    assert_eq!(response.body, content[199990..]);
+}
+
+#[tokio::test]
+async fn range_past_the_end_is_not_satisfiable() {
+    let _database = TestDatabase::new().await;
+    let (_, path) = create_entry_with_backup(&backup_content(1000)).await;
+
+    let response = get_range(&path, &[("Range", "bytes=1000-")]).await;
+    assert_eq!(response.status, StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(response.header("Content-Range"), Some("bytes */1000"));
+    assert!(response.body.is_empty());
+}
+
+#[tokio::test]
+async fn multiple_ranges_serve_the_whole_backup() {
+    let _database = TestDatabase::new().await;
+    let content = backup_content(1000);
+    let (_, path) = create_entry_with_backup(&content).await;
+
+    let response = get_range(&path, &[("Range", "bytes=0-9,20-29")]).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body, content);
+}
+
+#[tokio::test]
+async fn if_range_with_current_etag_honors_range() {
+    let _database = TestDatabase::new().await;
+    let content = backup_content(1000);
+    let (_, path) = create_entry_with_backup(&content).await;
+    let etag = String::from(get(&path).await.header("ETag").unwrap());
+
+    let response = get_range(&path, &[("Range", "bytes=0-9"), ("If-Range", &etag)]).await;
+    assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.body, content[0..10]);
+}
+
+#[tokio::test]
+async fn if_range_with_outdated_etag_serves_everything() {
+    let _database = TestDatabase::new().await;
+    let (id, path) = create_entry_with_backup(&backup_content(1000)).await;
+    let old_etag = String::from(get(&path).await.header("ETag").unwrap());
+
+    let new_content = backup_content(2000);
+    put_backup(id, "application/pdf", new_content.clone()).await;
+    let new_etag = String::from(get(&path).await.header("ETag").unwrap());
+    assert_ne!(old_etag, new_etag);
+
+    let response = get_range(&path, &[("Range", "bytes=0-9"), ("If-Range", &old_etag)]).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body, new_content);
+}
+
+#[tokio::test]
+async fn delete_entry_backup_removes_it() {
+    let _database = TestDatabase::new().await;
+    let (id, path) = create_entry_with_backup(&backup_content(1000)).await;
+
+    assert_eq!(delete(&path).await.status, StatusCode::NO_CONTENT);
+    assert_eq!(get(&path).await.status, StatusCode::NOT_FOUND);
+    assert!(get(&format!("/api/texts/{}", id)).await.json()["backup"].is_null());
+}
+
+#[tokio::test]
+async fn put_entry_backup_fills_the_empty_fields_of_the_entry_from_a_pdf() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry_with(
+        "The Frontier of Movies",
+        serde_json::json!({ "authors": [], "entry_type": { "Paper": { "pages": 0 } } }),
+    )
+    .await;
+
+    let response = put_backup(id, "application/pdf", PDF_PAPER).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.json(),
+        serde_json::json!({
+            "link": format!("/api/texts/{}/backup", id),
+            "auto_filled": ["authors", "entry_type"]
+        })
+    );
+
+    let entry = get(&format!("/api/texts/{}", id)).await.json();
+    assert_eq!(entry["title"], "The Frontier of Movies");
+    assert_eq!(
+        entry["authors"],
+        serde_json::json!(["Pauline Kael", "Arthur Penn"])
+    );
+    assert_eq!(
+        entry["entry_type"],
+        serde_json::json!({ "Paper": { "pages": 3 } })
+    );
+    let history = get(&format!("/api/texts/{}/history", id)).await.json();
+    assert_eq!(history.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn put_entry_backup_keeps_the_fields_that_are_filled() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry_with(
+        "The Frontier of Movies",
+        serde_json::json!({ "entry_type": { "Paper": { "pages": 20 } } }),
+    )
+    .await;
+
+    let response = put_backup(id, "application/octet-stream", PDF_PAPER).await;
+    assert_eq!(response.json()["auto_filled"], serde_json::json!([]));
+
+    let entry = get(&format!("/api/texts/{}", id)).await.json();
+    assert_eq!(entry["authors"], serde_json::json!(["Pauline Kael"]));
+    assert_eq!(
+        entry["entry_type"],
+        serde_json::json!({ "Paper": { "pages": 20 } })
+    );
+}
+
+#[tokio::test]
+async fn backup_metadata_of_a_pdf() {
+    let _database = TestDatabase::new().await;
+    let (id, _) = create_entry_with_backup(PDF_PAPER).await;
+
+    let response = get(&format!("/api/texts/{}/backup/metadata", id)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let metadata = response.json();
+    assert_eq!(metadata["content_type"], "application/pdf");
+    assert_eq!(metadata["pdf"]["page_count"], 3);
+    assert_eq!(
+        metadata["pdf"]["creation_date"],
+        serde_json::json!({ "day": 15, "month": "January", "year": 1968 })
+    );
+    assert!(metadata["pdf"]["first_page_text"]
+        .as_str()
+        .unwrap()
+        .starts_with("The Frontier of Movies\nPauline Kael\n"));
+    assert_eq!(
+        metadata["suggestion"]["title"],
+        "The Frontier of Movies: Bonnie and Clyde"
+    );
+    assert_eq!(
+        metadata["suggestion"]["provenance"]["entry_type"],
+        serde_json::json!({ "source": "pdf", "confidence": 0.7 })
+    );
+}
+
+#[tokio::test]
+async fn backup_metadata_of_other_backups_has_no_suggestion() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    assert_eq!(
+        get(&format!("/api/texts/{}/backup/metadata", id))
+            .await
+            .status,
+        StatusCode::NOT_FOUND
+    );
+
+    put_backup(id, "text/plain", "Hello").await;
+    let metadata = get(&format!("/api/texts/{}/backup/metadata", id))
+        .await
+        .json();
+    assert_eq!(
+        metadata,
+        serde_json::json!({
+            "content_type": "text/plain",
+            "suggestion": null,
+            "pdf": null,
+            "media": null
+        })
+    );
+}
+
+#[tokio::test]
+async fn put_entry_backup_fills_the_length_of_a_song() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry_with(
+        "Foggy Mountain Breakdown",
+        serde_json::json!({ "entry_type": { "Audio": { "length_in_seconds": 0 } } }),
+    )
+    .await;
+
+    let response = put_backup(id, "audio/mpeg", MP3_SONG).await;
+    assert_eq!(
+        response.json()["auto_filled"],
+        serde_json::json!(["entry_type"])
+    );
+
+    let entry = get(&format!("/api/texts/{}", id)).await.json();
+    assert_eq!(
+        entry["entry_type"],
+        serde_json::json!({ "Audio": { "length_in_seconds": 165 } })
+    );
+    // The artist is only suggested, as the entry already has authors.
+    assert_eq!(entry["authors"], serde_json::json!(["Pauline Kael"]));
+
+    let metadata = get(&format!("/api/texts/{}/backup/metadata", id))
+        .await
+        .json();
+    assert_eq!(
+        metadata["media"],
+        serde_json::json!({
+            "container": "mp3",
+            "has_video": false,
+            "length_in_seconds": 165,
+            "title": "Foggy Mountain Breakdown",
+            "artist": "Flatt & Scruggs"
+        })
+    );
+    assert_eq!(
+        metadata["suggestion"]["authors"],
+        serde_json::json!(["Flatt & Scruggs"])
+    );
+    assert!(metadata["pdf"].is_null());
+}
@@ -0,0 +1,188 @@
+use super::stub_server::{StubResponse, StubServer};
+use super::*;
+
+async fn bulk(request: serde_json::Value) -> TestResponse {
+    post_json("/api/texts/bulk", request).await
+}
+
+#[tokio::test]
+async fn bulk_operation_on_ids_reports_every_entry() {
+    let _database = TestDatabase::new().await;
+    let tagged = create_entry_with(
+        "Bonnie and Clyde",
+        serde_json::json!({ "tags": ["Classic"] }),
+    )
+    .await;
+    let untagged = create_entry("The Godfather").await;
+
+    let response = bulk(serde_json::json!({
+        "ids": [tagged, untagged, 1000],
+        "operation": { "add_tags": ["Classic"] }
+    }))
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.json(),
+        serde_json::json!({ "results": [
+            { "id": tagged, "status": "unchanged" },
+            { "id": untagged, "status": "updated" },
+            { "id": 1000, "status": "not_found" },
+        ]})
+    );
+    assert_eq!(
+        get(&format!("/api/texts/{}", untagged)).await.json()["tags"],
+        serde_json::json!(["Classic"])
+    );
+}
+
+#[tokio::test]
+async fn bulk_operation_on_query_applies_to_every_result() {
+    let _database = TestDatabase::new().await;
+    for i in 0..12 {
+        create_entry(&format!("Review {}", i)).await;
+    }
+    let other = create_entry_with(
+        "Dark Souls",
+        serde_json::json!({ "category": "Videogames" }),
+    )
+    .await;
+
+    let response = bulk(serde_json::json!({
+        "query": "category=Cinema",
+        "operation": { "set_exceptional": true }
+    }))
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["results"].as_array().unwrap().len(), 12);
+
+    let exceptional = get("/api/texts?exceptional=true").await.json();
+    assert_eq!(exceptional["total_size"], 12);
+    assert_eq!(
+        get(&format!("/api/texts/{}", other)).await.json()["exceptional"],
+        false
+    );
+}
+
+#[tokio::test]
+async fn bulk_edits_of_lists_and_category() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry_with(
+        "Bonnie and Clyde",
+        serde_json::json!({ "themes": ["Criticism", "New Hollywood"] }),
+    )
+    .await;
+    let path = format!("/api/texts/{}", id);
+
+    bulk(serde_json::json!({ "ids": [id], "operation": { "remove_themes": ["Criticism"] } })).await;
+    bulk(serde_json::json!({ "ids": [id], "operation": { "add_themes": ["Violence"] } })).await;
+    bulk(serde_json::json!({ "ids": [id], "operation": { "set_category": "Film" } })).await;
+
+    let entry = get(&path).await.json();
+    assert_eq!(
+        entry["themes"],
+        serde_json::json!(["New Hollywood", "Violence"])
+    );
+    assert_eq!(entry["category"], "Film");
+}
+
+#[tokio::test]
+async fn bulk_delete_moves_entries_to_the_trash() {
+    let _database = TestDatabase::new().await;
+    let first = create_entry("Bonnie and Clyde").await;
+    let second = create_entry("The Godfather").await;
+
+    let response = bulk(serde_json::json!({ "ids": [first, second], "operation": "delete" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    assert_eq!(get("/api/trash").await.json().as_array().unwrap().len(), 2);
+    assert_eq!(get("/api/texts").await.json()["total_size"], 0);
+}
+
+#[tokio::test]
+async fn bulk_refetch_of_backups_and_images() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route(
+        "/article",
+        StubResponse::html(
+            r#"<html><head><meta property="og:image" content="/cover.png"></head></html>"#,
+        ),
+    );
+    stub.route(
+        "/cover.png",
+        StubResponse::ok("image/png", png_image(600, 338)),
+    );
+
+    let id = create_entry_with(
+        "Bonnie and Clyde",
+        serde_json::json!({ "link": stub.url("/article") }),
+    )
+    .await;
+    let broken = create_entry_with(
+        "The Godfather",
+        serde_json::json!({ "link": stub.url("/missing") }),
+    )
+    .await;
+
+    let response =
+        bulk(serde_json::json!({ "ids": [id, broken], "operation": "refetch_backup" })).await;
+    let results = response.json()["results"].clone();
+    assert_eq!(results[0]["status"], "updated");
+    assert_eq!(results[1]["status"], "failed");
+    assert!(results[1]["error"].is_string());
+    let backup = get(&format!("/api/texts/{}/backup", id)).await;
+    assert_eq!(
+        backup.header("Content-Type"),
+        Some("text/html; charset=utf-8")
+    );
+
+    let response = bulk(serde_json::json!({ "ids": [id], "operation": "refetch_image" })).await;
+    assert_eq!(response.json()["results"][0]["status"], "updated");
+    assert_eq!(stub.hits("/cover.png"), 1);
+    let image = get(&format!("/api/texts/{}/image", id)).await;
+    assert_eq!(image.status, StatusCode::OK);
+    assert_eq!(image.header("Content-Type"), Some("image/png"));
+}
+
+#[tokio::test]
+async fn bulk_operation_is_all_or_nothing() {
+    let database = TestDatabase::new().await;
+    let first = create_entry("Bonnie and Clyde").await;
+    let second = create_entry("The Godfather").await;
+    database.execute(&format!(
+        "CREATE TRIGGER fail_update BEFORE UPDATE ON entries WHEN NEW.entry_id = {} BEGIN SELECT RAISE(ABORT, 'boom'); END;",
+        second
+    ));
+
+    let response = bulk(serde_json::json!({
+        "ids": [first, second],
+        "operation": { "set_exceptional": true }
+    }))
+    .await;
+    assert_eq!(response.status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(
+        get(&format!("/api/texts/{}", first)).await.json()["exceptional"],
+        false
+    );
+}
+
+#[tokio::test]
+async fn bulk_request_must_select_entries_one_way() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+
+    for invalid in [
+        serde_json::json!({ "operation": "delete" }),
+        serde_json::json!({ "ids": [id], "query": "category=Cinema", "operation": "delete" }),
+        serde_json::json!({ "query": "rating=5", "operation": "delete" }),
+        serde_json::json!({ "ids": [id], "operation": "explode" }),
+    ] {
+        let response = bulk(invalid.clone()).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", invalid);
+    }
+
+    assert_eq!(
+        get(&format!("/api/texts/{}", id)).await.status,
+        StatusCode::OK
+    );
+}
@@ -0,0 +1,121 @@
+use super::*;
+
+use serde_json::json;
+
+#[tokio::test]
+async fn entry_with_the_same_link_is_a_conflict() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry_with(
+        "Bonnie and Clyde",
+        json!({ "link": "https://www.example.com/bonnie-and-clyde/" }),
+    )
+    .await;
+
+    for link in [
+        "https://www.example.com/bonnie-and-clyde/",
+        "http://example.com/bonnie-and-clyde",
+        "https://EXAMPLE.com/bonnie-and-clyde?utm_source=feed&utm_medium=rss",
+        "https://example.com/bonnie-and-clyde#comments",
+    ] {
+        let response = post_json(
+            "/api/texts",
+            entry_form_with("Bonnie and Clyde", json!({ "link": link })),
+        )
+        .await;
+        assert_eq!(response.status, StatusCode::CONFLICT, "{}", link);
+        assert_eq!(response.json()["existing_id"], id);
+        assert_eq!(
+            response.json()["existing_link"],
+            format!("/api/texts/{}", id)
+        );
+    }
+
+    assert_eq!(get("/api/texts").await.json()["total_size"], 1);
+}
+
+#[tokio::test]
+async fn different_links_are_not_a_conflict() {
+    let _database = TestDatabase::new().await;
+    create_entry_with(
+        "Bonnie and Clyde",
+        json!({ "link": "https://example.com/review?id=1" }),
+    )
+    .await;
+
+    let response = post_json(
+        "/api/texts",
+        entry_form_with(
+            "Bonnie and Clyde",
+            json!({ "link": "https://example.com/review?id=2" }),
+        ),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn forced_entry_with_the_same_link_is_created() {
+    let _database = TestDatabase::new().await;
+    create_entry("Bonnie and Clyde").await;
+
+    let response = post_json("/api/texts?force=true", new_entry_form("Bonnie and Clyde")).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(get("/api/texts").await.json()["total_size"], 2);
+}
+
+#[tokio::test]
+async fn entry_in_the_trash_is_not_a_conflict() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    delete(&format!("/api/texts/{}", id)).await;
+
+    let response = post_json("/api/texts", new_entry_form("Bonnie and Clyde")).await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn duplicates_are_grouped_by_link_then_title() {
+    let _database = TestDatabase::new().await;
+    let first = create_entry("Bonnie and Clyde").await;
+    let second = post_json(
+        "/api/texts?force=true",
+        entry_form_with(
+            "Bonnie and Clyde (reprint)",
+            json!({ "link": "https://www.example.com/bonnie-and-clyde/?utm_source=feed" }),
+        ),
+    )
+    .await
+    .json()["id"]
+        .as_i64()
+        .unwrap();
+    let third = create_entry_with(
+        "Bonnie & Clyde - The frontier of movies",
+        json!({ "link": "https://example.org/frontier" }),
+    )
+    .await;
+    let fourth = create_entry_with(
+        "Bonnie and Clyde: the frontier of movies",
+        json!({ "link": "https://example.net/frontier" }),
+    )
+    .await;
+    create_entry("The Godfather").await;
+
+    let response = get("/api/duplicates").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let groups = response.json();
+    let ids = |group: &serde_json::Value| {
+        group["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["id"].as_i64().unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(groups.as_array().unwrap().len(), 2);
+    assert_eq!(groups[0]["reason"], "link");
+    assert_eq!(groups[0]["similarity"], 1.0);
+    assert_eq!(ids(&groups[0]), [first, second]);
+    assert_eq!(groups[1]["reason"], "title");
+    assert_eq!(ids(&groups[1]), [third, fourth]);
+}
@@ -0,0 +1,290 @@
+use super::*;
+
+#[tokio::test]
+async fn post_texts_returns_id_and_link() {
+    let _database = TestDatabase::new().await;
+    let response = post_json("/api/texts", new_entry_form("Bonnie and Clyde")).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let json = response.json();
+    let id = json["id"].as_i64().unwrap();
+    assert_eq!(json["link"], format!("/api/texts/{}", id));
+}
+
+#[tokio::test]
+async fn post_texts_with_malformed_body_is_bad_request() {
+    let _database = TestDatabase::new().await;
+    let response = request(Method::POST, "/api/texts", "{ not json").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn created_entry_can_be_read_back() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry_with(
+        "Bonnie and Clyde",
+        serde_json::json!({
+            "description": "A review",
+            "authors": ["Pauline Kael", "Roger Ebert"],
+            "themes": ["Criticism", "New Hollywood"],
+            "works_mentioned": ["Bonnie and Clyde"],
+            "tags": ["Classic"],
+            "exceptional": true,
+            "entry_type": { "Video": { "length_in_seconds": 600 } }
+        }),
+    )
+    .await;
+
+    let response = get(&format!("/api/texts/{}", id)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("Content-Type"), Some("application/json"));
+
+    let entry = response.json();
+    assert_eq!(entry["id"], id);
+    assert_eq!(entry["link"], "https://example.com/bonnie-and-clyde");
+    assert_eq!(entry["title"], "Bonnie and Clyde");
+    assert_eq!(entry["description"], "A review");
+    assert_eq!(
+        entry["authors"],
+        serde_json::json!(["Pauline Kael", "Roger Ebert"])
+    );
+    assert_eq!(entry["category"], "Cinema");
+    assert_eq!(
+        entry["themes"],
+        serde_json::json!(["Criticism", "New Hollywood"])
+    );
+    assert_eq!(
+        entry["works_mentioned"],
+        serde_json::json!(["Bonnie and Clyde"])
+    );
+    assert_eq!(entry["tags"], serde_json::json!(["Classic"]));
+    assert_eq!(
+        entry["date_published"],
+        serde_json::json!({ "day": 3, "month": "May", "year": 1967 })
+    );
+    assert_eq!(entry["exceptional"], true);
+    assert_eq!(
+        entry["entry_type"],
+        serde_json::json!({ "Video": { "length_in_seconds": 600 } })
+    );
+    assert!(entry["image"].is_null());
+    assert!(entry["backup"].is_null());
+}
+
+#[tokio::test]
+async fn created_entry_fills_the_taxonomy_tables() {
+    let _database = TestDatabase::new().await;
+    create_entry_with(
+        "Bonnie and Clyde",
+        serde_json::json!({
+            "works_mentioned": ["Bonnie and Clyde"],
+            "tags": ["Classic"],
+        }),
+    )
+    .await;
+    create_entry_with(
+        "Dark Souls",
+        serde_json::json!({
+            "category": "Videogames",
+            "authors": ["Pauline Kael"],
+        }),
+    )
+    .await;
+
+    assert_eq!(
+        get("/api/categories").await.json(),
+        serde_json::json!(["Cinema", "Videogames"])
+    );
+    assert_eq!(
+        get("/api/authors").await.json(),
+        serde_json::json!([{ "value": "Pauline Kael", "category": "Cinema" }])
+    );
+    assert_eq!(
+        get("/api/themes").await.json(),
+        serde_json::json!([{ "value": "Criticism", "category": "Cinema" }])
+    );
+    assert_eq!(
+        get("/api/works").await.json(),
+        serde_json::json!([{ "value": "Bonnie and Clyde", "category": "Cinema" }])
+    );
+    assert_eq!(
+        get("/api/tags").await.json(),
+        serde_json::json!([{ "value": "Classic", "category": "Cinema" }])
+    );
+}
+
+#[tokio::test]
+async fn edited_and_deleted_entries_update_the_taxonomy_tables() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry_with(
+        "Bonnie and Clyde",
+        serde_json::json!({ "tags": ["Classic"] }),
+    )
+    .await;
+    create_entry("The Godfather").await;
+
+    put_json(
+        &format!("/api/texts/{}", id),
+        entry_form_with(
+            "Bonnie and Clyde",
+            serde_json::json!({ "category": "Books", "authors": ["Roger Ebert"], "tags": ["Crime"] }),
+        ),
+    )
+    .await;
+    assert_eq!(
+        get("/api/categories").await.json(),
+        serde_json::json!(["Cinema", "Books"])
+    );
+    assert_eq!(
+        get("/api/authors").await.json(),
+        serde_json::json!([
+            { "value": "Pauline Kael", "category": "Cinema" },
+            { "value": "Roger Ebert", "category": "Books" }
+        ])
+    );
+    assert_eq!(
+        get("/api/tags").await.json(),
+        serde_json::json!([{ "value": "Crime", "category": "Books" }])
+    );
+
+    delete(&format!("/api/texts/{}", id)).await;
+    assert_eq!(
+        get("/api/categories").await.json(),
+        serde_json::json!(["Cinema"])
+    );
+    assert_eq!(
+        get("/api/authors").await.json(),
+        serde_json::json!([{ "value": "Pauline Kael", "category": "Cinema" }])
+    );
+    assert_eq!(get("/api/tags").await.json(), serde_json::json!([]));
+
+    request(Method::POST, &format!("/api/trash/{}/restore", id), "").await;
+    assert_eq!(
+        get("/api/tags").await.json(),
+        serde_json::json!([{ "value": "Crime", "category": "Books" }])
+    );
+}
+
+#[tokio::test]
+async fn put_single_text_replaces_the_entry() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+
+    let response = put_json(
+        &path,
+        entry_form_with(
+            "Bonnie & Clyde",
+            serde_json::json!({
+                "authors": ["Roger Ebert"],
+                "exceptional": true,
+                "entry_type": { "Book": { "pages": 300 } }
+            }),
+        ),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let entry = get(&path).await.json();
+    assert_eq!(entry["title"], "Bonnie & Clyde");
+    assert_eq!(entry["authors"], serde_json::json!(["Roger Ebert"]));
+    assert_eq!(entry["exceptional"], true);
+    assert_eq!(
+        entry["entry_type"],
+        serde_json::json!({ "Book": { "pages": 300 } })
+    );
+}
+
+fn today() -> serde_json::Value {
+    serde_json::to_value(archive::date::today()).unwrap()
+}
+
+fn day_modified(entry: &serde_json::Value) -> serde_json::Value {
+    let day = archive::date::day_of_timestamp(entry["date_modified"].as_str().unwrap());
+    serde_json::to_value(day).unwrap()
+}
+
+#[tokio::test]
+async fn created_entry_is_saved_and_modified_today() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+
+    let entry = get(&format!("/api/texts/{}", id)).await.json();
+    assert_eq!(entry["date_saved"], today());
+    assert_eq!(day_modified(&entry), today());
+}
+
+#[tokio::test]
+async fn put_single_text_keeps_the_date_the_entry_was_saved() {
+    let _database = TestDatabase::new().await;
+    let saved = serde_json::json!({ "day": 12, "month": "August", "year": 2003 });
+    let id = create_entry_with(
+        "Bonnie and Clyde",
+        serde_json::json!({ "date_saved": saved }),
+    )
+    .await;
+    let path = format!("/api/texts/{}", id);
+    assert_eq!(get(&path).await.json()["date_saved"], saved);
+
+    put_json(&path, new_entry_form("Bonnie & Clyde")).await;
+
+    let entry = get(&path).await.json();
+    assert_eq!(entry["date_saved"], saved);
+    assert_eq!(day_modified(&entry), today());
+}
+
+#[tokio::test]
+async fn every_edit_moves_date_modified_forward() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+
+    let mut modified = vec![get(&path).await.json()["date_modified"].clone()];
+    for title in ["Bonnie & Clyde", "Bonnie and Clyde"] {
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        put_json(&path, new_entry_form(title)).await;
+        modified.push(get(&path).await.json()["date_modified"].clone());
+    }
+
+    let modified = modified
+        .iter()
+        .map(|timestamp| timestamp.as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert!(modified[0] < modified[1] && modified[1] < modified[2]);
+}
+
+#[tokio::test]
+async fn put_single_text_can_set_the_date_the_entry_was_saved() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+
+    let saved = serde_json::json!({ "day": 12, "month": "August", "year": 2003 });
+    put_json(
+        &path,
+        entry_form_with(
+            "Bonnie and Clyde",
+            serde_json::json!({ "date_saved": saved }),
+        ),
+    )
+    .await;
+
+    assert_eq!(get(&path).await.json()["date_saved"], saved);
+}
+
+#[tokio::test]
+async fn edits_do_not_affect_other_entries() {
+    let _database = TestDatabase::new().await;
+    let first = create_entry("Bonnie and Clyde").await;
+    let second = create_entry("The Godfather").await;
+
+    put_json(
+        &format!("/api/texts/{}", first),
+        new_entry_form("Bonnie & Clyde"),
+    )
+    .await;
+    delete(&format!("/api/texts/{}", first)).await;
+
+    let entry = get(&format!("/api/texts/{}", second)).await.json();
+    assert_eq!(entry["title"], "The Godfather");
+}
@@ -0,0 +1,307 @@
+use super::*;
+
+// Single entry
+
+#[tokio::test]
+async fn get_single_text_that_does_not_exist_is_not_found() {
+    let _database = TestDatabase::new().await;
+    assert_eq!(get("/api/texts/1").await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn get_single_text_database_failure_is_internal_server_error() {
+    let database = TestDatabase::new().await;
+    database.break_entries_table();
+    assert_eq!(
+        get("/api/texts/1").await.status,
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+#[tokio::test]
+async fn put_single_text_that_does_not_exist_is_not_found() {
+    let _database = TestDatabase::new().await;
+    let response = put_json("/api/texts/1", new_entry_form("Bonnie and Clyde")).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn put_single_text_with_malformed_body_is_bad_request() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let response = put_json(
+        &format!("/api/texts/{}", id),
+        serde_json::json!({ "title": "Missing everything else" }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(response.json()["error_message"].is_string());
+}
+
+#[tokio::test]
+async fn put_single_text_database_failure_is_internal_server_error() {
+    let database = TestDatabase::new().await;
+    database.break_entries_table();
+    let response = put_json("/api/texts/1", new_entry_form("Bonnie and Clyde")).await;
+    assert_eq!(response.status, StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn delete_single_text_removes_the_entry() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+
+    assert_eq!(delete(&path).await.status, StatusCode::NO_CONTENT);
+    assert_eq!(get(&path).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(delete(&path).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn delete_single_text_database_failure_is_internal_server_error() {
+    let database = TestDatabase::new().await;
+    database.break_entries_table();
+    assert_eq!(
+        delete("/api/texts/1").await.status,
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+// Search
+
+#[tokio::test]
+async fn get_texts_with_unparsable_query_is_bad_request() {
+    let _database = TestDatabase::new().await;
+    assert_eq!(
+        get("/api/texts?type=snafucated").await.status,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        get("/api/texts?no_such_field=1").await.status,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+async fn get_texts_database_failure_is_internal_server_error() {
+    let database = TestDatabase::new().await;
+    database.break_entries_table();
+    assert_eq!(
+        get("/api/texts?title=Bonnie").await.status,
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+// Image
+
+#[tokio::test]
+async fn get_entry_image_without_image_is_not_found() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    assert_eq!(
+        get(&format!("/api/texts/{}/image", id)).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        get("/api/texts/1000/image").await.status,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn put_entry_image_with_invalid_image_is_bad_request() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let response = request_with_headers(
+        Method::PUT,
+        &format!("/api/texts/{}/image", id),
+        &[("Content-Type", "image/png")],
+        "definitely not a png",
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn put_entry_image_without_content_type_is_bad_request() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let response = request(Method::PUT, &format!("/api/texts/{}/image", id), "").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn delete_entry_image_of_missing_entry_is_not_found() {
+    let _database = TestDatabase::new().await;
+    assert_eq!(
+        delete("/api/texts/1/image").await.status,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn delete_entry_image_of_existing_entry_is_no_content() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    assert_eq!(
+        delete(&format!("/api/texts/{}/image", id)).await.status,
+        StatusCode::NO_CONTENT
+    );
+}
+
+#[tokio::test]
+async fn delete_entry_image_database_failure_is_internal_server_error() {
+    let database = TestDatabase::new().await;
+    database.break_entries_table();
+    assert_eq!(
+        delete("/api/texts/1/image").await.status,
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+// Backup
+
+#[tokio::test]
+async fn put_entry_backup_of_missing_entry_is_not_found() {
+    let _database = TestDatabase::new().await;
+    let response = request_with_headers(
+        Method::PUT,
+        "/api/texts/1/backup",
+        &[("Content-Type", "text/plain")],
+        "Hello",
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn put_entry_backup_without_content_type_is_bad_request() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let response = request(Method::PUT, &format!("/api/texts/{}/backup", id), "Hello").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn get_entry_backup_without_backup_is_not_found() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    assert_eq!(
+        get(&format!("/api/texts/{}/backup", id)).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        get("/api/texts/1000/backup").await.status,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn delete_entry_backup_of_missing_entry_is_not_found() {
+    let _database = TestDatabase::new().await;
+    assert_eq!(
+        delete("/api/texts/1/backup").await.status,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn delete_entry_backup_database_failure_is_internal_server_error() {
+    let database = TestDatabase::new().await;
+    database.break_entries_table();
+    assert_eq!(
+        delete("/api/texts/1/backup").await.status,
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+// HEAD
+
+#[tokio::test]
+async fn head_single_text_has_the_headers_of_get_and_no_body() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+
+    let get_response = get(&path).await;
+    let head_response = head(&path).await;
+
+    assert_eq!(head_response.status, StatusCode::OK);
+    assert!(head_response.body.is_empty());
+    assert_eq!(
+        head_response.header("Content-Type"),
+        get_response.header("Content-Type")
+    );
+    assert_eq!(
+        head_response.header("Content-Length"),
+        Some(get_response.body.len().to_string().as_str())
+    );
+}
+
+#[tokio::test]
+async fn head_maps_errors_like_get() {
+    let _database = TestDatabase::new().await;
+    assert_eq!(head("/api/texts/1").await.status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        head("/api/texts/1/image").await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        head("/api/texts/1/backup").await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        head("/api/texts?type=snafucated").await.status,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+async fn head_lists_and_searches() {
+    let _database = TestDatabase::new().await;
+    create_entry("Bonnie and Clyde").await;
+
+    for path in [
+        "/api/texts",
+        "/api/texts?title=Bonnie",
+        "/api/categories",
+        "/api/authors",
+        "/api/themes",
+        "/api/works",
+        "/api/tags",
+    ] {
+        let response = head(path).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", path);
+        assert!(response.body.is_empty(), "{}", path);
+    }
+}
+
+#[tokio::test]
+async fn head_entry_backup_reports_length_and_ranges() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}/backup", id);
+    request_with_headers(
+        Method::PUT,
+        &path,
+        &[("Content-Type", "text/plain")],
+        "Hello, world!",
+    )
+    .await;
+
+    let response = head(&path).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.is_empty());
+    assert_eq!(response.header("Content-Length"), Some("13"));
+    assert_eq!(response.header("Content-Type"), Some("text/plain"));
+    assert_eq!(response.header("Accept-Ranges"), Some("bytes"));
+}
+
+#[tokio::test]
+async fn options_allows_head() {
+    let response = request(Method::OPTIONS, "/api/texts", "").await;
+    assert!(response
+        .header("Access-Control-Allow-Methods")
+        .unwrap()
+        .contains("HEAD"));
+}
@@ -0,0 +1,248 @@
+use super::stub_server::{StubResponse, StubServer};
+use super::*;
+
+use serde_json::json;
+
+const ARTICLE: &str = r#"
+    <html><head>
+    <meta property="og:type" content="article">
+    <meta property="og:title" content="Bonnie and Clyde">
+    <meta name="description" content="The frontier of movies">
+    <meta name="author" content="Pauline Kael">
+    <meta property="article:published_time" content="1967-10-21T00:00:00Z">
+    <meta property="og:image" content="/cover.png">
+    </head><body>How do you make a good movie in this country without being jumped on?</body></html>
+"#;
+
+async fn post_from_url(body: serde_json::Value) -> TestResponse {
+    post_json("/api/texts/from_url", body).await
+}
+
+#[tokio::test]
+async fn entry_is_created_from_the_page() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route("/bonnie-and-clyde", StubResponse::html(ARTICLE));
+    stub.route(
+        "/cover.png",
+        StubResponse::ok("image/png", png_image(600, 338)),
+    );
+
+    let response = post_from_url(json!({
+        "url": stub.url("/bonnie-and-clyde"),
+        "overrides": { "category": "Cinema", "tags": ["Classic"] },
+        "backup": true
+    }))
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let created = response.json();
+    let id = created["id"].as_i64().unwrap();
+    assert_eq!(created["link"], format!("/api/texts/{}", id));
+    assert_eq!(
+        created["auto_filled"],
+        json!([
+            "title",
+            "description",
+            "authors",
+            "date_published",
+            "entry_type",
+            "image"
+        ])
+    );
+    assert_eq!(created["image"], format!("/api/texts/{}/image", id));
+    assert_eq!(created["backup"], format!("/api/texts/{}/backup", id));
+    assert_eq!(created["warnings"], json!([]));
+
+    let entry = get(&format!("/api/texts/{}", id)).await.json();
+    assert_eq!(entry["link"], stub.url("/bonnie-and-clyde"));
+    assert_eq!(entry["title"], "Bonnie and Clyde");
+    assert_eq!(entry["description"], "The frontier of movies");
+    assert_eq!(entry["authors"], json!(["Pauline Kael"]));
+    assert_eq!(entry["category"], "Cinema");
+    assert_eq!(entry["tags"], json!(["Classic"]));
+    assert_eq!(
+        entry["date_published"],
+        json!({ "day": 21, "month": "October", "year": 1967 })
+    );
+    // Counted from the text of the page.
+    assert_eq!(entry["entry_type"], json!({ "Article": { "words": 14 } }));
+
+    let image = get(&format!("/api/texts/{}/image", id)).await;
+    assert_eq!(image.status, StatusCode::OK);
+    let backup = get(&format!("/api/texts/{}/backup", id)).await;
+    assert_eq!(
+        backup.header("Content-Type"),
+        Some("text/html; charset=utf-8")
+    );
+    assert_eq!(backup.body, ARTICLE.as_bytes());
+    assert_eq!(stub.hits("/bonnie-and-clyde"), 1);
+}
+
+#[tokio::test]
+async fn overrides_take_precedence_over_the_page() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route("/bonnie-and-clyde", StubResponse::html(ARTICLE));
+
+    let response = post_from_url(json!({
+        "url": stub.url("/bonnie-and-clyde"),
+        "overrides": { "category": "Cinema", "title": "Bonnie & Clyde" }
+    }))
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let created = response.json();
+    assert_eq!(
+        created["auto_filled"],
+        json!(["description", "authors", "date_published", "entry_type"])
+    );
+    assert!(created["image"].is_null());
+    assert!(created["backup"].is_null());
+    assert_eq!(created["warnings"].as_array().unwrap().len(), 1);
+
+    let entry = get(&format!("/api/texts/{}", created["id"])).await.json();
+    assert_eq!(entry["title"], "Bonnie & Clyde");
+    assert!(entry["image"].is_null());
+    assert!(entry["backup"].is_null());
+}
+
+#[tokio::test]
+async fn fields_neither_found_nor_given_are_bad_request() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route(
+        "/empty",
+        StubResponse::html("<html><body>Hello</body></html>"),
+    );
+
+    let response = post_from_url(json!({ "url": stub.url("/empty") })).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.json()["fields"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>(),
+        ["category", "date_published", "title"]
+    );
+    assert_eq!(get("/api/texts").await.json()["total_size"], 0);
+}
+
+#[tokio::test]
+async fn unreachable_page_is_bad_request() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route("/gone", StubResponse::with_status(StatusCode::NOT_FOUND));
+
+    let response = post_from_url(json!({
+        "url": stub.url("/gone"),
+        "overrides": { "category": "Cinema" }
+    }))
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn url_that_is_not_a_web_url_is_bad_request() {
+    let _database = TestDatabase::new().await;
+
+    for url in [
+        "example.com/page",
+        "not a url",
+        "https://example.com/a b",
+        "ftp://example.com/page",
+    ] {
+        let response = post_from_url(json!({
+            "url": url,
+            "overrides": { "category": "Cinema" }
+        }))
+        .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", url);
+    }
+}
+
+#[tokio::test]
+async fn page_that_is_already_archived_is_a_conflict() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route("/bonnie-and-clyde", StubResponse::html(ARTICLE));
+    let body = json!({
+        "url": stub.url("/bonnie-and-clyde"),
+        "overrides": { "category": "Cinema" }
+    });
+
+    let id = post_from_url(body.clone()).await.json()["id"].clone();
+    let response = post_from_url(body.clone()).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.json()["existing_id"], id);
+
+    let response = post_json("/api/texts/from_url?force=true", body).await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn entry_is_created_from_a_pdf() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route(
+        "/frontier.pdf",
+        StubResponse::ok("application/pdf", PDF_PAPER.to_vec()),
+    );
+
+    let response = post_from_url(json!({
+        "url": stub.url("/frontier.pdf"),
+        "overrides": { "category": "Cinema", "date_published": { "day": 1, "month": "January", "year": 1968 } }
+    }))
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let created = response.json();
+    assert_eq!(
+        created["auto_filled"],
+        json!(["title", "authors", "entry_type"])
+    );
+
+    let entry = get(&format!("/api/texts/{}", created["id"])).await.json();
+    assert_eq!(entry["title"], "The Frontier of Movies: Bonnie and Clyde");
+    assert_eq!(entry["authors"], json!(["Pauline Kael", "Arthur Penn"]));
+    assert_eq!(entry["entry_type"], json!({ "Paper": { "pages": 3 } }));
+}
+
+#[tokio::test]
+async fn entry_of_a_video_gets_its_author_and_length_from_oembed() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route(
+        "/watch",
+        StubResponse::html(&format!(
+            r#"<meta property="og:type" content="video.other">
+            <meta property="og:title" content="Arthur Penn on Bonnie and Clyde">
+            <link rel="alternate" type="application/json+oembed" href="{}">"#,
+            stub.url("/oembed")
+        )),
+    );
+    stub.route(
+        "/oembed",
+        StubResponse::ok(
+            "application/json",
+            r#"{"type": "video", "version": "1.0", "author_name": "Archivo de Cine", "duration": 754, "upload_date": "2012-03-02 10:00:00"}"#,
+        ),
+    );
+
+    let response = post_from_url(json!({
+        "url": stub.url("/watch"),
+        "overrides": { "category": "Cinema" }
+    }))
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let created = response.json();
+    assert_eq!(
+        created["auto_filled"],
+        json!(["title", "authors", "date_published", "entry_type"])
+    );
+
+    let entry = get(&format!("/api/texts/{}", created["id"])).await.json();
+    assert_eq!(entry["authors"], json!(["Archivo de Cine"]));
+    assert_eq!(
+        entry["entry_type"],
+        json!({ "Video": { "length_in_seconds": 754 } })
+    );
+}
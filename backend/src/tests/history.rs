@@ -0,0 +1,176 @@
+use super::*;
+
+async fn history_of(id: i64) -> serde_json::Value {
+    let response = get(&format!("/api/texts/{}/history", id)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    response.json()
+}
+
+async fn restore(id: i64, revision: &serde_json::Value) -> TestResponse {
+    request(
+        Method::POST,
+        &format!("/api/texts/{}/history/{}/restore", id, revision),
+        "",
+    )
+    .await
+}
+
+#[tokio::test]
+async fn creating_an_entry_records_every_field_as_new() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+
+    let history = history_of(id).await;
+    assert_eq!(history.as_array().unwrap().len(), 1);
+    assert_eq!(history[0]["action"], "create");
+    assert!(history[0]["timestamp"].is_string());
+
+    let changes = history[0]["changes"].as_array().unwrap();
+    assert!(changes.iter().all(|change| change["old"].is_null()));
+    assert!(changes
+        .iter()
+        .any(|change| change["field"] == "title" && change["new"] == "Bonnie and Clyde"));
+}
+
+#[tokio::test]
+async fn revisions_are_timed_like_date_modified() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+    for title in ["The Frontier of Movies", "Bonnie and Clyde"] {
+        put_json(&path, new_entry_form(title)).await;
+    }
+
+    let history = history_of(id).await;
+    let timestamps: Vec<&str> = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|revision| revision["timestamp"].as_str().unwrap())
+        .collect();
+    assert_eq!(timestamps.len(), 3);
+    // Writes within the same second are told apart.
+    assert!(timestamps.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let entry = get(&path).await.json();
+    let date_modified = entry["date_modified"].as_str().unwrap();
+    assert_eq!(timestamps[2].len(), date_modified.len());
+    assert!(timestamps[2].ends_with('Z'));
+    assert!(timestamps[1] < date_modified && date_modified <= timestamps[2]);
+}
+
+#[tokio::test]
+async fn updating_an_entry_records_only_the_changed_fields() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    put_json(
+        &format!("/api/texts/{}", id),
+        entry_form_with(
+            "Bonnie and Clyde",
+            serde_json::json!({ "title": "Bonnie & Clyde", "exceptional": true }),
+        ),
+    )
+    .await;
+
+    let history = history_of(id).await;
+    assert_eq!(history[1]["action"], "update");
+    assert_eq!(
+        history[1]["changes"],
+        serde_json::json!([
+            { "field": "exceptional", "old": false, "new": true },
+            { "field": "title", "old": "Bonnie and Clyde", "new": "Bonnie & Clyde" },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn deleting_an_entry_keeps_its_history() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    delete(&format!("/api/texts/{}", id)).await;
+
+    let history = history_of(id).await;
+    assert_eq!(history[1]["action"], "delete");
+    let changes = history[1]["changes"].as_array().unwrap();
+    assert!(changes.iter().all(|change| change["new"].is_null()));
+}
+
+#[tokio::test]
+async fn history_of_entry_that_never_existed_is_not_found() {
+    let _database = TestDatabase::new().await;
+    let response = get("/api/texts/1/history").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn restore_sets_the_values_of_the_revision_back() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+    put_json(
+        &path,
+        entry_form_with(
+            "Bonnie and Clyde",
+            serde_json::json!({ "title": "Bonnie & Clyde" }),
+        ),
+    )
+    .await;
+
+    let created = &history_of(id).await[0]["revision"];
+    let response = restore(id, created).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert_eq!(get(&path).await.json()["title"], "Bonnie and Clyde");
+
+    let history = history_of(id).await;
+    assert_eq!(history[2]["action"], "restore");
+    assert_eq!(
+        history[2]["changes"],
+        serde_json::json!([
+            { "field": "title", "old": "Bonnie & Clyde", "new": "Bonnie and Clyde" },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn restore_brings_a_deleted_entry_back_with_the_same_id() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+    delete(&path).await;
+
+    let created = &history_of(id).await[0]["revision"];
+    assert_eq!(restore(id, created).await.status, StatusCode::NO_CONTENT);
+
+    let entry = get(&path).await.json();
+    assert_eq!(entry["id"], id);
+    assert_eq!(entry["title"], "Bonnie and Clyde");
+}
+
+#[tokio::test]
+async fn restore_of_a_delete_is_bad_request() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    delete(&format!("/api/texts/{}", id)).await;
+
+    let deleted = &history_of(id).await[1]["revision"];
+    let response = restore(id, deleted).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(response.json()["error_message"].is_string());
+}
+
+#[tokio::test]
+async fn restore_of_a_revision_of_another_entry_is_not_found() {
+    let _database = TestDatabase::new().await;
+    let first = create_entry("Bonnie and Clyde").await;
+    let second = create_entry("The Godfather").await;
+
+    let revision = &history_of(first).await[0]["revision"];
+    assert_eq!(
+        restore(second, revision).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        restore(second, &serde_json::json!(1000)).await.status,
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,308 @@
+use super::stub_server::{StubResponse, StubServer};
+use super::*;
+
+use archive::resolvers::ResolverUrls;
+use serde_json::json;
+
+const CROSSREF_PAPER: &str = r#"{"status": "ok", "message-type": "work", "message": {
+    "DOI": "10.2307/1210357",
+    "title": ["The Frontier of Movies"],
+    "author": [{"given": "Pauline", "family": "Kael"}, {"given": "Arthur", "family": "Penn"}],
+    "issued": {"date-parts": [[1968, 1, 15]]},
+    "page": "112-131",
+    "type": "journal-article"
+}}"#;
+
+const ARXIV_PAPER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <entry>
+    <id>http://arxiv.org/abs/1706.03762v7</id>
+    <published>2017-06-12T17:57:34Z</published>
+    <title>Attention Is All You Need</title>
+    <summary>The dominant sequence transduction models are based on complex recurrent networks.</summary>
+    <author><name>Ashish Vaswani</name></author>
+    <author><name>Noam Shazeer</name></author>
+    <arxiv:comment xmlns:arxiv="http://arxiv.org/schemas/atom">15 pages, 5 figures</arxiv:comment>
+  </entry>
+</feed>"#;
+
+const OPENLIBRARY_BOOK: &str = r#"{"ISBN:9780316481632": {
+    "title": "Kiss Kiss Bang Bang",
+    "authors": [{"name": "Pauline Kael"}],
+    "number_of_pages": 404,
+    "publish_date": "1968"
+}}"#;
+
+// Looks identifiers up in the stub server instead of the public registries.
+fn use_registries_of(stub: &StubServer) {
+    crate::state::global_state().lock().unwrap().resolvers = Some(ResolverUrls {
+        crossref: stub.url("/crossref/works"),
+        arxiv: stub.url("/arxiv/query"),
+        openlibrary: stub.url("/openlibrary/books"),
+    });
+}
+
+async fn stub_registries() -> StubServer {
+    let stub = StubServer::start().await;
+    stub.route(
+        "/crossref/works/10.2307/1210357",
+        StubResponse::ok("application/json", CROSSREF_PAPER),
+    );
+    stub.route(
+        "/arxiv/query",
+        StubResponse::ok("application/atom+xml; charset=utf-8", ARXIV_PAPER),
+    );
+    stub.route(
+        "/openlibrary/books",
+        StubResponse::ok("application/json", OPENLIBRARY_BOOK),
+    );
+    use_registries_of(&stub);
+    stub
+}
+
+async fn patch(path: &str, patch: serde_json::Value) -> TestResponse {
+    request_with_headers(
+        Method::PATCH,
+        path,
+        &[("Content-Type", "application/merge-patch+json")],
+        patch.to_string(),
+    )
+    .await
+}
+
+async fn restore(id: i64, revision: &serde_json::Value) -> TestResponse {
+    request(
+        Method::POST,
+        &format!("/api/texts/{}/history/{}/restore", id, revision),
+        "",
+    )
+    .await
+}
+
+#[tokio::test]
+async fn entry_is_created_from_a_doi() {
+    let _database = TestDatabase::new().await;
+    let stub = stub_registries().await;
+
+    let response = post_json(
+        "/api/texts/from_url",
+        json!({
+            "url": "DOI: 10.2307/1210357",
+            "overrides": { "category": "Cinema" },
+            "backup": true
+        }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let created = response.json();
+    assert_eq!(
+        created["auto_filled"],
+        json!([
+            "title",
+            "authors",
+            "date_published",
+            "entry_type",
+            "identifier"
+        ])
+    );
+    assert_eq!(created["image"], json!(null));
+    assert_eq!(created["backup"], json!(null));
+    assert_eq!(
+        created["warnings"],
+        json!(["DOI: 10.2307/1210357 is not a page, so there is nothing to back up"])
+    );
+
+    let entry = get(created["link"].as_str().unwrap()).await.json();
+    assert_eq!(entry["link"], "https://doi.org/10.2307/1210357");
+    assert_eq!(entry["identifier"], "doi:10.2307/1210357");
+    assert_eq!(entry["title"], "The Frontier of Movies");
+    assert_eq!(entry["authors"], json!(["Pauline Kael", "Arthur Penn"]));
+    assert_eq!(
+        entry["date_published"],
+        json!({ "day": 15, "month": "January", "year": 1968 })
+    );
+    assert_eq!(entry["entry_type"], json!({ "Paper": { "pages": 20 } }));
+    assert_eq!(stub.hits("/crossref/works/10.2307/1210357"), 1);
+}
+
+#[tokio::test]
+async fn entry_of_a_book_page_is_completed_from_its_isbn() {
+    let _database = TestDatabase::new().await;
+    let stub = stub_registries().await;
+    stub.route(
+        "/kiss-kiss-bang-bang",
+        StubResponse::html(
+            r#"<meta name="citation_title" content="Kiss Kiss Bang Bang">
+            <meta name="citation_isbn" content="0-316-48163-7">"#,
+        ),
+    );
+
+    let response = post_json(
+        "/api/texts/from_url",
+        json!({
+            "url": stub.url("/kiss-kiss-bang-bang"),
+            "overrides": { "category": "Cinema" }
+        }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let entry = get(response.json()["link"].as_str().unwrap()).await.json();
+    assert_eq!(entry["link"], stub.url("/kiss-kiss-bang-bang"));
+    assert_eq!(entry["identifier"], "isbn:9780316481632");
+    assert_eq!(entry["authors"], json!(["Pauline Kael"]));
+    assert_eq!(entry["entry_type"], json!({ "Book": { "pages": 404 } }));
+    assert_eq!(stub.hits("/openlibrary/books"), 1);
+}
+
+#[tokio::test]
+async fn identifier_without_record_is_bad_request() {
+    let _database = TestDatabase::new().await;
+    let stub = stub_registries().await;
+    stub.route(
+        "/openlibrary/books",
+        StubResponse::ok("application/json", "{}"),
+    );
+
+    let response = post_json(
+        "/api/texts/from_url",
+        json!({ "url": "ISBN 0-316-48163-7", "overrides": { "category": "Cinema" } }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        get("/api/texts?category=Cinema").await.json()["total_size"],
+        0
+    );
+}
+
+#[tokio::test]
+async fn page_metadata_of_an_arxiv_id_comes_from_its_registry() {
+    let _database = TestDatabase::new().await;
+    let _stub = stub_registries().await;
+
+    let response = get("/api/page_metadata/arXiv:1706.03762v7").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let suggestion = response.json();
+    assert_eq!(suggestion["title"], "Attention Is All You Need");
+    assert_eq!(
+        suggestion["authors"],
+        json!(["Ashish Vaswani", "Noam Shazeer"])
+    );
+    assert_eq!(
+        suggestion["entry_type"],
+        json!({ "Paper": { "pages": 15 } })
+    );
+    assert_eq!(suggestion["identifier"], "arxiv:1706.03762");
+    assert_eq!(
+        suggestion["provenance"]["identifier"],
+        json!({ "source": "registry", "confidence": 0.95 })
+    );
+}
+
+#[tokio::test]
+async fn page_metadata_keeps_the_identifier_of_the_page_when_its_registry_is_unreachable() {
+    let _database = TestDatabase::new().await;
+    let stub = stub_registries().await;
+    stub.route(
+        "/frontier",
+        StubResponse::html(
+            r#"<meta name="citation_title" content="The Frontier of Movies">
+            <meta name="citation_doi" content="10.2307/9999999">"#,
+        ),
+    );
+
+    let response = get(&format!("/api/page_metadata/{}", stub.url("/frontier"))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let suggestion = response.json();
+    assert_eq!(suggestion["title"], "The Frontier of Movies");
+    assert_eq!(suggestion["identifier"], "doi:10.2307/9999999");
+}
+
+#[tokio::test]
+async fn entries_are_found_by_their_identifier_however_it_is_written() {
+    let _database = TestDatabase::new().await;
+    create_entry_with(
+        "The Frontier of Movies",
+        json!({ "identifier": "https://doi.org/10.2307/1210357" }),
+    )
+    .await;
+    create_entry_with(
+        "Kiss Kiss Bang Bang",
+        json!({ "identifier": "ISBN 0-316-48163-7" }),
+    )
+    .await;
+    create_entry("Bonnie and Clyde").await;
+
+    let titles = |response: TestResponse| {
+        response.json()["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| String::from(entry["title"].as_str().unwrap()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        titles(get("/api/texts?identifier=doi:10.2307/1210357").await),
+        ["The Frontier of Movies"]
+    );
+    assert_eq!(
+        titles(get("/api/texts?identifier=978-0-316-48163-2").await),
+        ["Kiss Kiss Bang Bang"]
+    );
+    assert_eq!(
+        titles(get("/api/texts?identifier=isbn").await),
+        ["Kiss Kiss Bang Bang"]
+    );
+}
+
+#[tokio::test]
+async fn identifier_is_kept_by_edits_without_it_and_removed_by_null() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry_with(
+        "The Frontier of Movies",
+        json!({ "identifier": "doi:10.2307/1210357" }),
+    )
+    .await;
+    let path = format!("/api/texts/{}", id);
+
+    let response = put_json(&path, new_entry_form("The Frontier of Movies")).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert_eq!(get(&path).await.json()["identifier"], "doi:10.2307/1210357");
+
+    let response = patch(&path, json!({ "identifier": null })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["identifier"], json!(null));
+
+    // Restoring a revision without identifier removes the one the entry has.
+    let history = get(&format!("/api/texts/{}/history", id)).await.json();
+    assert_eq!(
+        restore(id, &history[0]["revision"]).await.status,
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(get(&path).await.json()["identifier"], "doi:10.2307/1210357");
+    assert_eq!(
+        restore(id, &history[2]["revision"]).await.status,
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(get(&path).await.json()["identifier"], json!(null));
+}
+
+#[tokio::test]
+async fn identifiers_that_are_not_dois_arxiv_ids_or_isbns_are_rejected() {
+    let _database = TestDatabase::new().await;
+
+    let response = post_json(
+        "/api/texts",
+        entry_form_with(
+            "The Frontier of Movies",
+            json!({ "identifier": "isbn:0-316-48163-8" }),
+        ),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.json()["fields"],
+        json!({ "identifier": ["'isbn:0-316-48163-8' is not a DOI, arXiv id or ISBN"] })
+    );
+}
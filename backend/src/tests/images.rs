@@ -0,0 +1,131 @@
+use super::stub_server::{StubResponse, StubServer};
+use super::*;
+
+fn decode_png(bytes: &[u8]) -> image::DynamicImage {
+    image::load_from_memory_with_format(bytes, image::ImageFormat::Png).unwrap()
+}
+
+#[tokio::test]
+async fn put_entry_image_in_body_is_normalized_to_png() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}/image", id);
+
+    let response = request_with_headers(
+        Method::PUT,
+        &path,
+        &[("Content-Type", "image/png")],
+        png_image(640, 480),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["link"], path);
+
+    let image = get(&path).await;
+    assert_eq!(image.status, StatusCode::OK);
+    assert_eq!(image.header("Content-Type"), Some("image/png"));
+    let decoded = decode_png(&image.body);
+    assert_eq!((decoded.width(), decoded.height()), (300, 169));
+
+    let entry = get(&format!("/api/texts/{}", id)).await.json();
+    assert_eq!(entry["image"], path);
+}
+
+#[tokio::test]
+async fn put_entry_image_from_link() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route(
+        "/cover.png",
+        StubResponse::ok("image/png", png_image(100, 400)),
+    );
+
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}/image", id);
+
+    let response = put_json(
+        &path,
+        serde_json::json!({ "image_url": stub.url("/cover.png") }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(stub.hits("/cover.png"), 1);
+
+    let decoded = decode_png(&get(&path).await.body);
+    assert_eq!((decoded.width(), decoded.height()), (300, 169));
+}
+
+#[tokio::test]
+async fn put_entry_image_from_link_that_is_not_an_image_is_bad_request() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route("/page", StubResponse::html("<html></html>"));
+
+    let id = create_entry("Bonnie and Clyde").await;
+    let response = put_json(
+        &format!("/api/texts/{}/image", id),
+        serde_json::json!({ "image_url": stub.url("/page") }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn put_entry_image_from_broken_link_is_bad_request() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+
+    let id = create_entry("Bonnie and Clyde").await;
+    let response = put_json(
+        &format!("/api/texts/{}/image", id),
+        serde_json::json!({ "image_url": stub.url("/missing.png") }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn put_entry_image_from_malformed_link_is_bad_request() {
+    let _database = TestDatabase::new().await;
+
+    let id = create_entry("Bonnie and Clyde").await;
+    for link in ["example.com/cover.png", "not a url", "https://example.com/a b"] {
+        let response = put_json(
+            &format!("/api/texts/{}/image", id),
+            serde_json::json!({ "image_url": link }),
+        )
+        .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", link);
+    }
+}
+
+#[tokio::test]
+async fn put_entry_image_of_missing_entry_is_not_found() {
+    let _database = TestDatabase::new().await;
+    let response = request_with_headers(
+        Method::PUT,
+        "/api/texts/1/image",
+        &[("Content-Type", "image/png")],
+        png_image(300, 169),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn delete_entry_image_removes_it() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}/image", id);
+    request_with_headers(
+        Method::PUT,
+        &path,
+        &[("Content-Type", "image/png")],
+        png_image(300, 169),
+    )
+    .await;
+
+    assert_eq!(delete(&path).await.status, StatusCode::NO_CONTENT);
+    assert_eq!(get(&path).await.status, StatusCode::NOT_FOUND);
+    assert!(get(&format!("/api/texts/{}", id)).await.json()["image"].is_null());
+}
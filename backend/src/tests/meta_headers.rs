@@ -0,0 +1,253 @@
+use super::stub_server::{StubResponse, StubServer};
+use super::*;
+
+const ARTICLE_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Bonnie and Clyde</title>
+    <meta charset="utf-8">
+    <meta name="author" content="Pauline Kael">
+    <meta property="og:title" content="Bonnie and Clyde">
+    <meta property="og:type" content="article">
+    <meta name="description" content="The frontier of movies">
+</head>
+<body></body>
+</html>"#;
+
+#[tokio::test]
+async fn meta_headers_of_html_page() {
+    let stub = StubServer::start().await;
+    stub.route("/article", StubResponse::html(ARTICLE_PAGE));
+
+    let response = get(&format!("/api/meta_headers/{}", stub.url("/article"))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.json(),
+        serde_json::json!([
+            ["author", "Pauline Kael"],
+            ["og:title", "Bonnie and Clyde"],
+            ["og:type", "article"],
+            ["description", "The frontier of movies"],
+        ])
+    );
+}
+
+#[tokio::test]
+async fn meta_headers_of_pages_in_other_charsets_are_transcoded() {
+    let stub = StubServer::start().await;
+    stub.route(
+        "/latin1",
+        StubResponse::ok(
+            "text/html; charset=ISO-8859-1",
+            &b"<meta name='description' content='Cr\xEDtica de \xABBonnie y Clyde\xBB'>"[..],
+        ),
+    );
+    stub.route(
+        "/undeclared",
+        StubResponse::ok("text/html", &b"<meta name='author' content='Pe\xF1a'>"[..]),
+    );
+
+    let response = get(&format!("/api/meta_headers/{}", stub.url("/latin1"))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.json(),
+        serde_json::json!([["description", "Crítica de «Bonnie y Clyde»"]])
+    );
+
+    let response = get(&format!("/api/meta_headers/{}", stub.url("/undeclared"))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json(), serde_json::json!([["author", "Peña"]]));
+}
+
+#[tokio::test]
+async fn meta_headers_keep_the_query_of_the_url() {
+    let stub = StubServer::start().await;
+    stub.route("/article", StubResponse::html(ARTICLE_PAGE));
+
+    let response = get(&format!("/api/meta_headers/{}?id=5", stub.url("/article"))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(stub.hits("/article"), 1);
+}
+
+#[tokio::test]
+async fn meta_headers_of_missing_page_is_not_found() {
+    let stub = StubServer::start().await;
+
+    let response = get(&format!("/api/meta_headers/{}", stub.url("/missing"))).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn meta_headers_of_non_html_resource_is_not_found() {
+    let stub = StubServer::start().await;
+    stub.route(
+        "/paper.pdf",
+        StubResponse::ok("application/pdf", "%PDF-1.4"),
+    );
+
+    let response = get(&format!("/api/meta_headers/{}", stub.url("/paper.pdf"))).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn forward_returns_the_remote_response() {
+    let stub = StubServer::start().await;
+    stub.route(
+        "/data.json",
+        StubResponse::ok("application/json", "[1, 2, 3]"),
+    );
+
+    let response = get(&format!("/api/forward/{}", stub.url("/data.json"))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("Access-Control-Allow-Origin"), Some("*"));
+    assert_eq!(response.body, "[1, 2, 3]");
+}
+
+#[tokio::test]
+async fn forward_of_an_invalid_url_is_bad_request() {
+    let response = get("/api/forward/example.com/page").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn page_metadata_merges_json_ld_and_meta_tags_with_their_provenance() {
+    let stub = StubServer::start().await;
+    stub.route(
+        "/article",
+        StubResponse::html(&ARTICLE_PAGE.replace(
+            "</head>",
+            r#"<script type="application/ld+json">
+                {"@context": "https://schema.org", "@type": "NewsArticle", "author": [{"@type": "Person", "name": "Pauline Kael"}], "datePublished": "1967-10-21", "wordCount": 7000}
+            </script></head>"#,
+        )),
+    );
+
+    let response = get(&format!("/api/page_metadata/{}", stub.url("/article"))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.json(),
+        serde_json::json!({
+            "title": "Bonnie and Clyde",
+            "description": "The frontier of movies",
+            "authors": ["Pauline Kael"],
+            "date_published": {"day": 21, "month": "October", "year": 1967},
+            "entry_type": {"Article": {"words": 7000}},
+            "image": null,
+            "canonical_link": null,
+            "identifier": null,
+            "provenance": {
+                "title": {"source": "open_graph", "confidence": 0.8},
+                "description": {"source": "html", "confidence": 0.6},
+                "authors": {"source": "json_ld", "confidence": 0.9},
+                "date_published": {"source": "json_ld", "confidence": 0.9},
+                "entry_type": {"source": "json_ld", "confidence": 0.9},
+            },
+        })
+    );
+}
+
+const VIDEO_PAGE: &str = r#"
+    <html><head>
+    <title>Arthur Penn on Bonnie and Clyde - YouTube</title>
+    <meta property="og:type" content="video.other">
+    <meta property="og:title" content="Arthur Penn on Bonnie and Clyde">
+    <link rel="alternate" type="application/json+oembed" href="/oembed?format=json&amp;url=https%3A%2F%2Fexample.com%2Fwatch">
+    </head></html>
+"#;
+
+#[tokio::test]
+async fn page_metadata_merges_the_oembed_endpoint_of_the_page() {
+    let stub = StubServer::start().await;
+    stub.route("/watch", StubResponse::html(VIDEO_PAGE));
+    stub.route(
+        "/oembed",
+        StubResponse::ok(
+            "application/json",
+            r#"{"type": "video", "version": "1.0", "provider_name": "Vimeo", "title": "Arthur Penn on Bonnie and Clyde", "author_name": "Archivo de Cine", "duration": 754}"#,
+        ),
+    );
+
+    let response = get(&format!("/api/page_metadata/{}", stub.url("/watch"))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let suggestion = response.json();
+    assert_eq!(suggestion["title"], "Arthur Penn on Bonnie and Clyde");
+    assert_eq!(
+        suggestion["authors"],
+        serde_json::json!(["Archivo de Cine"])
+    );
+    assert_eq!(
+        suggestion["entry_type"],
+        serde_json::json!({ "Video": { "length_in_seconds": 754 } })
+    );
+    assert_eq!(
+        suggestion["provenance"]["authors"],
+        serde_json::json!({ "source": "oembed", "confidence": 0.85 })
+    );
+    assert_eq!(stub.hits("/oembed"), 1);
+}
+
+#[tokio::test]
+async fn page_metadata_without_its_oembed_endpoint_comes_from_the_page() {
+    let stub = StubServer::start().await;
+    stub.route("/watch", StubResponse::html(VIDEO_PAGE));
+
+    let response = get(&format!("/api/page_metadata/{}", stub.url("/watch"))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let suggestion = response.json();
+    assert_eq!(suggestion["title"], "Arthur Penn on Bonnie and Clyde");
+    assert_eq!(suggestion["authors"], serde_json::json!([]));
+    assert_eq!(
+        suggestion["entry_type"],
+        serde_json::json!({ "Video": { "length_in_seconds": 0 } })
+    );
+}
+
+#[tokio::test]
+async fn page_metadata_of_non_html_resource_is_not_found() {
+    let stub = StubServer::start().await;
+    stub.route(
+        "/paper.pdf",
+        StubResponse::ok("application/pdf", "%PDF-1.4"),
+    );
+
+    let response = get(&format!("/api/page_metadata/{}", stub.url("/paper.pdf"))).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = get(&format!("/api/page_metadata/{}", stub.url("/missing"))).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn page_metadata_of_a_pdf() {
+    let stub = StubServer::start().await;
+    stub.route(
+        "/frontier.pdf",
+        StubResponse::ok("application/pdf", PDF_PAPER.to_vec()),
+    );
+
+    let response = get(&format!("/api/page_metadata/{}", stub.url("/frontier.pdf"))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let suggestion = response.json();
+    assert_eq!(
+        suggestion["authors"],
+        serde_json::json!(["Pauline Kael", "Arthur Penn"])
+    );
+    assert_eq!(
+        suggestion["entry_type"],
+        serde_json::json!({ "Paper": { "pages": 3 } })
+    );
+    assert_eq!(suggestion["provenance"]["title"]["source"], "pdf");
+}
+
+#[tokio::test]
+async fn page_metadata_of_a_page_that_declares_its_charset() {
+    let stub = StubServer::start().await;
+    let mut page = b"<html><head><meta charset=\"Shift_JIS\"><title>".to_vec();
+    page.extend(b"\x89\xb4\x82\xbd\x82\xbf\x82\xc9\x96\xbe\x93\xfa\x82\xcd\x82\xc8\x82\xa2");
+    page.extend(b"</title></head></html>");
+    stub.route("/bonnie", StubResponse::ok("text/html", page));
+
+    let response = get(&format!("/api/page_metadata/{}", stub.url("/bonnie"))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["title"], "俺たちに明日はない");
+}
@@ -0,0 +1,201 @@
+// Tests that drive whole request/response cycles through `process_request` against an in-memory database.
+
+mod backups;
+mod bulk;
+mod duplicates;
+mod entries;
+mod entry_routes;
+mod from_url;
+mod history;
+mod identifiers;
+mod images;
+mod meta_headers;
+mod openapi;
+mod patch;
+mod search;
+mod sites;
+mod stub_server;
+mod trash;
+mod validation;
+
+use crate::state::global_state;
+
+use hyper::body::Bytes;
+use hyper::{Body, HeaderMap, Method, Request, StatusCode};
+use tokio::sync::{Mutex, MutexGuard};
+
+// The database lives in global state, so tests that use it can't run in parallel.
+static DATABASE_LOCK: Mutex<()> = Mutex::const_new(());
+
+pub struct TestDatabase {
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl TestDatabase {
+    pub async fn new() -> TestDatabase {
+        let guard = DATABASE_LOCK.lock().await;
+
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        archive::database::create_tables(&connection).unwrap();
+        {
+            let mut state = global_state().lock().unwrap();
+            state.database = Some(connection);
+            state.resolvers = None;
+        }
+
+        TestDatabase { _guard: guard }
+    }
+
+    pub fn execute(&self, sql: &str) {
+        let state = global_state().lock().unwrap();
+        state.database.as_ref().unwrap().execute(sql, []).unwrap();
+    }
+
+    // Makes every query on entries fail from now on.
+    pub fn break_entries_table(&self) {
+        self.execute("DROP TABLE entries");
+    }
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl TestResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(|value| value.to_str().unwrap())
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+pub async fn request_with_headers(
+    method: Method,
+    uri: &str,
+    headers: &[(&str, &str)],
+    body: impl Into<Body>,
+) -> TestResponse {
+    let mut builder = Request::builder().method(method).uri(uri);
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+
+    let response = crate::process_request(builder.body(body.into()).unwrap())
+        .await
+        .unwrap();
+    let (parts, body) = response.into_parts();
+
+    TestResponse {
+        status: parts.status,
+        headers: parts.headers,
+        body: hyper::body::to_bytes(body).await.unwrap(),
+    }
+}
+
+pub async fn request(method: Method, uri: &str, body: impl Into<Body>) -> TestResponse {
+    request_with_headers(method, uri, &[], body).await
+}
+
+pub async fn get(uri: &str) -> TestResponse {
+    request(Method::GET, uri, "").await
+}
+
+pub async fn head(uri: &str) -> TestResponse {
+    request(Method::HEAD, uri, "").await
+}
+
+pub async fn delete(uri: &str) -> TestResponse {
+    request(Method::DELETE, uri, "").await
+}
+
+pub async fn put_json(uri: &str, json: serde_json::Value) -> TestResponse {
+    request_with_headers(
+        Method::PUT,
+        uri,
+        &[("Content-Type", "application/json")],
+        json.to_string(),
+    )
+    .await
+}
+
+pub async fn post_json(uri: &str, json: serde_json::Value) -> TestResponse {
+    request_with_headers(
+        Method::POST,
+        uri,
+        &[("Content-Type", "application/json")],
+        json.to_string(),
+    )
+    .await
+}
+
+pub fn new_entry_form(title: &str) -> serde_json::Value {
+    serde_json::json!({
+        "link": format!("https://example.com/{}", title.to_lowercase().replace(' ', "-")),
+        "title": title,
+        "description": "",
+        "authors": ["Pauline Kael"],
+        "category": "Cinema",
+        "themes": ["Criticism"],
+        "works_mentioned": [],
+        "tags": [],
+        "date_published": { "day": 3, "month": "May", "year": 1967 },
+        "exceptional": false,
+        "entry_type": { "Article": { "words": 1000 } }
+    })
+}
+
+pub fn entry_form_with(title: &str, fields: serde_json::Value) -> serde_json::Value {
+    let mut form = new_entry_form(title);
+    for (key, value) in fields.as_object().unwrap() {
+        form[key] = value.clone();
+    }
+    form
+}
+
+// Creates an entry and returns its id.
+pub async fn create_entry(title: &str) -> i64 {
+    let response = post_json("/api/texts", new_entry_form(title)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    response.json()["id"].as_i64().unwrap()
+}
+
+pub async fn create_entry_with(title: &str, fields: serde_json::Value) -> i64 {
+    let response = post_json("/api/texts", entry_form_with(title, fields)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    response.json()["id"].as_i64().unwrap()
+}
+
+pub async fn put_backup(id: i64, content_type: &str, body: impl Into<Body>) -> TestResponse {
+    request_with_headers(
+        Method::PUT,
+        &format!("/api/texts/{}/backup", id),
+        &[("Content-Type", content_type)],
+        body,
+    )
+    .await
+}
+
+// A paper of three pages, by Pauline Kael and Arthur Penn, with a title and a creation date.
+pub const PDF_PAPER: &[u8] =
+    include_bytes!("../../archive/fixtures/pdf/the-frontier-of-movies.pdf");
+
+// A song of 165 seconds by Flatt & Scruggs, with ID3 tags.
+pub const MP3_SONG: &[u8] =
+    include_bytes!("../../archive/fixtures/media/foggy-mountain-breakdown.mp3");
+
+// A png image of the given size, filled with a single color.
+pub fn png_image(width: u32, height: u32) -> Vec<u8> {
+    let image = image::RgbaImage::from_pixel(width, height, image::Rgba([200, 30, 30, 255]));
+    let mut bytes = Vec::new();
+    image
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageOutputFormat::Png,
+        )
+        .unwrap();
+    bytes
+}
@@ -0,0 +1,212 @@
+use super::stub_server::{StubResponse, StubServer};
+use super::*;
+use crate::openapi::ApiDoc;
+use crate::paths;
+
+use utoipa::openapi::PathItem;
+use utoipa::OpenApi;
+
+const SPEC_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+// The committed spec is what clients are written against. If this fails after changing a route or one of the
+// types in the API on purpose, regenerate the spec with `UPDATE_OPENAPI=1 cargo test openapi` and commit it.
+#[test]
+fn openapi_spec_is_up_to_date() {
+    let generated = ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+
+    if std::env::var_os("UPDATE_OPENAPI").is_some() {
+        std::fs::write(SPEC_PATH, &generated).unwrap();
+    }
+
+    let committed = std::fs::read_to_string(SPEC_PATH).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "openapi.json is out of date. Run `UPDATE_OPENAPI=1 cargo test openapi` to regenerate it."
+    );
+}
+
+#[tokio::test]
+async fn openapi_spec_is_served() {
+    let response = get("/api/openapi.json").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("Content-Type"), Some("application/json"));
+    assert_eq!(
+        response.json(),
+        serde_json::to_value(ApiDoc::openapi()).unwrap()
+    );
+}
+
+// Every operation in the spec must reach its handler. Requests are made against an entry that has an image, a
+// backup, a revision and a site with a favicon, or one in the trash for the trash routes, so that the only way of getting a 404 is the
+// route not existing.
+#[tokio::test]
+async fn every_operation_in_the_spec_is_routed() {
+    let stub = StubServer::start().await;
+    stub.route("/page", StubResponse::html("<html></html>"));
+
+    let spec = ApiDoc::openapi();
+    assert!(!spec.paths.paths.is_empty());
+
+    for (path, item) in &spec.paths.paths {
+        let operations = [
+            (Method::GET, &item.get),
+            (Method::PUT, &item.put),
+            (Method::PATCH, &item.patch),
+            (Method::POST, &item.post),
+            (Method::DELETE, &item.delete),
+        ];
+
+        for (method, operation) in operations {
+            if operation.is_none() {
+                continue;
+            }
+
+            let database = TestDatabase::new().await;
+            let id = create_entry("Bonnie and Clyde").await;
+            database.execute("INSERT INTO sites (domain, favicon) VALUES ('example.com', x'00')");
+            request_with_headers(
+                Method::PUT,
+                &format!("/api/texts/{}/image", id),
+                &[("Content-Type", "image/png")],
+                png_image(300, 169),
+            )
+            .await;
+            put_backup(id, "text/plain", "Hello").await;
+
+            let revision =
+                get(&format!("/api/texts/{}/history", id)).await.json()[0]["revision"].to_string();
+
+            let trashed = create_entry("The Godfather").await;
+            delete(&format!("/api/texts/{}", trashed)).await;
+
+            let uri = path
+                .replace(
+                    "{id}",
+                    &if path.starts_with("/api/trash/") {
+                        trashed
+                    } else {
+                        id
+                    }
+                    .to_string(),
+                )
+                .replace("{revision}", &revision)
+                .replace("{domain}", "example.com")
+                .replace("{url}", &stub.url("/page"));
+            let response = request(method.clone(), &uri, "").await;
+            assert_ne!(
+                response.status,
+                StatusCode::NOT_FOUND,
+                "{} {} is in the spec but not routed",
+                method,
+                path
+            );
+        }
+    }
+}
+
+// How an arm of route_request matches the path of a request.
+enum RoutedPath {
+    Exactly(String),
+    StartingWith(String),
+    Guard(fn(&str) -> bool),
+}
+
+impl RoutedPath {
+    fn matches(&self, path: &str) -> bool {
+        match self {
+            RoutedPath::Exactly(routed) => path == routed,
+            RoutedPath::StartingWith(prefix) => path.starts_with(prefix.as_str()),
+            RoutedPath::Guard(guard) => guard(path),
+        }
+    }
+}
+
+fn path_guard(name: &str) -> fn(&str) -> bool {
+    match name {
+        "is_single_entry_path" => paths::is_single_entry_path,
+        "is_entry_image_path" => paths::is_entry_image_path,
+        "is_entry_backup_path" => paths::is_entry_backup_path,
+        "is_entry_history_path" => paths::is_entry_history_path,
+        "is_revision_restore_path" => paths::is_revision_restore_path,
+        "is_entry_backup_metadata_path" => paths::is_entry_backup_metadata_path,
+        "is_trash_entry_path" => paths::is_trash_entry_path,
+        "is_trash_restore_path" => paths::is_trash_restore_path,
+        "is_site_favicon_path" => paths::is_site_favicon_path,
+        other => panic!(
+            "paths::{} is used in route_request, add it to path_guard",
+            other
+        ),
+    }
+}
+
+// The method and path of every arm of route_request under /api/, read from its source, so that a route added there
+// is found without being listed anywhere else.
+fn api_routes() -> Vec<(String, RoutedPath, &'static str)> {
+    let source = include_str!("../main.rs");
+    let start = source.find("async fn route_request(").unwrap();
+    let body = &source[start..start + source[start..].find("\n}\n").unwrap()];
+
+    let mut routes = Vec::new();
+    for line in body.lines().map(str::trim) {
+        let Some(arm) = line.strip_prefix("(&Method::") else {
+            continue;
+        };
+        let (method, rest) = arm.split_once(", ").unwrap();
+        let (path, rest) = rest.split_once(')').unwrap();
+        let guard = rest
+            .strip_prefix(" if ")
+            .map(|rest| rest.split(" =>").next().unwrap());
+
+        let routed = match (path, guard) {
+            (literal, None) if literal.starts_with('"') => {
+                RoutedPath::Exactly(literal.trim_matches('"').to_owned())
+            }
+            ("path", Some(guard)) if guard.starts_with("path.starts_with(\"") => {
+                RoutedPath::StartingWith(guard.split('"').nth(1).unwrap().to_owned())
+            }
+            ("path", Some(guard)) if guard.starts_with("paths::") => RoutedPath::Guard(path_guard(
+                guard["paths::".len()..].split('(').next().unwrap(),
+            )),
+            // Pages, their files and the answer to preflight requests, which are not part of the api.
+            _ => continue,
+        };
+        if let RoutedPath::Exactly(path) | RoutedPath::StartingWith(path) = &routed {
+            if !path.starts_with("/api/") {
+                continue;
+            }
+        }
+        routes.push((method.to_owned(), routed, line));
+    }
+    routes
+}
+
+fn has_operation(item: &PathItem, method: &str) -> bool {
+    match method {
+        "GET" => item.get.is_some(),
+        "PUT" => item.put.is_some(),
+        "PATCH" => item.patch.is_some(),
+        "POST" => item.post.is_some(),
+        "DELETE" => item.delete.is_some(),
+        other => panic!("{} is not a method of the api", other),
+    }
+}
+
+// The other way around: every route of the api must be in the spec, matched by a path of it with its parameters filled.
+#[test]
+fn every_route_is_in_the_spec() {
+    let spec = ApiDoc::openapi();
+    let routes = api_routes();
+    assert!(routes.len() > 30, "route_request could not be read");
+
+    for (method, routed, arm) in routes {
+        let documented = spec.paths.paths.iter().any(|(path, item)| {
+            let example = path
+                .replace("{id}", "1")
+                .replace("{revision}", "2")
+                .replace("{domain}", "example.com")
+                .replace("{url}", "https://example.com/page");
+            routed.matches(&example) && has_operation(item, &method)
+        });
+        assert!(documented, "{} is routed but not in the spec", arm);
+    }
+}
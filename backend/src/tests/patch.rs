@@ -0,0 +1,224 @@
+use super::*;
+
+async fn patch(path: &str, if_match: Option<&str>, patch: serde_json::Value) -> TestResponse {
+    let mut headers = vec![("Content-Type", "application/merge-patch+json")];
+    if let Some(etag) = if_match {
+        headers.push(("If-Match", etag));
+    }
+    request_with_headers(Method::PATCH, path, &headers, patch.to_string()).await
+}
+
+#[tokio::test]
+async fn patch_changes_only_the_given_fields() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry_with(
+        "Bonnie and Clyde",
+        serde_json::json!({ "tags": ["Classic"] }),
+    )
+    .await;
+    let path = format!("/api/texts/{}", id);
+
+    let response = patch(&path, None, serde_json::json!({ "exceptional": true })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let entry = response.json();
+    assert_eq!(entry["exceptional"], true);
+    assert_eq!(entry["title"], "Bonnie and Clyde");
+    assert_eq!(entry["tags"], serde_json::json!(["Classic"]));
+    assert_eq!(get(&path).await.json(), entry);
+}
+
+#[tokio::test]
+async fn patch_replaces_lists_and_entry_type() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+
+    let entry = patch(
+        &path,
+        None,
+        serde_json::json!({
+            "themes": ["Criticism", "New Hollywood"],
+            "entry_type": { "Book": { "pages": 300 } }
+        }),
+    )
+    .await
+    .json();
+    assert_eq!(
+        entry["themes"],
+        serde_json::json!(["Criticism", "New Hollywood"])
+    );
+    assert_eq!(
+        entry["entry_type"],
+        serde_json::json!({ "Book": { "pages": 300 } })
+    );
+}
+
+#[tokio::test]
+async fn patch_adds_new_values_to_the_taxonomy_tables() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+
+    patch(
+        &format!("/api/texts/{}", id),
+        None,
+        serde_json::json!({ "tags": ["Classic"] }),
+    )
+    .await;
+
+    assert_eq!(
+        get("/api/tags").await.json(),
+        serde_json::json!([{ "value": "Classic", "category": "Cinema" }])
+    );
+}
+
+#[tokio::test]
+async fn patch_is_recorded_in_the_history() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+
+    patch(
+        &format!("/api/texts/{}", id),
+        None,
+        serde_json::json!({ "exceptional": true }),
+    )
+    .await;
+
+    let history = get(&format!("/api/texts/{}/history", id)).await.json();
+    assert_eq!(
+        history[1]["changes"],
+        serde_json::json!([{ "field": "exceptional", "old": false, "new": true }])
+    );
+}
+
+#[tokio::test]
+async fn invalid_patches_are_bad_requests() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+
+    for invalid in [
+        serde_json::json!({ "rating": 5 }),
+        serde_json::json!({ "exceptional": "yes" }),
+        serde_json::json!({ "title": null }),
+        serde_json::json!({ "entry_type": { "Podcast": {} } }),
+        serde_json::json!(["exceptional"]),
+    ] {
+        let response = patch(&path, None, invalid.clone()).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", invalid);
+        assert!(response.json()["error_message"].is_string());
+    }
+
+    let response = request(Method::PATCH, &path, "{").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    assert_eq!(get(&path).await.json()["title"], "Bonnie and Clyde");
+}
+
+#[tokio::test]
+async fn patch_of_missing_entry_is_not_found() {
+    let _database = TestDatabase::new().await;
+    let response = patch(
+        "/api/texts/1",
+        None,
+        serde_json::json!({ "exceptional": true }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn every_change_gives_the_entry_a_new_etag() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+
+    let created = get(&path).await;
+    let etag = created.header("ETag").unwrap().to_owned();
+    assert_eq!(etag, format!("\"{}\"", created.json()["version"]));
+
+    put_json(&path, new_entry_form("Bonnie and Clyde")).await;
+    let replaced = get(&path).await.header("ETag").unwrap().to_owned();
+    assert_ne!(replaced, etag);
+
+    put_backup(id, "text/plain", "Hello").await;
+    assert_ne!(get(&path).await.header("ETag").unwrap(), replaced);
+}
+
+#[tokio::test]
+async fn patch_with_current_etag_succeeds() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+    let etag = get(&path).await.header("ETag").unwrap().to_owned();
+
+    let response = patch(
+        &path,
+        Some(&etag),
+        serde_json::json!({ "exceptional": true }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_ne!(response.header("ETag").unwrap(), etag);
+
+    let version = response.json()["version"].to_string();
+    let response = patch(
+        &path,
+        Some(&version),
+        serde_json::json!({ "exceptional": false }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = patch(&path, Some("*"), serde_json::json!({ "exceptional": true })).await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn patch_with_stale_etag_is_precondition_failed() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+    let etag = get(&path).await.header("ETag").unwrap().to_owned();
+
+    // Another tab changes the entry first.
+    patch(
+        &path,
+        Some(&etag),
+        serde_json::json!({ "title": "Bonnie & Clyde" }),
+    )
+    .await;
+
+    let response = patch(
+        &path,
+        Some(&etag),
+        serde_json::json!({ "exceptional": true }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(get(&path).await.json()["exceptional"], false);
+
+    let response = patch(
+        &path,
+        Some("\"abc\""),
+        serde_json::json!({ "exceptional": true }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::PRECONDITION_FAILED);
+}
+
+#[tokio::test]
+async fn patch_with_weak_etag_is_precondition_failed() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+    let etag = get(&path).await.header("ETag").unwrap().to_owned();
+
+    let response = patch(
+        &path,
+        Some(&format!("W/{}", etag)),
+        serde_json::json!({ "exceptional": true }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(get(&path).await.json()["exceptional"], false);
+}
@@ -0,0 +1,225 @@
+use super::*;
+
+fn titles(response: &TestResponse) -> Vec<String> {
+    let mut titles: Vec<String> = response.json()["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| String::from(entry["title"].as_str().unwrap()))
+        .collect();
+    titles.sort();
+    titles
+}
+
+async fn create_search_fixture() {
+    create_entry_with(
+        "Bonnie and Clyde",
+        serde_json::json!({
+            "link": "https://www.newyorker.com/bonnie-and-clyde",
+            "description": "The frontier of movies",
+            "themes": ["Criticism", "New Hollywood"],
+            "date_published": { "day": 21, "month": "October", "year": 1967 },
+            "exceptional": true,
+        }),
+    )
+    .await;
+    create_entry_with(
+        "Dark Souls retrospective",
+        serde_json::json!({
+            "authors": ["Someone Else"],
+            "category": "Videogames",
+            "themes": ["Criticism"],
+            "tags": ["Soulslike", "Great soundtrack"],
+            "date_published": { "day": 1, "month": "June", "year": 2016 },
+            "entry_type": { "Video": { "length_in_seconds": 3600 } },
+        }),
+    )
+    .await;
+    create_entry_with(
+        "Writing a compiler",
+        serde_json::json!({
+            "authors": ["Someone Else"],
+            "category": "Programming",
+            "themes": [],
+            "description": "Parsing and code generation",
+            "date_published": { "day": 5, "month": "March", "year": 2020 },
+            "entry_type": { "Book": { "pages": 400 } },
+        }),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn search_by_text_fields() {
+    let _database = TestDatabase::new().await;
+    create_search_fixture().await;
+
+    assert_eq!(
+        titles(&get("/api/texts?title=souls").await),
+        ["Dark Souls retrospective"]
+    );
+    assert_eq!(
+        titles(&get("/api/texts?link=newyorker").await),
+        ["Bonnie and Clyde"]
+    );
+    assert_eq!(
+        titles(&get("/api/texts?description=code%20generation").await),
+        ["Writing a compiler"]
+    );
+    assert_eq!(
+        titles(&get("/api/texts?author=Pauline%20Kael").await),
+        ["Bonnie and Clyde"]
+    );
+}
+
+#[tokio::test]
+async fn search_by_category_type_and_exceptional() {
+    let _database = TestDatabase::new().await;
+    create_search_fixture().await;
+
+    assert_eq!(
+        titles(&get("/api/texts?category=Programming").await),
+        ["Writing a compiler"]
+    );
+    assert_eq!(
+        titles(&get("/api/texts?type=video").await),
+        ["Dark Souls retrospective"]
+    );
+    assert_eq!(
+        titles(&get("/api/texts?exceptional=true").await),
+        ["Bonnie and Clyde"]
+    );
+    assert_eq!(
+        titles(&get("/api/texts?exceptional=false").await),
+        ["Dark Souls retrospective", "Writing a compiler"]
+    );
+}
+
+#[tokio::test]
+async fn search_by_lists_requires_every_value() {
+    let _database = TestDatabase::new().await;
+    create_search_fixture().await;
+
+    assert_eq!(
+        titles(&get("/api/texts?themes=Criticism").await),
+        ["Bonnie and Clyde", "Dark Souls retrospective"]
+    );
+    assert_eq!(
+        titles(&get("/api/texts?themes=Criticism%7CNew%20Hollywood").await),
+        ["Bonnie and Clyde"]
+    );
+    assert_eq!(
+        titles(&get("/api/texts?tags=Soulslike%7CGreat%20soundtrack").await),
+        ["Dark Souls retrospective"]
+    );
+}
+
+#[tokio::test]
+async fn search_by_date_ranges() {
+    let _database = TestDatabase::new().await;
+    create_search_fixture().await;
+
+    assert_eq!(
+        titles(&get("/api/texts?published_between_from=2000-1-1").await),
+        ["Dark Souls retrospective", "Writing a compiler"]
+    );
+    assert_eq!(
+        titles(
+            &get("/api/texts?published_between_from=2000-1-1&published_between_until=2019-12-31")
+                .await
+        ),
+        ["Dark Souls retrospective"]
+    );
+    assert_eq!(
+        titles(&get("/api/texts?saved_between_until=1999-1-1").await),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        titles(&get("/api/texts?modified_between_until=1999-1-1").await),
+        Vec::<String>::new()
+    );
+}
+
+#[tokio::test]
+async fn search_by_date_saved_and_date_modified() {
+    let _database = TestDatabase::new().await;
+    create_entry_with(
+        "Bonnie and Clyde",
+        serde_json::json!({ "date_saved": { "day": 12, "month": "August", "year": 2003 } }),
+    )
+    .await;
+    create_entry("The Godfather").await;
+
+    assert_eq!(
+        titles(&get("/api/texts?saved_between_until=2010-1-1").await),
+        ["Bonnie and Clyde"]
+    );
+    assert_eq!(
+        titles(&get("/api/texts?saved_between_from=2010-1-1").await),
+        ["The Godfather"]
+    );
+    assert_eq!(
+        titles(&get("/api/texts?modified_between_from=2010-1-1").await),
+        ["Bonnie and Clyde", "The Godfather"]
+    );
+}
+
+#[tokio::test]
+async fn search_combines_predicates() {
+    let _database = TestDatabase::new().await;
+    create_search_fixture().await;
+
+    assert_eq!(
+        titles(&get("/api/texts?author=Someone%20Else&themes=Criticism").await),
+        ["Dark Souls retrospective"]
+    );
+}
+
+#[tokio::test]
+async fn search_is_paginated_in_pages_of_ten() {
+    let _database = TestDatabase::new().await;
+    for i in 0..12 {
+        create_entry(&format!("Review {}", i)).await;
+    }
+
+    let first_page = get("/api/texts?category=Cinema").await.json();
+    assert_eq!(first_page["entries"].as_array().unwrap().len(), 10);
+    assert_eq!(first_page["current_offset"], 0);
+    assert_eq!(first_page["next_offset"], 10);
+    assert_eq!(first_page["total_size"], 12);
+
+    let second_page = get("/api/texts?category=Cinema&offset=10").await.json();
+    assert_eq!(second_page["entries"].as_array().unwrap().len(), 2);
+    assert_eq!(second_page["current_offset"], 10);
+    assert_eq!(second_page["next_offset"], 12);
+    assert_eq!(second_page["total_size"], 12);
+}
+
+#[tokio::test]
+async fn empty_search_is_random_but_stable_for_a_seed() {
+    let _database = TestDatabase::new().await;
+    for i in 0..15 {
+        create_entry(&format!("Review {}", i)).await;
+    }
+
+    let first = get("/api/texts?seed=42").await.json();
+    let again = get("/api/texts?seed=42").await.json();
+    assert_eq!(first, again);
+    assert_eq!(first["seed"], 42);
+    assert_eq!(first["total_size"], 15);
+    assert_eq!(first["entries"].as_array().unwrap().len(), 10);
+
+    let rest = get("/api/texts?seed=42&offset=10").await.json();
+    assert_eq!(rest["entries"].as_array().unwrap().len(), 5);
+
+    // Both pages together contain every entry exactly once.
+    let mut ids: Vec<i64> = first["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .chain(rest["entries"].as_array().unwrap())
+        .map(|entry| entry["id"].as_i64().unwrap())
+        .collect();
+    ids.sort();
+    assert_eq!(ids, (1..=15).collect::<Vec<i64>>());
+}
@@ -0,0 +1,218 @@
+use super::stub_server::{StubResponse, StubServer};
+use super::*;
+
+use archive::links::canonical_domain;
+use serde_json::json;
+
+const ARTICLE: &str = r#"
+    <html><head>
+    <meta property="og:title" content="Bonnie and Clyde">
+    <meta property="og:site_name" content="The New Yorker">
+    <link rel="icon" href="/icons/favicon-64.png">
+    </head><body>How do you make a good movie in this country without being jumped on?</body></html>
+"#;
+
+async fn post_from_url(url: &str) -> TestResponse {
+    post_json(
+        "/api/texts/from_url",
+        json!({
+            "url": url,
+            "overrides": {
+                "category": "Cinema",
+                "date_published": { "day": 21, "month": "October", "year": 1967 },
+                "entry_type": { "Article": { "words": 1000 } }
+            }
+        }),
+    )
+    .await
+}
+
+fn titles(response: TestResponse) -> Vec<String> {
+    response.json()["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| String::from(entry["title"].as_str().unwrap()))
+        .collect()
+}
+
+#[tokio::test]
+async fn site_is_captured_from_the_first_page_of_it() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route("/bonnie-and-clyde", StubResponse::html(ARTICLE));
+    stub.route(
+        "/the-frontier-of-movies",
+        StubResponse::html(&ARTICLE.replace("The New Yorker", "Another name")),
+    );
+    stub.route(
+        "/icons/favicon-64.png",
+        StubResponse::ok("image/png", png_image(64, 48)),
+    );
+    let domain = canonical_domain(&stub.url("/")).unwrap();
+
+    let response = post_from_url(&stub.url("/bonnie-and-clyde")).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let entry = get(response.json()["link"].as_str().unwrap()).await.json();
+    let favicon_link = format!("/api/sites/{}/favicon", domain);
+    assert_eq!(
+        entry["site"],
+        json!({ "domain": domain, "name": "The New Yorker", "favicon": favicon_link })
+    );
+
+    let favicon = get(&favicon_link).await;
+    assert_eq!(favicon.status, StatusCode::OK);
+    assert_eq!(favicon.header("Content-Type"), Some("image/png"));
+    let image = image::load_from_memory(&favicon.body).unwrap();
+    assert_eq!((image.width(), image.height()), (32, 32));
+
+    // The site is kept as it was first captured, without fetching its favicon again.
+    let response = post_from_url(&stub.url("/the-frontier-of-movies")).await;
+    let entry = get(response.json()["link"].as_str().unwrap()).await.json();
+    assert_eq!(entry["site"]["name"], "The New Yorker");
+    assert_eq!(stub.hits("/icons/favicon-64.png"), 1);
+}
+
+#[tokio::test]
+async fn site_without_favicon_is_captured_without_one() {
+    let _database = TestDatabase::new().await;
+    let stub = StubServer::start().await;
+    stub.route(
+        "/bonnie-and-clyde",
+        StubResponse::html("<title>Bonnie and Clyde</title>"),
+    );
+    stub.route(
+        "/the-frontier-of-movies",
+        StubResponse::html("<title>The Frontier of Movies</title>"),
+    );
+    let domain = canonical_domain(&stub.url("/")).unwrap();
+
+    post_from_url(&stub.url("/bonnie-and-clyde")).await;
+    let response = post_from_url(&stub.url("/the-frontier-of-movies")).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let entry = get(response.json()["link"].as_str().unwrap()).await.json();
+    assert_eq!(
+        entry["site"],
+        json!({ "domain": domain, "name": null, "favicon": null })
+    );
+    assert_eq!(stub.hits("/favicon.ico"), 1);
+    assert_eq!(
+        get(&format!("/api/sites/{}/favicon", domain)).await.status,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn site_of_an_entry_follows_its_link() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+    assert_eq!(
+        get(&path).await.json()["site"],
+        json!({ "domain": "example.com", "name": null, "favicon": null })
+    );
+
+    let response = put_json(
+        &path,
+        entry_form_with(
+            "Bonnie and Clyde",
+            json!({ "link": "https://www.NewYorker.com/magazine/bonnie-and-clyde" }),
+        ),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert_eq!(get(&path).await.json()["site"]["domain"], "newyorker.com");
+}
+
+#[tokio::test]
+async fn entries_are_found_by_their_site() {
+    let database = TestDatabase::new().await;
+    create_entry("Bonnie and Clyde").await;
+    create_entry_with(
+        "The Frontier of Movies",
+        json!({ "link": "https://www.newyorker.com/magazine/the-frontier-of-movies" }),
+    )
+    .await;
+    database.execute(
+        "INSERT INTO sites (domain, site_name) VALUES ('newyorker.com', 'The New Yorker')",
+    );
+
+    assert_eq!(
+        titles(get("/api/texts?site=NewYorker.com").await),
+        ["The Frontier of Movies"]
+    );
+    assert_eq!(
+        titles(get("/api/texts?site=https%3A%2F%2Fwww.example.com%2Fabout").await),
+        ["Bonnie and Clyde"]
+    );
+    assert_eq!(
+        titles(get("/api/texts?site=the%20new%20yorker").await),
+        ["The Frontier of Movies"]
+    );
+    assert!(titles(get("/api/texts?site=elpais.com").await).is_empty());
+}
+
+#[tokio::test]
+async fn sites_are_listed_with_their_number_of_entries() {
+    let database = TestDatabase::new().await;
+    create_entry("Bonnie and Clyde").await;
+    create_entry("The Godfather").await;
+    create_entry_with(
+        "The Frontier of Movies",
+        json!({ "link": "https://www.newyorker.com/magazine/the-frontier-of-movies" }),
+    )
+    .await;
+    let deleted = create_entry_with(
+        "Crítica: Bonnie y Clyde",
+        json!({ "link": "https://elpais.com/cultura/bonnie-y-clyde.html" }),
+    )
+    .await;
+    delete(&format!("/api/texts/{}", deleted)).await;
+    database.execute("INSERT INTO sites (domain, site_name, favicon) VALUES ('newyorker.com', 'The New Yorker', x'00')");
+
+    let response = get("/api/sites").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.json(),
+        json!([
+            { "domain": "example.com", "name": null, "favicon": null, "entries": 2 },
+            {
+                "domain": "newyorker.com",
+                "name": "The New Yorker",
+                "favicon": "/api/sites/newyorker.com/favicon",
+                "entries": 1
+            },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn entries_saved_without_fetching_their_link_are_listed_in_their_site() {
+    let _database = TestDatabase::new().await;
+    let response = post_json(
+        "/api/texts",
+        entry_form_with(
+            "The Frontier of Movies",
+            json!({ "link": "https://www.newyorker.com/magazine/the-frontier-of-movies" }),
+        ),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let id = create_entry("Bonnie and Clyde").await;
+    let response = request_with_headers(
+        Method::PATCH,
+        &format!("/api/texts/{}", id),
+        &[("Content-Type", "application/merge-patch+json")],
+        json!({ "link": "https://elpais.com/cultura/bonnie-y-clyde.html" }).to_string(),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    assert_eq!(
+        get("/api/sites").await.json(),
+        json!([
+            { "domain": "elpais.com", "name": null, "favicon": null, "entries": 1 },
+            { "domain": "newyorker.com", "name": null, "favicon": null, "entries": 1 },
+        ])
+    );
+}
@@ -0,0 +1,114 @@
+// A local HTTP server that stands in for the websites the backend fetches pages, images and backups from.
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+#[derive(Clone)]
+pub struct StubResponse {
+    pub status: StatusCode,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl StubResponse {
+    pub fn ok(content_type: &str, body: impl Into<Vec<u8>>) -> StubResponse {
+        StubResponse {
+            status: StatusCode::OK,
+            headers: vec![(String::from("Content-Type"), String::from(content_type))],
+            body: body.into(),
+        }
+    }
+
+    pub fn html(body: &str) -> StubResponse {
+        StubResponse::ok("text/html; charset=utf-8", body)
+    }
+
+    pub fn with_status(status: StatusCode) -> StubResponse {
+        StubResponse {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Routes {
+    responses: HashMap<String, StubResponse>,
+    hits: HashMap<String, usize>,
+}
+
+pub struct StubServer {
+    address: SocketAddr,
+    routes: Arc<Mutex<Routes>>,
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl StubServer {
+    pub async fn start() -> StubServer {
+        let routes = Arc::new(Mutex::new(Routes::default()));
+
+        let service_routes = routes.clone();
+        let make_svc = make_service_fn(move |_conn| {
+            let routes = service_routes.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let routes = routes.clone();
+                    async move { Ok::<_, Infallible>(respond(&routes, req.uri().path())) }
+                }))
+            }
+        });
+
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let address = server.local_addr();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            _ = shutdown_receiver.await;
+        }));
+
+        StubServer {
+            address,
+            routes,
+            _shutdown: shutdown_sender,
+        }
+    }
+
+    pub fn route(&self, path: &str, response: StubResponse) {
+        self.routes
+            .lock()
+            .unwrap()
+            .responses
+            .insert(String::from(path), response);
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.address, path)
+    }
+
+    // Number of requests received at the path.
+    pub fn hits(&self, path: &str) -> usize {
+        *self.routes.lock().unwrap().hits.get(path).unwrap_or(&0)
+    }
+}
+
+fn respond(routes: &Mutex<Routes>, path: &str) -> Response<Body> {
+    let mut routes = routes.lock().unwrap();
+    *routes.hits.entry(String::from(path)).or_insert(0) += 1;
+
+    let stub = routes
+        .responses
+        .get(path)
+        .cloned()
+        .unwrap_or_else(|| StubResponse::with_status(StatusCode::NOT_FOUND));
+
+    let mut response = Response::builder().status(stub.status);
+    for (name, value) in &stub.headers {
+        response = response.header(name, value);
+    }
+    response.body(Body::from(stub.body)).unwrap()
+}
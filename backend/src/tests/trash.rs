@@ -0,0 +1,147 @@
+use super::*;
+
+fn titles(entries: &serde_json::Value) -> Vec<String> {
+    entries
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["title"].as_str().unwrap().to_owned())
+        .collect()
+}
+
+#[tokio::test]
+async fn deleted_entry_goes_to_the_trash() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    create_entry("The Godfather").await;
+
+    assert_eq!(
+        delete(&format!("/api/texts/{}", id)).await.status,
+        StatusCode::NO_CONTENT
+    );
+
+    let trash = get("/api/trash").await;
+    assert_eq!(trash.status, StatusCode::OK);
+    assert_eq!(titles(&trash.json()), ["Bonnie and Clyde"]);
+    assert_eq!(
+        trash.json()[0]["date_deleted"],
+        serde_json::to_value(archive::date::today()).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn entries_in_the_trash_are_hidden() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    put_backup(id, "text/plain", "Hello").await;
+    create_entry("The Godfather").await;
+    let path = format!("/api/texts/{}", id);
+    delete(&path).await;
+
+    assert_eq!(get(&path).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        get(&format!("{}/backup", path)).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        put_json(&path, new_entry_form("Bonnie and Clyde"))
+            .await
+            .status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(delete(&path).await.status, StatusCode::NOT_FOUND);
+
+    let search = get("/api/texts").await.json();
+    assert_eq!(search["total_size"], 1);
+    assert_eq!(titles(&search["entries"]), ["The Godfather"]);
+    let search = get("/api/texts?author=Pauline%20Kael").await.json();
+    assert_eq!(titles(&search["entries"]), ["The Godfather"]);
+}
+
+#[tokio::test]
+async fn restored_entry_is_back_with_its_backup() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    put_backup(id, "text/plain", "Hello").await;
+    let path = format!("/api/texts/{}", id);
+    delete(&path).await;
+
+    let response = request(Method::POST, &format!("/api/trash/{}/restore", id), "").await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    assert_eq!(get(&path).await.json()["title"], "Bonnie and Clyde");
+    assert_eq!(get(&format!("{}/backup", path)).await.body, "Hello");
+    assert_eq!(get("/api/trash").await.json(), serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn purged_entry_is_gone_but_keeps_its_history() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    delete(&format!("/api/texts/{}", id)).await;
+
+    let response = delete(&format!("/api/trash/{}", id)).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    assert_eq!(get("/api/trash").await.json(), serde_json::json!([]));
+    let restore = request(Method::POST, &format!("/api/trash/{}/restore", id), "").await;
+    assert_eq!(restore.status, StatusCode::NOT_FOUND);
+
+    let history = get(&format!("/api/texts/{}/history", id)).await.json();
+    assert_eq!(
+        history.as_array().unwrap().last().unwrap()["action"],
+        "purge"
+    );
+}
+
+#[tokio::test]
+async fn purged_entry_restored_from_its_history_gets_a_new_etag() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+    let etag = get(&path).await.header("ETag").unwrap().to_owned();
+    put_json(&path, new_entry_form("Bonnie & Clyde")).await;
+    delete(&path).await;
+    delete(&format!("/api/trash/{}", id)).await;
+
+    let created = &get(&format!("{}/history", path)).await.json()[0];
+    let response = request(
+        Method::POST,
+        &format!("{}/history/{}/restore", path, created["revision"]),
+        "",
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let restored = get(&path).await;
+    assert_eq!(restored.json()["title"], "Bonnie and Clyde");
+    assert_ne!(restored.header("ETag").unwrap(), etag);
+    let response = request_with_headers(
+        Method::PATCH,
+        &path,
+        &[
+            ("Content-Type", "application/merge-patch+json"),
+            ("If-Match", &etag),
+        ],
+        serde_json::json!({ "exceptional": true }).to_string(),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::PRECONDITION_FAILED);
+}
+
+#[tokio::test]
+async fn entries_outside_the_trash_can_not_be_restored_or_purged() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+
+    let restore = request(Method::POST, &format!("/api/trash/{}/restore", id), "").await;
+    assert_eq!(restore.status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        delete(&format!("/api/trash/{}", id)).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        get(&format!("/api/texts/{}", id)).await.status,
+        StatusCode::OK
+    );
+}
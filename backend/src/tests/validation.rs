@@ -0,0 +1,237 @@
+use super::*;
+
+use serde_json::json;
+
+fn invalid_fields(response: &TestResponse) -> Vec<String> {
+    response.json()["fields"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect()
+}
+
+#[tokio::test]
+async fn post_texts_reports_every_invalid_field() {
+    let _database = TestDatabase::new().await;
+
+    let response = post_json(
+        "/api/texts",
+        entry_form_with(
+            "Bonnie and Clyde",
+            json!({
+                "link": "not a link",
+                "title": "   ",
+                "category": "",
+                "tags": ["", "a|b"],
+                "date_published": { "day": 31, "month": "February", "year": 1967 },
+                "entry_type": { "Article": { "words": -5 } }
+            }),
+        ),
+    )
+    .await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        invalid_fields(&response),
+        [
+            "category",
+            "date_published",
+            "entry_type",
+            "link",
+            "tags",
+            "title"
+        ]
+    );
+    assert_eq!(
+        response.json()["fields"]["tags"].as_array().unwrap().len(),
+        2
+    );
+    assert!(response.json()["error_message"]
+        .as_str()
+        .unwrap()
+        .contains("title: Must not be empty"));
+    assert_eq!(get("/api/texts").await.json()["total_size"], 0);
+}
+
+#[tokio::test]
+async fn post_texts_accepts_leap_days() {
+    let _database = TestDatabase::new().await;
+    create_entry_with(
+        "Bonnie and Clyde",
+        json!({ "date_published": { "day": 29, "month": "February", "year": 1968 } }),
+    )
+    .await;
+
+    let response = post_json(
+        "/api/texts",
+        entry_form_with(
+            "The Godfather",
+            json!({ "date_published": { "day": 29, "month": "February", "year": 1967 } }),
+        ),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(invalid_fields(&response), ["date_published"]);
+}
+
+#[tokio::test]
+async fn texts_and_lists_are_normalized() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry_with(
+        "Bonnie and Clyde",
+        json!({
+            "title": "  Bonnie and Clyde \n",
+            "authors": ["Pauline Kael ", " Pauline Kael", "Roger Ebert"],
+            "tags": ["crime", "crime"]
+        }),
+    )
+    .await;
+
+    let entry = get(&format!("/api/texts/{}", id)).await.json();
+    assert_eq!(entry["title"], "Bonnie and Clyde");
+    assert_eq!(entry["authors"], json!(["Pauline Kael", "Roger Ebert"]));
+    assert_eq!(entry["tags"], json!(["crime"]));
+    let authors = get("/api/authors").await.json();
+    assert_eq!(authors.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn put_and_patch_reject_invalid_entries() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+
+    let response = put_json(
+        &path,
+        entry_form_with("Bonnie and Clyde", json!({ "link": "ftp://example.com" })),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(invalid_fields(&response), ["link"]);
+
+    let response = request_with_headers(
+        Method::PATCH,
+        &path,
+        &[("Content-Type", "application/merge-patch+json")],
+        json!({ "title": "", "entry_type": { "Book": { "pages": -1 } } }).to_string(),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(invalid_fields(&response), ["entry_type", "title"]);
+
+    let entry = get(&path).await.json();
+    assert_eq!(entry["title"], "Bonnie and Clyde");
+    assert_eq!(entry["version"], 1);
+}
+
+#[tokio::test]
+async fn entries_with_links_of_older_versions_can_still_be_edited() {
+    let database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+    let link = "file:///home/pauline/bonnie-and-clyde.pdf";
+    database.execute(&format!("UPDATE entries SET link = '{}'", link));
+
+    let response = put_json(
+        &path,
+        entry_form_with("Bonnie & Clyde", json!({ "link": link })),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let patch = |patch: serde_json::Value| {
+        request_with_headers(
+            Method::PATCH,
+            &path,
+            &[("Content-Type", "application/merge-patch+json")],
+            patch.to_string(),
+        )
+    };
+    assert_eq!(
+        patch(json!({ "exceptional": true })).await.status,
+        StatusCode::OK
+    );
+    let response = patch(json!({ "link": "ftp://example.com/bonnie-and-clyde.pdf" })).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(invalid_fields(&response), ["link"]);
+
+    let response = post_json(
+        "/api/texts/bulk",
+        json!({ "ids": [id], "operation": { "add_tags": ["Classic"] } }),
+    )
+    .await;
+    assert_eq!(response.json()["results"][0]["status"], "updated");
+
+    let entry = get(&path).await.json();
+    assert_eq!(entry["link"], link);
+    assert_eq!(entry["title"], "Bonnie & Clyde");
+    assert_eq!(entry["tags"], json!(["Classic"]));
+}
+
+#[tokio::test]
+async fn bulk_leaves_entries_it_would_make_invalid_as_they_are() {
+    let database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    database.execute("UPDATE entries SET title = ''");
+
+    let response = post_json(
+        "/api/texts/bulk",
+        json!({ "ids": [id], "operation": { "add_tags": ["Classic"] } }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let result = &response.json()["results"][0];
+    assert_eq!(result["status"], "failed");
+    assert_eq!(result["error"], "title: Must not be empty");
+    assert_eq!(
+        get(&format!("/api/texts/{}", id)).await.json()["tags"],
+        json!([])
+    );
+}
+
+#[tokio::test]
+async fn bulk_rejects_invalid_values() {
+    let _database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+
+    let response = post_json(
+        "/api/texts/bulk",
+        json!({ "ids": [id], "operation": { "add_tags": ["crime|drama"] } }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(invalid_fields(&response), ["tags"]);
+
+    let response = post_json(
+        "/api/texts/bulk",
+        json!({ "ids": [id], "operation": { "add_tags": [" crime ", "crime"] } }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        get(&format!("/api/texts/{}", id)).await.json()["tags"],
+        json!(["crime"])
+    );
+}
+
+#[tokio::test]
+async fn entries_with_invalid_dates_of_older_versions_are_repaired() {
+    let database = TestDatabase::new().await;
+    let id = create_entry("Bonnie and Clyde").await;
+    let path = format!("/api/texts/{}", id);
+    database.execute("UPDATE entries SET date_published = '2019-02-31'");
+
+    // The entry can't be read, but that doesn't take the server down with it.
+    assert_eq!(get(&path).await.status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(get("/api/sites").await.status, StatusCode::OK);
+
+    crate::state::with_database(archive::database::create_tables).unwrap();
+
+    let response = get(&path).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.json()["date_published"],
+        json!({ "day": 28, "month": "February", "year": 2019 })
+    );
+}